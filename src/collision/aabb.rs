@@ -1,5 +1,6 @@
 use std::f64;
 use std::result::Result;
+use collision::{RayCastInput, RayCastOutput};
 use common::Vec2d;
 
 /// Aabb contains the information for an axis aligned bounding box. 
@@ -25,6 +26,12 @@ impl Aabb {
         })
     }
 
+    /// Returns a new instance of an Aabb with the given lower left
+    /// and upper right corners
+    pub fn from_bounds(min: Vec2d, max: Vec2d) -> Aabb {
+        Aabb { min, max }
+    }
+
     /// Returns the `Vec2d` representing
     /// the lower left corner of this AABB
     pub fn min(&self) -> &Vec2d {
@@ -51,6 +58,105 @@ impl Aabb {
         }
         true
     }
+
+    /// Returns if this `Aabb` fully contains the passed in `Aabb`
+    pub fn contains(&self, rhs: &Aabb) -> bool {
+        self.min.x <= rhs.min.x && self.min.y <= rhs.min.y && rhs.max.x <= self.max.x &&
+        rhs.max.y <= self.max.y
+    }
+
+    /// Returns the smallest `Aabb` containing both this
+    /// `Aabb` and the passed in `Aabb`
+    pub fn combine(&self, rhs: &Aabb) -> Aabb {
+        Aabb {
+            min: Vec2d::new(self.min.x.min(rhs.min.x), self.min.y.min(rhs.min.y)),
+            max: Vec2d::new(self.max.x.max(rhs.max.x), self.max.y.max(rhs.max.y)),
+        }
+    }
+
+    /// Returns this `Aabb` grown by `margin` on every side
+    pub fn fatten(&self, margin: f64) -> Aabb {
+        let m = Vec2d::new(margin, margin);
+        Aabb {
+            min: self.min - m,
+            max: self.max + m,
+        }
+    }
+
+    /// Returns the center of this `Aabb`
+    pub fn center(&self) -> Vec2d {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Returns the perimeter of this `Aabb`
+    pub fn perimeter(&self) -> f64 {
+        2.0 * ((self.max.x - self.min.x) + (self.max.y - self.min.y))
+    }
+
+    /// Casts the ray against this `Aabb`, returning the first hit
+    /// along the ray or `None` if the ray misses. Rays starting
+    /// inside the `Aabb` hit at fraction 0
+    ///
+    /// # Remarks
+    ///
+    /// Uses the slab test: the ray is clipped against the pair of
+    /// parallel lines bounding each axis and hits if the clipped
+    /// intervals overlap
+    pub fn ray_cast(&self, input: &RayCastInput) -> Option<RayCastOutput> {
+        let p = input.p1;
+        let d = input.p2 - input.p1;
+        let p = [p.x, p.y];
+        let d = [d.x, d.y];
+        let min = [self.min.x, self.min.y];
+        let max = [self.max.x, self.max.y];
+
+        let mut tmin = -f64::MAX;
+        let mut tmax = f64::MAX;
+        let mut normal = Vec2d::zero();
+        for i in 0..2 {
+            if d[i].abs() < f64::EPSILON {
+                // parallel to the slab
+                if p[i] < min[i] || max[i] < p[i] {
+                    return None;
+                }
+                continue;
+            }
+
+            let inv = 1.0 / d[i];
+            let mut t1 = (min[i] - p[i]) * inv;
+            let mut t2 = (max[i] - p[i]) * inv;
+            let mut s = -1.0;
+            if t1 > t2 {
+                ::std::mem::swap(&mut t1, &mut t2);
+                s = 1.0;
+            }
+
+            if t1 > tmin {
+                normal = if i == 0 {
+                    Vec2d::new(s, 0.0)
+                } else {
+                    Vec2d::new(0.0, s)
+                };
+                tmin = t1;
+            }
+            tmax = tmax.min(t2);
+            if tmin > tmax {
+                return None;
+            }
+        }
+
+        if tmax < 0.0 || input.max_fraction < tmin {
+            return None;
+        }
+        if tmin < 0.0 {
+            return Some(input.inside());
+        }
+        Some(RayCastOutput {
+            fraction: tmin,
+            point: input.point_at(tmin),
+            normal,
+        })
+    }
 }
 
 // Returns bounding box information used for the creation of Aabbs from
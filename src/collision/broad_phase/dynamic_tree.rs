@@ -0,0 +1,548 @@
+use std::vec::Vec;
+use collision::Aabb;
+use collision::broad_phase::{self, BroadPhase, ProxyId};
+use common::Vec2d;
+
+/// The default margin proxies are fattened by so that
+/// small motions don't require the tree to be updated
+pub const DEFAULT_AABB_MARGIN: f64 = 0.1;
+
+/// Marks the absence of a node
+const NULL_NODE: usize = usize::MAX;
+
+/// A node of the tree. Leaves hold a proxy, branches
+/// hold the union of the bounding boxes of their children.
+/// Freed nodes are chained through `parent` and have a height of -1
+#[derive(Debug, Clone)]
+struct Node {
+    aabb: Aabb,
+    parent: usize,
+    child1: usize,
+    child2: usize,
+    height: i32,
+}
+
+impl Node {
+    fn is_leaf(&self) -> bool {
+        self.child1 == NULL_NODE
+    }
+}
+
+/// DynamicTree is a bounding volume hierarchy of fattened `Aabb`s.
+/// Proxies may be inserted, moved and removed incrementally and the
+/// tree is kept balanced through tree rotations.
+/// Algorithm sourced from Erin Catto's Box2D: https://github.com/erincatto/Box2D
+#[derive(Debug, Clone)]
+pub struct DynamicTree {
+    nodes: Vec<Node>,
+    root: usize,
+    free_list: usize,
+    margin: f64,
+    proxy_count: usize,
+    moved: Vec<ProxyId>,
+}
+
+impl DynamicTree {
+    /// Creates a new empty tree fattening proxies by `DEFAULT_AABB_MARGIN`
+    pub fn new() -> DynamicTree {
+        DynamicTree::with_margin(DEFAULT_AABB_MARGIN)
+    }
+
+    /// Creates a new empty tree fattening proxies by `margin`
+    pub fn with_margin(margin: f64) -> DynamicTree {
+        DynamicTree {
+            nodes: Vec::new(),
+            root: NULL_NODE,
+            free_list: NULL_NODE,
+            margin,
+            proxy_count: 0,
+            moved: Vec::new(),
+        }
+    }
+
+    /// Returns the number of proxies in the tree
+    pub fn proxy_count(&self) -> usize {
+        self.proxy_count
+    }
+
+    /// Returns the height of the tree. An empty tree
+    /// and a tree with a single proxy have a height of 0
+    pub fn height(&self) -> usize {
+        if self.root == NULL_NODE {
+            0
+        } else {
+            self.nodes[self.root].height as usize
+        }
+    }
+
+    /// Inserts a proxy for the given `Aabb` into the tree
+    pub fn create_proxy(&mut self, aabb: &Aabb) -> ProxyId {
+        let id = self.allocate_node();
+        self.nodes[id].aabb = aabb.fatten(self.margin);
+        self.nodes[id].height = 0;
+        self.insert_leaf(id);
+        self.proxy_count += 1;
+        self.moved.push(ProxyId(id));
+        ProxyId(id)
+    }
+
+    /// Removes the proxy from the tree
+    pub fn destroy_proxy(&mut self, proxy: ProxyId) {
+        debug_assert!(self.is_proxy(proxy));
+        self.remove_leaf(proxy.0);
+        self.free_node(proxy.0);
+        self.proxy_count -= 1;
+        self.moved.retain(|&p| p != proxy);
+    }
+
+    /// Updates the proxy with its new `Aabb` and the displacement it
+    /// underwent since the last update. The proxy is only reinserted if
+    /// `aabb` escapes its fattened `Aabb`, in which case the new fattened
+    /// `Aabb` is extended in the direction of travel. Returns if the proxy
+    /// was reinserted
+    pub fn move_proxy(&mut self, proxy: ProxyId, aabb: &Aabb, displacement: Vec2d) -> bool {
+        debug_assert!(self.is_proxy(proxy));
+        if self.nodes[proxy.0].aabb.contains(aabb) {
+            return false;
+        }
+
+        self.remove_leaf(proxy.0);
+        self.nodes[proxy.0].aabb = broad_phase::fatten(aabb, self.margin, displacement);
+        self.insert_leaf(proxy.0);
+        self.moved.push(proxy);
+        true
+    }
+
+    /// Reports the pairs of the proxy again on the next
+    /// call to `moved_pairs` as if the proxy was moved
+    pub fn touch_proxy(&mut self, proxy: ProxyId) {
+        debug_assert!(self.is_proxy(proxy));
+        self.moved.push(proxy);
+    }
+
+    /// Returns the fattened `Aabb` of the proxy
+    pub fn fat_aabb(&self, proxy: ProxyId) -> &Aabb {
+        debug_assert!(self.is_proxy(proxy));
+        &self.nodes[proxy.0].aabb
+    }
+
+    /// Calls `callback` with every proxy whose fattened `Aabb` overlaps
+    /// `aabb`. The query stops early if `callback` returns false
+    pub fn query<F>(&self, aabb: &Aabb, mut callback: F)
+        where F: FnMut(ProxyId) -> bool
+    {
+        if self.root == NULL_NODE {
+            return;
+        }
+
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+            if !node.aabb.intersects(aabb) {
+                continue;
+            }
+            if node.is_leaf() {
+                if !callback(ProxyId(id)) {
+                    return;
+                }
+            } else {
+                stack.push(node.child1);
+                stack.push(node.child2);
+            }
+        }
+    }
+
+    /// Returns every pair of proxies whose fattened `Aabb`s overlap. Each
+    /// pair is reported once with the smaller proxy first, and pairs are sorted
+    pub fn overlapping_pairs(&self) -> Vec<(ProxyId, ProxyId)> {
+        let mut pairs = Vec::new();
+        for (id, node) in self.nodes.iter().enumerate() {
+            if node.height != 0 {
+                continue;
+            }
+            self.query(&node.aabb, |other| {
+                if other.0 > id {
+                    pairs.push((ProxyId(id), other));
+                }
+                true
+            });
+        }
+        pairs.sort();
+        pairs
+    }
+
+    /// Returns if `proxy` refers to a leaf of the tree
+    fn is_proxy(&self, proxy: ProxyId) -> bool {
+        proxy.0 < self.nodes.len() && self.nodes[proxy.0].height == 0
+    }
+
+    /// Takes a node from the free list or grows the node pool
+    fn allocate_node(&mut self) -> usize {
+        let node = Node {
+            aabb: Aabb::from_bounds(Vec2d::zero(), Vec2d::zero()),
+            parent: NULL_NODE,
+            child1: NULL_NODE,
+            child2: NULL_NODE,
+            height: 0,
+        };
+        if self.free_list == NULL_NODE {
+            self.nodes.push(node);
+            return self.nodes.len() - 1;
+        }
+        let id = self.free_list;
+        self.free_list = self.nodes[id].parent;
+        self.nodes[id] = node;
+        id
+    }
+
+    /// Returns a node to the free list
+    fn free_node(&mut self, id: usize) {
+        self.nodes[id].parent = self.free_list;
+        self.nodes[id].height = -1;
+        self.free_list = id;
+    }
+
+    /// Inserts the leaf next to the sibling that minimizes the total
+    /// perimeter of the tree, then refits and rebalances its ancestors
+    fn insert_leaf(&mut self, leaf: usize) {
+        if self.root == NULL_NODE {
+            self.root = leaf;
+            self.nodes[leaf].parent = NULL_NODE;
+            return;
+        }
+
+        // find the best sibling by descending the
+        // branch with the smallest increase in cost
+        let leaf_aabb = self.nodes[leaf].aabb;
+        let mut index = self.root;
+        while !self.nodes[index].is_leaf() {
+            let child1 = self.nodes[index].child1;
+            let child2 = self.nodes[index].child2;
+
+            let area = self.nodes[index].aabb.perimeter();
+            let combined_area = self.nodes[index].aabb.combine(&leaf_aabb).perimeter();
+
+            // cost of creating a new parent for this node and the new leaf
+            let cost = 2.0 * combined_area;
+
+            // minimum cost of pushing the leaf further down the tree
+            let inheritance_cost = 2.0 * (combined_area - area);
+            let cost1 = self.descend_cost(child1, &leaf_aabb) + inheritance_cost;
+            let cost2 = self.descend_cost(child2, &leaf_aabb) + inheritance_cost;
+
+            if cost < cost1 && cost < cost2 {
+                break;
+            }
+            index = if cost1 < cost2 {
+                child1
+            } else {
+                child2
+            };
+        }
+        let sibling = index;
+
+        // create a new parent for the sibling and the leaf
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.allocate_node();
+        self.nodes[new_parent].parent = old_parent;
+        self.nodes[new_parent].aabb = leaf_aabb.combine(&self.nodes[sibling].aabb);
+        self.nodes[new_parent].height = self.nodes[sibling].height + 1;
+        self.nodes[new_parent].child1 = sibling;
+        self.nodes[new_parent].child2 = leaf;
+        self.nodes[sibling].parent = new_parent;
+        self.nodes[leaf].parent = new_parent;
+
+        if old_parent == NULL_NODE {
+            self.root = new_parent;
+        } else if self.nodes[old_parent].child1 == sibling {
+            self.nodes[old_parent].child1 = new_parent;
+        } else {
+            self.nodes[old_parent].child2 = new_parent;
+        }
+
+        let parent = self.nodes[leaf].parent;
+        self.refit(parent);
+    }
+
+    /// Returns the cost of descending into `child` when inserting `aabb`
+    fn descend_cost(&self, child: usize, aabb: &Aabb) -> f64 {
+        let node = &self.nodes[child];
+        let combined = aabb.combine(&node.aabb).perimeter();
+        if node.is_leaf() {
+            combined
+        } else {
+            combined - node.aabb.perimeter()
+        }
+    }
+
+    /// Removes the leaf from the tree, replacing its
+    /// parent with its sibling
+    fn remove_leaf(&mut self, leaf: usize) {
+        if leaf == self.root {
+            self.root = NULL_NODE;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent;
+        let grand_parent = self.nodes[parent].parent;
+        let sibling = if self.nodes[parent].child1 == leaf {
+            self.nodes[parent].child2
+        } else {
+            self.nodes[parent].child1
+        };
+
+        self.nodes[sibling].parent = grand_parent;
+        self.free_node(parent);
+        if grand_parent == NULL_NODE {
+            self.root = sibling;
+            return;
+        }
+
+        if self.nodes[grand_parent].child1 == parent {
+            self.nodes[grand_parent].child1 = sibling;
+        } else {
+            self.nodes[grand_parent].child2 = sibling;
+        }
+        self.refit(grand_parent);
+    }
+
+    /// Walks from `index` to the root rebalancing each node
+    /// and recomputing its height and `Aabb`
+    fn refit(&mut self, mut index: usize) {
+        while index != NULL_NODE {
+            index = self.balance(index);
+
+            let child1 = self.nodes[index].child1;
+            let child2 = self.nodes[index].child2;
+            self.nodes[index].height = 1 +
+                                       self.nodes[child1].height.max(self.nodes[child2].height);
+            self.nodes[index].aabb = self.nodes[child1].aabb.combine(&self.nodes[child2].aabb);
+
+            index = self.nodes[index].parent;
+        }
+    }
+
+    /// Performs a left or right rotation if the subtree rooted at `a`
+    /// is imbalanced, returning the new root of the subtree
+    fn balance(&mut self, a: usize) -> usize {
+        if self.nodes[a].is_leaf() || self.nodes[a].height < 2 {
+            return a;
+        }
+
+        let b = self.nodes[a].child1;
+        let c = self.nodes[a].child2;
+        let balance = self.nodes[c].height - self.nodes[b].height;
+
+        if balance > 1 {
+            // rotate c up
+            self.rotate_up(a, c, b, false)
+        } else if balance < -1 {
+            // rotate b up
+            self.rotate_up(a, b, c, true)
+        } else {
+            a
+        }
+    }
+
+    /// Promotes the child `up` of `a` to the position of `a`. `a` becomes a
+    /// child of `up` and keeps `other` along with the shorter child of `up`.
+    /// `up_is_child1` tells which side of `a` `up` was on
+    fn rotate_up(&mut self, a: usize, up: usize, other: usize, up_is_child1: bool) -> usize {
+        let f = self.nodes[up].child1;
+        let g = self.nodes[up].child2;
+
+        // swap a and up
+        let a_parent = self.nodes[a].parent;
+        self.nodes[up].child1 = a;
+        self.nodes[up].parent = a_parent;
+        self.nodes[a].parent = up;
+
+        if a_parent == NULL_NODE {
+            self.root = up;
+        } else if self.nodes[a_parent].child1 == a {
+            self.nodes[a_parent].child1 = up;
+        } else {
+            self.nodes[a_parent].child2 = up;
+        }
+
+        // the taller grandchild stays under up,
+        // the shorter one moves under a
+        let (tall, short) = if self.nodes[f].height > self.nodes[g].height {
+            (f, g)
+        } else {
+            (g, f)
+        };
+        self.nodes[up].child2 = tall;
+        if up_is_child1 {
+            self.nodes[a].child1 = short;
+        } else {
+            self.nodes[a].child2 = short;
+        }
+        self.nodes[short].parent = a;
+
+        self.nodes[a].aabb = self.nodes[other].aabb.combine(&self.nodes[short].aabb);
+        self.nodes[up].aabb = self.nodes[a].aabb.combine(&self.nodes[tall].aabb);
+        self.nodes[a].height = 1 + self.nodes[other].height.max(self.nodes[short].height);
+        self.nodes[up].height = 1 + self.nodes[a].height.max(self.nodes[tall].height);
+        up
+    }
+}
+
+impl BroadPhase for DynamicTree {
+    fn create_proxy(&mut self, aabb: &Aabb) -> ProxyId {
+        DynamicTree::create_proxy(self, aabb)
+    }
+
+    fn destroy_proxy(&mut self, proxy: ProxyId) {
+        DynamicTree::destroy_proxy(self, proxy)
+    }
+
+    fn move_proxy(&mut self, proxy: ProxyId, aabb: &Aabb, displacement: Vec2d) -> bool {
+        DynamicTree::move_proxy(self, proxy, aabb, displacement)
+    }
+
+    fn touch_proxy(&mut self, proxy: ProxyId) {
+        DynamicTree::touch_proxy(self, proxy)
+    }
+
+    fn fat_aabb(&self, proxy: ProxyId) -> &Aabb {
+        DynamicTree::fat_aabb(self, proxy)
+    }
+
+    fn proxy_count(&self) -> usize {
+        DynamicTree::proxy_count(self)
+    }
+
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(ProxyId) -> bool) {
+        DynamicTree::query(self, aabb, callback)
+    }
+
+    fn moved_pairs(&mut self) -> Vec<(ProxyId, ProxyId)> {
+        let pairs = broad_phase::moved_pairs(self, &self.moved);
+        self.moved.clear();
+        pairs
+    }
+}
+
+impl Default for DynamicTree {
+    fn default() -> DynamicTree {
+        DynamicTree::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::vec::Vec;
+    use collision::Aabb;
+    use collision::broad_phase::{BroadPhase, DynamicTree, ProxyId};
+    use common::Vec2d;
+    use super::NULL_NODE;
+
+    fn aabb(x: f64, y: f64, half: f64) -> Aabb {
+        Aabb::from_bounds(Vec2d::new(x - half, y - half), Vec2d::new(x + half, y + half))
+    }
+
+    /// Checks parent links, heights, bounding boxes and balance
+    /// of the subtree at `index`, returning its height
+    fn validate(tree: &DynamicTree, index: usize, parent: usize) -> i32 {
+        let node = &tree.nodes[index];
+        assert_eq!(parent, node.parent);
+        if node.is_leaf() {
+            assert_eq!(0, node.height);
+            return 0;
+        }
+        let h1 = validate(tree, node.child1, index);
+        let h2 = validate(tree, node.child2, index);
+        assert_eq!(1 + h1.max(h2), node.height);
+        assert!((h1 - h2).abs() <= 1);
+        assert_eq!(tree.nodes[node.child1].aabb.combine(&tree.nodes[node.child2].aabb), node.aabb);
+        node.height
+    }
+
+    fn brute_force(tree: &DynamicTree, proxies: &[ProxyId]) -> Vec<(ProxyId, ProxyId)> {
+        let mut pairs = Vec::new();
+        for (i, &a) in proxies.iter().enumerate() {
+            for &b in &proxies[i + 1..] {
+                if tree.fat_aabb(a).intersects(tree.fat_aabb(b)) {
+                    pairs.push(if a < b {
+                        (a, b)
+                    } else {
+                        (b, a)
+                    });
+                }
+            }
+        }
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn test_dynamic_tree() {
+        let mut tree = DynamicTree::new();
+        let mut proxies = Vec::new();
+
+        // a row of boxes inserted in order would degenerate
+        // into a list without rotations
+        for i in 0..256 {
+            proxies.push(tree.create_proxy(&aabb(i as f64 * 0.9, 0.0, 0.5)));
+        }
+        assert_eq!(256, tree.proxy_count());
+        validate(&tree, tree.root, NULL_NODE);
+        assert!(tree.height() <= 16);
+        assert_eq!(brute_force(&tree, &proxies), tree.overlapping_pairs());
+        assert_eq!(255, tree.overlapping_pairs().len());
+
+        // small motions stay within the fattened box
+        assert!(!tree.move_proxy(proxies[10], &aabb(9.05, 0.0, 0.5), Vec2d::new(0.05, 0.0)));
+        assert!(tree.move_proxy(proxies[10], &aabb(9.0, 5.0, 0.5), Vec2d::new(0.0, 5.0)));
+        assert!(tree.fat_aabb(proxies[10]).max().y > 5.5 + 0.1);
+        validate(&tree, tree.root, NULL_NODE);
+        assert_eq!(brute_force(&tree, &proxies), tree.overlapping_pairs());
+
+        for p in proxies.drain(..128) {
+            tree.destroy_proxy(p);
+        }
+        validate(&tree, tree.root, NULL_NODE);
+        assert_eq!(brute_force(&tree, &proxies), tree.overlapping_pairs());
+
+        // freed nodes are reused
+        let nodes = tree.nodes.len();
+        for i in 0..64 {
+            proxies.push(tree.create_proxy(&aabb(i as f64, 10.0, 0.6)));
+        }
+        assert_eq!(nodes, tree.nodes.len());
+        validate(&tree, tree.root, NULL_NODE);
+        assert_eq!(brute_force(&tree, &proxies), tree.overlapping_pairs());
+
+        let mut found = Vec::new();
+        tree.query(&aabb(2.5, 10.0, 0.1), |p| {
+            found.push(p);
+            true
+        });
+        assert_eq!(2, found.len());
+    }
+
+    #[test]
+    fn test_dynamic_tree_moved_pairs() {
+        let mut tree = DynamicTree::new();
+        let proxies: Vec<ProxyId> = (0..16).map(|i| tree.create_proxy(&aabb(i as f64 * 0.9, 0.0, 0.5)))
+                                           .collect();
+        assert_eq!(brute_force(&tree, &proxies), tree.moved_pairs());
+        assert!(tree.moved_pairs().is_empty());
+
+        // proxies staying within their fattened boxes aren't paired again
+        assert!(!tree.move_proxy(proxies[3], &aabb(2.75, 0.0, 0.5), Vec2d::new(0.05, 0.0)));
+        assert!(tree.moved_pairs().is_empty());
+
+        assert!(tree.move_proxy(proxies[3], &aabb(9.0, 0.3, 0.5), Vec2d::new(6.3, 0.3)));
+        let pairs = tree.moved_pairs();
+        assert!(!pairs.is_empty());
+        assert!(pairs.iter().all(|&(a, b)| a == proxies[3] || b == proxies[3]));
+
+        // touched proxies are paired again, destroyed ones are forgotten
+        tree.touch_proxy(proxies[0]);
+        tree.touch_proxy(proxies[15]);
+        tree.destroy_proxy(proxies[15]);
+        assert_eq!(vec![(proxies[0], proxies[1])], tree.moved_pairs());
+    }
+}
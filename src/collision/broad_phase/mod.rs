@@ -0,0 +1,103 @@
+pub use self::dynamic_tree::{DEFAULT_AABB_MARGIN, DynamicTree};
+pub use self::spatial_hash::SpatialHash;
+pub use self::sweep_and_prune::SweepAndPrune;
+
+use std::vec::Vec;
+use collision::Aabb;
+use common::Vec2d;
+
+mod dynamic_tree;
+mod spatial_hash;
+mod sweep_and_prune;
+
+/// Scales the displacement of a moving proxy to predict
+/// where the proxy will be over the next few steps
+const AABB_MULTIPLIER: f64 = 2.0;
+
+/// ProxyId identifies an `Aabb` inserted into a broad phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProxyId(usize);
+
+/// Trait represents a broad phase: a structure tracking the `Aabb`s
+/// of many objects that can cheaply report which of them may overlap.
+///
+/// # Remarks
+///
+/// Implementations store fattened `Aabb`s so that small motions
+/// don't require the structure to be updated. Reported pairs are
+/// based on the fattened `Aabb`s and need to be confirmed by a
+/// narrow phase.
+///
+/// Like the move buffer of Box2D, only pairs involving a proxy that was
+/// created, moved or touched since the last update are reported, so
+/// proxies that stay put, like those of static bodies, aren't paired up
+/// with each other again and again
+pub trait BroadPhase {
+    /// Inserts a proxy for the given `Aabb`
+    fn create_proxy(&mut self, aabb: &Aabb) -> ProxyId;
+
+    /// Removes the proxy
+    fn destroy_proxy(&mut self, proxy: ProxyId);
+
+    /// Updates the proxy with its new `Aabb` and the displacement it
+    /// underwent since the last update. Returns if the fattened `Aabb`
+    /// of the proxy had to be updated
+    fn move_proxy(&mut self, proxy: ProxyId, aabb: &Aabb, displacement: Vec2d) -> bool;
+
+    /// Reports the pairs of the proxy again on the next call to
+    /// `moved_pairs` as if the proxy was moved
+    fn touch_proxy(&mut self, proxy: ProxyId);
+
+    /// Returns the fattened `Aabb` of the proxy
+    fn fat_aabb(&self, proxy: ProxyId) -> &Aabb;
+
+    /// Returns the number of proxies
+    fn proxy_count(&self) -> usize;
+
+    /// Calls `callback` with every proxy whose fattened `Aabb` overlaps
+    /// `aabb`. The query stops early if `callback` returns false
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(ProxyId) -> bool);
+
+    /// Returns every pair of proxies whose fattened `Aabb`s overlap and of
+    /// which at least one proxy was created, moved or touched since the
+    /// last call. Each pair is reported once with the smaller proxy first,
+    /// and pairs are sorted
+    fn moved_pairs(&mut self) -> Vec<(ProxyId, ProxyId)>;
+}
+
+/// Returns the sorted pairs of overlapping proxies of the
+/// broad phase of which at least one proxy is in `moved`
+fn moved_pairs<B: BroadPhase + ?Sized>(broad_phase: &B, moved: &[ProxyId]) -> Vec<(ProxyId, ProxyId)> {
+    let mut pairs = Vec::new();
+    for &proxy in moved {
+        broad_phase.query(broad_phase.fat_aabb(proxy), &mut |other| {
+            if other != proxy {
+                pairs.push((proxy.min(other), proxy.max(other)));
+            }
+            true
+        });
+    }
+    pairs.sort();
+    pairs.dedup();
+    pairs
+}
+
+/// Returns `aabb` fattened by `margin` and extended in the
+/// direction of `displacement` to anticipate further motion
+fn fatten(aabb: &Aabb, margin: f64, displacement: Vec2d) -> Aabb {
+    let fat = aabb.fatten(margin);
+    let d = displacement * AABB_MULTIPLIER;
+    let mut min = *fat.min();
+    let mut max = *fat.max();
+    if d.x < 0.0 {
+        min.x += d.x;
+    } else {
+        max.x += d.x;
+    }
+    if d.y < 0.0 {
+        min.y += d.y;
+    } else {
+        max.y += d.y;
+    }
+    Aabb::from_bounds(min, max)
+}
\ No newline at end of file
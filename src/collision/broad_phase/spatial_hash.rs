@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::vec::Vec;
+use collision::Aabb;
+use collision::broad_phase::{self, BroadPhase, DEFAULT_AABB_MARGIN, ProxyId};
+use common::Vec2d;
+
+/// An inclusive range of grid cells covered by an `Aabb`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CellRange {
+    min: (i64, i64),
+    max: (i64, i64),
+}
+
+impl CellRange {
+    fn contains(&self, cell: (i64, i64)) -> bool {
+        self.min.0 <= cell.0 && cell.0 <= self.max.0 && self.min.1 <= cell.1 &&
+        cell.1 <= self.max.1
+    }
+
+    fn cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        for x in self.min.0..self.max.0 + 1 {
+            for y in self.min.1..self.max.1 + 1 {
+                cells.push((x, y));
+            }
+        }
+        cells
+    }
+}
+
+/// SpatialHash buckets the fattened `Aabb`s of its proxies into a uniform
+/// grid of square cells stored in a hash map, so only occupied cells cost
+/// memory. Proxies are only tested against proxies sharing a cell.
+///
+/// # Remarks
+///
+/// Works best when most proxies are about the size of a cell. Much larger
+/// proxies occupy many cells and much smaller cells hold many proxies.
+/// Moving a proxy only touches the cells it enters or leaves
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    cell_size: f64,
+    margin: f64,
+    aabbs: Vec<Option<Aabb>>,
+    free: Vec<usize>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    moved: Vec<ProxyId>,
+}
+
+impl SpatialHash {
+    /// Creates a new empty spatial hash with square cells of `cell_size`
+    /// fattening proxies by `DEFAULT_AABB_MARGIN`
+    pub fn new(cell_size: f64) -> SpatialHash {
+        SpatialHash::with_margin(cell_size, DEFAULT_AABB_MARGIN)
+    }
+
+    /// Creates a new empty spatial hash with square cells of `cell_size`
+    /// fattening proxies by `margin`
+    pub fn with_margin(cell_size: f64, margin: f64) -> SpatialHash {
+        debug_assert!(cell_size > 0.0);
+        SpatialHash {
+            cell_size,
+            margin,
+            aabbs: Vec::new(),
+            free: Vec::new(),
+            cells: HashMap::new(),
+            moved: Vec::new(),
+        }
+    }
+
+    /// Returns the size of the cells
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// Returns the number of occupied cells
+    pub fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns the cell containing the point
+    fn cell(&self, p: &Vec2d) -> (i64, i64) {
+        ((p.x / self.cell_size).floor() as i64, (p.y / self.cell_size).floor() as i64)
+    }
+
+    /// Returns the range of cells covered by the `Aabb`
+    fn cell_range(&self, aabb: &Aabb) -> CellRange {
+        CellRange {
+            min: self.cell(aabb.min()),
+            max: self.cell(aabb.max()),
+        }
+    }
+
+    /// Returns every pair of proxies whose fattened `Aabb`s overlap. Each
+    /// pair is reported once with the smaller proxy first, and pairs are sorted
+    pub fn overlapping_pairs(&self) -> Vec<(ProxyId, ProxyId)> {
+        let mut pairs = Vec::new();
+        for (&cell, bucket) in &self.cells {
+            for (i, &a) in bucket.iter().enumerate() {
+                let aabb_a = self.aabb(a);
+                for &b in &bucket[i + 1..] {
+                    let aabb_b = self.aabb(b);
+                    if !aabb_a.intersects(aabb_b) {
+                        continue;
+                    }
+
+                    // a pair sharing several cells is only reported by the
+                    // cell holding the lower corner of the overlap region
+                    let corner = Vec2d::new(aabb_a.min().x.max(aabb_b.min().x),
+                                            aabb_a.min().y.max(aabb_b.min().y));
+                    if self.cell(&corner) == cell {
+                        pairs.push((ProxyId(a.min(b)), ProxyId(a.max(b))));
+                    }
+                }
+            }
+        }
+        pairs.sort();
+        pairs
+    }
+
+    /// Returns the fattened `Aabb` of the proxy with index `i`
+    fn aabb(&self, i: usize) -> &Aabb {
+        self.aabbs[i].as_ref().unwrap()
+    }
+
+    /// Adds the proxy with index `id` to the cell
+    fn insert(&mut self, id: usize, cell: (i64, i64)) {
+        self.cells.entry(cell).or_default().push(id);
+    }
+
+    /// Removes the proxy with index `id` from the cell,
+    /// dropping the cell once it is empty
+    fn remove(&mut self, id: usize, cell: (i64, i64)) {
+        let empty = {
+            let bucket = self.cells.get_mut(&cell).unwrap();
+            let i = bucket.iter().position(|&other| other == id).unwrap();
+            bucket.swap_remove(i);
+            bucket.is_empty()
+        };
+        if empty {
+            self.cells.remove(&cell);
+        }
+    }
+}
+
+impl BroadPhase for SpatialHash {
+    fn create_proxy(&mut self, aabb: &Aabb) -> ProxyId {
+        let fat = aabb.fatten(self.margin);
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.aabbs[id] = Some(fat);
+                id
+            }
+            None => {
+                self.aabbs.push(Some(fat));
+                self.aabbs.len() - 1
+            }
+        };
+        for cell in self.cell_range(&fat).cells() {
+            self.insert(id, cell);
+        }
+        self.moved.push(ProxyId(id));
+        ProxyId(id)
+    }
+
+    fn destroy_proxy(&mut self, proxy: ProxyId) {
+        let range = self.cell_range(self.aabb(proxy.0));
+        for cell in range.cells() {
+            self.remove(proxy.0, cell);
+        }
+        self.aabbs[proxy.0] = None;
+        self.free.push(proxy.0);
+        self.moved.retain(|&p| p != proxy);
+    }
+
+    fn move_proxy(&mut self, proxy: ProxyId, aabb: &Aabb, displacement: Vec2d) -> bool {
+        if self.aabb(proxy.0).contains(aabb) {
+            return false;
+        }
+
+        let fat = broad_phase::fatten(aabb, self.margin, displacement);
+        let old_range = self.cell_range(self.aabb(proxy.0));
+        let new_range = self.cell_range(&fat);
+        self.aabbs[proxy.0] = Some(fat);
+
+        // only touch the cells that were left or entered
+        if old_range != new_range {
+            for cell in old_range.cells() {
+                if !new_range.contains(cell) {
+                    self.remove(proxy.0, cell);
+                }
+            }
+            for cell in new_range.cells() {
+                if !old_range.contains(cell) {
+                    self.insert(proxy.0, cell);
+                }
+            }
+        }
+        self.moved.push(proxy);
+        true
+    }
+
+    fn touch_proxy(&mut self, proxy: ProxyId) {
+        debug_assert!(self.aabbs[proxy.0].is_some());
+        self.moved.push(proxy);
+    }
+
+    fn fat_aabb(&self, proxy: ProxyId) -> &Aabb {
+        self.aabb(proxy.0)
+    }
+
+    fn proxy_count(&self) -> usize {
+        self.aabbs.len() - self.free.len()
+    }
+
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(ProxyId) -> bool) {
+        let mut found = Vec::new();
+        for cell in self.cell_range(aabb).cells() {
+            if let Some(bucket) = self.cells.get(&cell) {
+                found.extend(bucket.iter().filter(|&&id| self.aabb(id).intersects(aabb)));
+            }
+        }
+        found.sort();
+        found.dedup();
+        for id in found {
+            if !callback(ProxyId(id)) {
+                return;
+            }
+        }
+    }
+
+    fn moved_pairs(&mut self) -> Vec<(ProxyId, ProxyId)> {
+        let pairs = broad_phase::moved_pairs(self, &self.moved);
+        self.moved.clear();
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::vec::Vec;
+    use collision::Aabb;
+    use collision::broad_phase::{BroadPhase, DynamicTree, ProxyId, SpatialHash};
+    use common::Vec2d;
+
+    fn aabb(x: f64, y: f64, half: f64) -> Aabb {
+        Aabb::from_bounds(Vec2d::new(x - half, y - half), Vec2d::new(x + half, y + half))
+    }
+
+    /// Maps the reported pairs of proxies to pairs of indices into `ids`
+    fn indices(pairs: &[(ProxyId, ProxyId)], ids: &[ProxyId]) -> Vec<(usize, usize)> {
+        let mut mapped: Vec<(usize, usize)> = pairs.iter()
+                                                   .map(|&(a, b)| {
+                                                       let ia = ids.iter().position(|&p| p == a).unwrap();
+                                                       let ib = ids.iter().position(|&p| p == b).unwrap();
+                                                       (ia.min(ib), ia.max(ib))
+                                                   })
+                                                   .collect();
+        mapped.sort();
+        mapped
+    }
+
+    #[test]
+    fn test_spatial_hash_matches_tree() {
+        let mut hash = SpatialHash::new(1.0);
+        let mut tree = DynamicTree::new();
+        let mut hash_ids = Vec::new();
+        let mut tree_ids = Vec::new();
+        let mut boxes = Vec::new();
+
+        // particles of mixed sizes, some spanning several cells
+        for i in 0..200 {
+            let x = ((i * 31) % 40) as f64 * 0.37 - 3.0;
+            let y = ((i * 17) % 23) as f64 * 0.41 - 3.0;
+            let half = 0.2 + (i % 5) as f64 * 0.3;
+            hash_ids.push(hash.create_proxy(&aabb(x, y, half)));
+            tree_ids.push(tree.create_proxy(&aabb(x, y, half)));
+            boxes.push((x, y, half));
+        }
+        let pairs = hash.overlapping_pairs();
+        let mut deduped = pairs.clone();
+        deduped.dedup();
+        assert_eq!(deduped, pairs);
+        assert!(!pairs.is_empty());
+        assert_eq!(indices(&tree.overlapping_pairs(), &tree_ids), indices(&pairs, &hash_ids));
+
+        for (i, &(x, y, half)) in boxes.iter().enumerate() {
+            let d = Vec2d::new((i % 3) as f64 - 1.0, (i % 4) as f64 - 1.5) * 0.8;
+            hash.move_proxy(hash_ids[i], &aabb(x + d.x, y + d.y, half), d);
+            tree.move_proxy(tree_ids[i], &aabb(x + d.x, y + d.y, half), d);
+        }
+        assert_eq!(indices(&tree.overlapping_pairs(), &tree_ids),
+                   indices(&hash.overlapping_pairs(), &hash_ids));
+
+        for i in (0..200).step_by(3) {
+            hash.destroy_proxy(hash_ids[i]);
+            tree.destroy_proxy(tree_ids[i]);
+        }
+        assert_eq!(indices(&tree.overlapping_pairs(), &tree_ids),
+                   indices(&hash.overlapping_pairs(), &hash_ids));
+        assert_eq!(tree.proxy_count(), hash.proxy_count());
+
+        let query = aabb(0.0, 0.0, 0.5);
+        let mut from_hash = Vec::new();
+        hash.query(&query, &mut |p| {
+            from_hash.push(hash_ids.iter().position(|&id| id == p).unwrap());
+            true
+        });
+        let mut from_tree = Vec::new();
+        tree.query(&query, |p| {
+            from_tree.push(tree_ids.iter().position(|&id| id == p).unwrap());
+            true
+        });
+        from_hash.sort();
+        from_tree.sort();
+        assert_eq!(from_tree, from_hash);
+
+        // removing everything frees every cell
+        for i in (0..200).filter(|i| i % 3 != 0) {
+            hash.destroy_proxy(hash_ids[i]);
+        }
+        assert_eq!(0, hash.cell_count());
+    }
+}
@@ -0,0 +1,285 @@
+use std::vec::Vec;
+use collision::Aabb;
+use collision::broad_phase::{self, BroadPhase, DEFAULT_AABB_MARGIN, ProxyId};
+use common::Vec2d;
+
+/// SweepAndPrune keeps the fattened `Aabb`s of its proxies sorted along
+/// the x axis. Overlaps are found by searching or sweeping the sorted list
+/// and only testing proxies whose x intervals overlap.
+///
+/// # Remarks
+///
+/// The sorted order is kept up to date as proxies are created, moved and
+/// destroyed. A moved proxy is shifted into place from where it was, which
+/// is cheap when proxies only move a little between steps. Best suited to
+/// scenes spread out along the x axis with few proxies stacked on top of
+/// each other
+#[derive(Debug, Clone)]
+pub struct SweepAndPrune {
+    aabbs: Vec<Option<Aabb>>,
+    free: Vec<usize>,
+    order: Vec<usize>,
+    moved: Vec<ProxyId>,
+    margin: f64,
+}
+
+impl SweepAndPrune {
+    /// Creates a new empty sweep and prune fattening
+    /// proxies by `DEFAULT_AABB_MARGIN`
+    pub fn new() -> SweepAndPrune {
+        SweepAndPrune::with_margin(DEFAULT_AABB_MARGIN)
+    }
+
+    /// Creates a new empty sweep and prune fattening proxies by `margin`
+    pub fn with_margin(margin: f64) -> SweepAndPrune {
+        SweepAndPrune {
+            aabbs: Vec::new(),
+            free: Vec::new(),
+            order: Vec::new(),
+            moved: Vec::new(),
+            margin,
+        }
+    }
+
+    /// Returns every pair of proxies whose fattened `Aabb`s overlap. Each
+    /// pair is reported once with the smaller proxy first, and pairs are sorted
+    pub fn overlapping_pairs(&self) -> Vec<(ProxyId, ProxyId)> {
+        let mut pairs = Vec::new();
+        for (i, &a) in self.order.iter().enumerate() {
+            let aabb_a = self.aabb(a);
+            for &b in &self.order[i + 1..] {
+                let aabb_b = self.aabb(b);
+                if aabb_b.min().x > aabb_a.max().x {
+                    // every later proxy starts further right
+                    break;
+                }
+                if aabb_a.intersects(aabb_b) {
+                    pairs.push((ProxyId(a.min(b)), ProxyId(a.max(b))));
+                }
+            }
+        }
+        pairs.sort();
+        pairs
+    }
+
+    /// Returns the fattened `Aabb` of the proxy with index `i`
+    fn aabb(&self, i: usize) -> &Aabb {
+        self.aabbs[i].as_ref().unwrap()
+    }
+
+    /// Returns the number of proxies in `order` whose fattened `Aabb`
+    /// starts left of `x`, or at `x` too if `inclusive`
+    fn bound(&self, x: f64, inclusive: bool) -> usize {
+        self.order.partition_point(|&id| {
+            let min = self.aabb(id).min().x;
+            min < x || (inclusive && min == x)
+        })
+    }
+
+    /// Returns the index of the proxy with index `id` in `order`
+    fn position(&self, id: usize) -> usize {
+        let start = self.bound(self.aabb(id).min().x, false);
+        start + self.order[start..].iter().position(|&o| o == id).unwrap()
+    }
+
+    /// Shifts the proxy at index `i` of `order` left or right until the
+    /// proxies are ordered by the lower x bound of their fattened `Aabb`s
+    fn shift(&mut self, mut i: usize) {
+        let id = self.order[i];
+        let x = self.aabb(id).min().x;
+        while i > 0 && self.aabb(self.order[i - 1]).min().x > x {
+            self.order[i] = self.order[i - 1];
+            i -= 1;
+        }
+        while i + 1 < self.order.len() && self.aabb(self.order[i + 1]).min().x < x {
+            self.order[i] = self.order[i + 1];
+            i += 1;
+        }
+        self.order[i] = id;
+    }
+}
+
+impl BroadPhase for SweepAndPrune {
+    fn create_proxy(&mut self, aabb: &Aabb) -> ProxyId {
+        let fat = Some(aabb.fatten(self.margin));
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.aabbs[id] = fat;
+                id
+            }
+            None => {
+                self.aabbs.push(fat);
+                self.aabbs.len() - 1
+            }
+        };
+        let i = self.bound(self.aabb(id).min().x, true);
+        self.order.insert(i, id);
+        self.moved.push(ProxyId(id));
+        ProxyId(id)
+    }
+
+    fn destroy_proxy(&mut self, proxy: ProxyId) {
+        debug_assert!(self.aabbs[proxy.0].is_some());
+        let i = self.position(proxy.0);
+        self.order.remove(i);
+        self.aabbs[proxy.0] = None;
+        self.free.push(proxy.0);
+        self.moved.retain(|&p| p != proxy);
+    }
+
+    fn move_proxy(&mut self, proxy: ProxyId, aabb: &Aabb, displacement: Vec2d) -> bool {
+        if self.aabb(proxy.0).contains(aabb) {
+            return false;
+        }
+        let i = self.position(proxy.0);
+        self.aabbs[proxy.0] = Some(broad_phase::fatten(aabb, self.margin, displacement));
+        self.shift(i);
+        self.moved.push(proxy);
+        true
+    }
+
+    fn touch_proxy(&mut self, proxy: ProxyId) {
+        debug_assert!(self.aabbs[proxy.0].is_some());
+        self.moved.push(proxy);
+    }
+
+    fn fat_aabb(&self, proxy: ProxyId) -> &Aabb {
+        self.aabb(proxy.0)
+    }
+
+    fn proxy_count(&self) -> usize {
+        self.order.len()
+    }
+
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(ProxyId) -> bool) {
+        // proxies starting right of the query can't overlap it
+        let end = self.bound(aabb.max().x, true);
+        for &id in &self.order[..end] {
+            if self.aabb(id).intersects(aabb) && !callback(ProxyId(id)) {
+                return;
+            }
+        }
+    }
+
+    fn moved_pairs(&mut self) -> Vec<(ProxyId, ProxyId)> {
+        let pairs = broad_phase::moved_pairs(self, &self.moved);
+        self.moved.clear();
+        pairs
+    }
+}
+
+impl Default for SweepAndPrune {
+    fn default() -> SweepAndPrune {
+        SweepAndPrune::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::vec::Vec;
+    use collision::Aabb;
+    use collision::broad_phase::{BroadPhase, DynamicTree, ProxyId, SweepAndPrune};
+    use common::Vec2d;
+
+    fn aabb(x: f64, y: f64) -> Aabb {
+        Aabb::from_bounds(Vec2d::new(x - 0.5, y - 0.5), Vec2d::new(x + 0.5, y + 0.5))
+    }
+
+    /// Runs the same scene through the broad phase, returning the pairs
+    /// reported after creating and moving proxies, and after destroying
+    /// some proxies and touching the rest
+    fn run<B: BroadPhase>(bp: &mut B) -> Vec<Vec<(usize, usize)>> {
+        let mut results = Vec::new();
+        let mut record = |bp: &mut B, ids: &[ProxyId]| {
+            let mut pairs: Vec<(usize, usize)> = bp.moved_pairs()
+                                                   .iter()
+                                                   .map(|&(a, b)| {
+                                                       let ia = ids.iter().position(|&p| p == a).unwrap();
+                                                       let ib = ids.iter().position(|&p| p == b).unwrap();
+                                                       (ia.min(ib), ia.max(ib))
+                                                   })
+                                                   .collect();
+            pairs.sort();
+            results.push(pairs);
+        };
+
+        let mut ids = Vec::new();
+        for i in 0..50 {
+            let x = ((i * 37) % 50) as f64 * 0.8;
+            let y = (i % 3) as f64 * 0.8;
+            ids.push(bp.create_proxy(&aabb(x, y)));
+        }
+        record(bp, &ids);
+
+        // scroll everything to the left, reversing the order of some
+        for (i, &id) in ids.iter().enumerate() {
+            let x = ((i * 37) % 50) as f64 * 0.8 - (i % 7) as f64 * 1.5;
+            bp.move_proxy(id, &aabb(x, (i % 3) as f64 * 0.8), Vec2d::new(-1.5, 0.0));
+        }
+        record(bp, &ids);
+
+        for &id in ids.iter().step_by(4) {
+            bp.destroy_proxy(id);
+        }
+        let remaining: Vec<_> = ids.iter()
+                                   .enumerate()
+                                   .filter(|&(i, _)| i % 4 != 0)
+                                   .map(|(_, &id)| id)
+                                   .collect();
+        assert!(bp.moved_pairs().is_empty());
+        for &id in &remaining {
+            bp.touch_proxy(id);
+        }
+        record(bp, &remaining);
+        results
+    }
+
+    #[test]
+    fn test_sweep_and_prune_matches_tree() {
+        let mut sap = SweepAndPrune::new();
+        let mut tree = DynamicTree::new();
+        let sap_results = run(&mut sap);
+        assert_eq!(run(&mut tree), sap_results);
+        assert!(sap_results.iter().all(|pairs| !pairs.is_empty()));
+        assert_eq!(37, sap.proxy_count());
+
+        let mut count = 0;
+        sap.query(&aabb(0.0, 0.0), &mut |_| {
+            count += 1;
+            true
+        });
+        assert!(count > 0);
+    }
+
+    #[test]
+    fn test_sweep_and_prune_query_matches_tree() {
+        let mut sap = SweepAndPrune::new();
+        let mut tree = DynamicTree::new();
+        run(&mut sap);
+        run(&mut tree);
+
+        // proxy ids differ between broad phases, so compare the found
+        // fattened bounds which both derive from the same scene
+        let collect = |bp: &dyn BroadPhase, query: &Aabb| {
+            let mut proxies = Vec::new();
+            bp.query(query, &mut |proxy| {
+                proxies.push(proxy);
+                true
+            });
+            let mut found: Vec<(f64, f64, f64, f64)> = proxies.iter()
+                                                          .map(|&p| bp.fat_aabb(p))
+                                                          .map(|a| (a.min().x, a.min().y, a.max().x, a.max().y))
+                                                          .collect();
+            found.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            found
+        };
+        let mut total = 0;
+        for i in 0..20 {
+            let query = aabb(i as f64 * 2.0 - 5.0, (i % 3) as f64);
+            let found = collect(&sap, &query);
+            assert_eq!(collect(&tree, &query), found);
+            total += found.len();
+        }
+        assert!(total > 0);
+    }
+}
@@ -0,0 +1,11 @@
+use collision::{CollidesWith, Manifold};
+use common::Transform;
+
+/// Trait represents objects that can produce contact
+/// information when colliding with other objects
+pub trait ContactsWith<T> : CollidesWith<T> {
+    /// Returns the contact manifold between this object given transform `this_t`
+    /// and `other` given transform `other_t` or `None` if the objects do not collide.
+    /// The manifold normal points from this object towards `other`
+    fn manifold(&self, other: &T, this_t: &Transform, other_t: &Transform) -> Option<Manifold>;
+}
\ No newline at end of file
@@ -0,0 +1,351 @@
+use collision::HasSupport;
+use common::{Transform, Vec2d};
+use util;
+
+/// The maximum number of iterations of the GJK algorithm
+const MAX_ITERATIONS: usize = 20;
+
+/// DistanceOutput holds the result of a distance query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceOutput {
+    /// The distance between the shapes or 0 if they overlap
+    pub distance: f64,
+    /// The point on the first shape closest to the second shape in world space
+    pub point_a: Vec2d,
+    /// The point on the second shape closest to the first shape in world space
+    pub point_b: Vec2d,
+    /// The number of GJK iterations used
+    pub iterations: usize,
+}
+
+/// A vertex of the simplex on the minkowski difference `b - a`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SimplexVertex {
+    /// Support point on the first shape in world space
+    pub wa: Vec2d,
+    /// Support point on the second shape in world space
+    pub wb: Vec2d,
+    /// `wb - wa`
+    pub w: Vec2d,
+    /// Barycentric coordinate of the closest point
+    a: f64,
+}
+
+impl SimplexVertex {
+    /// Returns the simplex vertex for the support points of `a` and `b`
+    /// given transforms `at` and `bt` along `direction` in world space
+    pub fn new<A, B>(a: &A, at: &Transform, b: &B, bt: &Transform, direction: Vec2d) -> SimplexVertex
+        where A: HasSupport,
+              B: HasSupport
+    {
+        let wa = a.support((-direction).inv_rotate(at.rotation())).transform(at);
+        let wb = b.support(direction.inv_rotate(bt.rotation())).transform(bt);
+        SimplexVertex {
+            wa,
+            wb,
+            w: wb - wa,
+            a: 1.0,
+        }
+    }
+}
+
+/// A simplex of up to three vertices on the minkowski difference
+/// of two shapes, reduced towards the origin by the GJK algorithm
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Simplex {
+    pub v: [SimplexVertex; 3],
+    pub count: usize,
+}
+
+impl Simplex {
+    /// Returns the vertices of the simplex
+    pub fn vertices(&self) -> &[SimplexVertex] {
+        &self.v[..self.count]
+    }
+
+    /// Returns the direction from the simplex towards the origin
+    fn search_direction(&self) -> Vec2d {
+        match self.count {
+            1 => -self.v[0].w,
+            _ => {
+                let e12 = self.v[1].w - self.v[0].w;
+                if e12.cross(-self.v[0].w) > 0.0 {
+                    // origin is left of e12
+                    e12.perp()
+                } else {
+                    -e12.perp()
+                }
+            }
+        }
+    }
+
+    /// Returns the closest points on the first and second shape
+    pub fn witness_points(&self) -> (Vec2d, Vec2d) {
+        let mut pa = Vec2d::zero();
+        let mut pb = Vec2d::zero();
+        for v in self.vertices() {
+            pa += v.wa * v.a;
+            pb += v.wb * v.a;
+        }
+        if self.count == 3 {
+            // the origin is enclosed, the points coincide
+            pb = pa;
+        }
+        (pa, pb)
+    }
+
+    /// Reduces a line segment to the sub-simplex closest to the origin
+    fn solve2(&mut self) {
+        let w1 = self.v[0].w;
+        let w2 = self.v[1].w;
+        let e12 = w2 - w1;
+
+        // w1 region
+        let d12_2 = -(w1 * e12);
+        if d12_2 <= 0.0 {
+            self.v[0].a = 1.0;
+            self.count = 1;
+            return;
+        }
+
+        // w2 region
+        let d12_1 = w2 * e12;
+        if d12_1 <= 0.0 {
+            self.v[1].a = 1.0;
+            self.v[0] = self.v[1];
+            self.count = 1;
+            return;
+        }
+
+        // must be in e12 region
+        let inv = 1.0 / (d12_1 + d12_2);
+        self.v[0].a = d12_1 * inv;
+        self.v[1].a = d12_2 * inv;
+        self.count = 2;
+    }
+
+    /// Reduces a triangle to the sub-simplex closest to the origin
+    /// using the voronoi regions of its vertices and edges
+    fn solve3(&mut self) {
+        let w1 = self.v[0].w;
+        let w2 = self.v[1].w;
+        let w3 = self.v[2].w;
+
+        let e12 = w2 - w1;
+        let d12_1 = w2 * e12;
+        let d12_2 = -(w1 * e12);
+
+        let e13 = w3 - w1;
+        let d13_1 = w3 * e13;
+        let d13_2 = -(w1 * e13);
+
+        let e23 = w3 - w2;
+        let d23_1 = w3 * e23;
+        let d23_2 = -(w2 * e23);
+
+        let n123 = e12.cross(e13);
+        let d123_1 = n123 * w2.cross(w3);
+        let d123_2 = n123 * w3.cross(w1);
+        let d123_3 = n123 * w1.cross(w2);
+
+        if d12_2 <= 0.0 && d13_2 <= 0.0 {
+            // w1 region
+            self.v[0].a = 1.0;
+            self.count = 1;
+        } else if d12_1 > 0.0 && d12_2 > 0.0 && d123_3 <= 0.0 {
+            // e12 region
+            let inv = 1.0 / (d12_1 + d12_2);
+            self.v[0].a = d12_1 * inv;
+            self.v[1].a = d12_2 * inv;
+            self.count = 2;
+        } else if d13_1 > 0.0 && d13_2 > 0.0 && d123_2 <= 0.0 {
+            // e13 region
+            let inv = 1.0 / (d13_1 + d13_2);
+            self.v[0].a = d13_1 * inv;
+            self.v[2].a = d13_2 * inv;
+            self.v[1] = self.v[2];
+            self.count = 2;
+        } else if d12_1 <= 0.0 && d23_2 <= 0.0 {
+            // w2 region
+            self.v[1].a = 1.0;
+            self.v[0] = self.v[1];
+            self.count = 1;
+        } else if d13_1 <= 0.0 && d23_1 <= 0.0 {
+            // w3 region
+            self.v[2].a = 1.0;
+            self.v[0] = self.v[2];
+            self.count = 1;
+        } else if d23_1 > 0.0 && d23_2 > 0.0 && d123_1 <= 0.0 {
+            // e23 region
+            let inv = 1.0 / (d23_1 + d23_2);
+            self.v[1].a = d23_1 * inv;
+            self.v[2].a = d23_2 * inv;
+            self.v[0] = self.v[2];
+            self.count = 2;
+        } else {
+            // must be in triangle123
+            let inv = 1.0 / (d123_1 + d123_2 + d123_3);
+            self.v[0].a = d123_1 * inv;
+            self.v[1].a = d123_2 * inv;
+            self.v[2].a = d123_3 * inv;
+            self.count = 3;
+        }
+    }
+}
+
+/// Runs the GJK algorithm on the core shapes of `a` and `b`, ignoring their
+/// radii, returning the final simplex and the number of iterations used.
+/// If the simplex has three vertices, the core shapes overlap.
+/// Algorithm sourced from Erin Catto's Box2D: https://github.com/erincatto/Box2D
+pub(crate) fn gjk<A, B>(a: &A, at: &Transform, b: &B, bt: &Transform) -> (Simplex, usize)
+    where A: HasSupport,
+          B: HasSupport
+{
+    let first = SimplexVertex::new(a, at, b, bt, Vec2d::new(1.0, 0.0));
+    let mut simplex = Simplex {
+        v: [first; 3],
+        count: 1,
+    };
+
+    let mut iterations = 0;
+    while iterations < MAX_ITERATIONS {
+        // keep the current vertices to detect cycling
+        let saved = simplex;
+
+        match simplex.count {
+            2 => simplex.solve2(),
+            3 => simplex.solve3(),
+            _ => {}
+        }
+        if simplex.count == 3 {
+            break;
+        }
+
+        let d = simplex.search_direction();
+        if d.len_sq() < util::TOLERANCE * util::TOLERANCE {
+            // the origin is on the simplex, the shapes touch
+            break;
+        }
+
+        let vertex = SimplexVertex::new(a, at, b, bt, d);
+        iterations += 1;
+
+        // a repeated support point means no further progress can be made
+        let duplicate = saved.vertices()
+                             .iter()
+                             .any(|v| v.wa == vertex.wa && v.wb == vertex.wb);
+        if duplicate {
+            break;
+        }
+
+        simplex.v[simplex.count] = vertex;
+        simplex.count += 1;
+    }
+    (simplex, iterations)
+}
+
+/// Computes the distance between the shapes `a` given transform `at` and `b`
+/// given transform `bt` along with the closest points on each shape using
+/// the GJK algorithm. If the shapes overlap the distance is 0 and both
+/// points lie within the overlap
+pub fn distance<A, B>(a: &A, at: &Transform, b: &B, bt: &Transform) -> DistanceOutput
+    where A: HasSupport,
+          B: HasSupport
+{
+    let (simplex, iterations) = gjk(a, at, b, bt);
+    let (mut point_a, mut point_b) = simplex.witness_points();
+    let mut dist = (point_b - point_a).len();
+
+    // apply the radii of the shapes
+    let ra = a.radius();
+    let rb = b.radius();
+    if dist > ra + rb && dist > util::TOLERANCE {
+        let normal = (point_b - point_a) / dist;
+        dist -= ra + rb;
+        point_a += normal * ra;
+        point_b -= normal * rb;
+    } else {
+        let p = (point_a + point_b) * 0.5;
+        point_a = p;
+        point_b = p;
+        dist = 0.0;
+    }
+
+    DistanceOutput {
+        distance: dist,
+        point_a,
+        point_b,
+        iterations,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+    use collision::distance;
+    use collision::shapes::{Circle, Convex};
+    use common::{Rotation, Transform, Vec2d};
+
+    fn square(half: f64) -> Convex {
+        Convex::new(&[Vec2d::new(-half, -half),
+                      Vec2d::new(half, -half),
+                      Vec2d::new(half, half),
+                      Vec2d::new(-half, half)])
+            .unwrap()
+    }
+
+    fn at(x: f64, y: f64, angle: f64) -> Transform {
+        Transform::new(Vec2d::new(x, y), Rotation::new(angle))
+    }
+
+    fn approx(v: Vec2d, x: f64, y: f64) -> bool {
+        (v.x - x).abs() < 1e-9 && (v.y - y).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_distance_convex() {
+        let a = square(1.0);
+        let b = square(0.5);
+
+        // face to face
+        let out = distance(&a, &at(0.0, 0.0, 0.0), &b, &at(3.0, 0.2, 0.0));
+        assert!((out.distance - 1.5).abs() < 1e-9);
+        assert!((out.point_a.x - 1.0).abs() < 1e-9);
+        assert!((out.point_b.x - 2.5).abs() < 1e-9);
+        assert!((out.point_a.y - out.point_b.y).abs() < 1e-9);
+
+        // corner to corner
+        let out = distance(&a, &at(0.0, 0.0, 0.0), &b, &at(3.5, 3.5, 0.0));
+        assert!((out.distance - 2.0f64.sqrt() * 2.0).abs() < 1e-9);
+        assert!(approx(out.point_a, 1.0, 1.0));
+        assert!(approx(out.point_b, 3.0, 3.0));
+
+        // rotated corner to face
+        let out = distance(&a, &at(0.0, 0.0, PI / 4.0), &b, &at(3.0, 0.0, 0.0));
+        assert!((out.distance - (2.5 - 2.0f64.sqrt())).abs() < 1e-9);
+        assert!(approx(out.point_a, 2.0f64.sqrt(), 0.0));
+
+        // overlapping
+        let out = distance(&a, &at(0.0, 0.0, 0.0), &b, &at(1.2, 0.3, 0.3));
+        assert_eq!(0.0, out.distance);
+    }
+
+    #[test]
+    fn test_distance_circle() {
+        let c = Circle::new(Vec2d::new(0.5, 0.0), 1.0).unwrap();
+        let s = square(1.0);
+
+        let out = distance(&c, &at(3.0, 0.0, 0.0), &s, &at(0.0, 0.0, 0.0));
+        assert!((out.distance - 1.5).abs() < 1e-9);
+        assert!(approx(out.point_a, 2.5, 0.0));
+        assert!(approx(out.point_b, 1.0, 0.0));
+
+        let out = distance(&c, &at(0.0, 0.0, 0.0), &c, &at(0.0, 5.0, 0.0));
+        assert!((out.distance - 3.0).abs() < 1e-9);
+        assert!(approx(out.point_a, 0.5, 1.0));
+        assert!(approx(out.point_b, 0.5, 4.0));
+
+        let out = distance(&c, &at(1.0, 0.0, 0.0), &s, &at(0.0, 0.0, 0.0));
+        assert_eq!(0.0, out.distance);
+    }
+}
@@ -0,0 +1,20 @@
+use common::Vec2d;
+
+/// Trait representing convex objects described by a support function.
+///
+/// # Remarks
+///
+/// The object is the core shape mapped by `support` inflated by
+/// `radius`, e.g. a circle is a single point inflated by its radius.
+/// Keeping the radius separate lets algorithms treat curved shapes
+/// exactly instead of approximating them with many support points
+pub trait HasSupport {
+    /// Returns the point of the core shape in local space
+    /// furthest along `direction`, given in local space
+    fn support(&self, direction: Vec2d) -> Vec2d;
+
+    /// Returns the radius the core shape is inflated by
+    fn radius(&self) -> f64 {
+        0.0
+    }
+}
\ No newline at end of file
@@ -0,0 +1,225 @@
+use common::{Transform, Vec2d};
+use util;
+
+/// The type of geometric feature that took part
+/// in producing a contact point
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeatureType {
+    Vertex,
+    Face,
+}
+
+/// ContactId identifies the features on both shapes that
+/// produced a contact point. Ids are stable across steps as long
+/// as the same features remain in contact which allows contact
+/// information to be matched from one step to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContactId {
+    /// Index of the feature on the first shape
+    pub index_a: usize,
+    /// Index of the feature on the second shape
+    pub index_b: usize,
+    /// Type of the feature on the first shape
+    pub type_a: FeatureType,
+    /// Type of the feature on the second shape
+    pub type_b: FeatureType,
+}
+
+impl ContactId {
+    /// Returns the id with the roles of the first
+    /// and second shapes swapped
+    pub fn flip(&self) -> ContactId {
+        ContactId {
+            index_a: self.index_b,
+            index_b: self.index_a,
+            type_a: self.type_b,
+            type_b: self.type_a,
+        }
+    }
+}
+
+/// The type of reference geometry a manifold was built against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifoldKind {
+    /// The contact is between two points, each inflated by a radius
+    Circles,
+    /// The reference face belongs to the first shape
+    FaceA,
+    /// The reference face belongs to the second shape
+    FaceB,
+}
+
+/// A single contact point of a `Manifold`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ManifoldPoint {
+    point: Vec2d,
+    depth: f64,
+    local_point: Vec2d,
+    id: ContactId,
+}
+
+impl ManifoldPoint {
+    /// Returns the contact point in world space, midway
+    /// between the surfaces of both shapes
+    pub fn point(&self) -> &Vec2d {
+        &self.point
+    }
+
+    /// Returns the penetration depth at this point. Positive
+    /// values mean the shapes overlap
+    pub fn depth(&self) -> f64 {
+        self.depth
+    }
+
+    /// Returns the contact point on the incident shape in the local
+    /// space of the incident shape. For `ManifoldKind::Circles` this
+    /// is the center of the second shape
+    pub fn local_point(&self) -> &Vec2d {
+        &self.local_point
+    }
+
+    /// Returns the id of the features that produced this point
+    pub fn id(&self) -> ContactId {
+        self.id
+    }
+}
+
+/// Manifold contains the contact information of two colliding shapes:
+/// a contact normal pointing from the first shape to the second shape
+/// and up to two contact points.
+///
+/// # Remarks
+///
+/// Along with the world space results, the manifold keeps the reference
+/// face and contact points in local space so that the contact may be
+/// re-evaluated cheaply after the shapes have moved
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Manifold {
+    kind: ManifoldKind,
+    normal: Vec2d,
+    local_normal: Vec2d,
+    local_point: Vec2d,
+    radius_a: f64,
+    radius_b: f64,
+    points: [ManifoldPoint; 2],
+    point_count: usize,
+}
+
+impl Manifold {
+    /// Creates a new manifold against the reference face described by
+    /// `local_normal` and `local_point` from the incident points given
+    /// in the local space of the incident shape. Each shape is inflated
+    /// by its radius. `at` and `bt` are the transforms of the first and
+    /// second shape respectively
+    pub(crate) fn new(kind: ManifoldKind,
+                      local_normal: Vec2d,
+                      local_point: Vec2d,
+                      radii: (f64, f64),
+                      incident: &[(Vec2d, ContactId)],
+                      at: &Transform,
+                      bt: &Transform)
+                      -> Manifold {
+        debug_assert!(!incident.is_empty() && incident.len() <= 2);
+
+        let empty = ManifoldPoint {
+            point: Vec2d::zero(),
+            depth: 0.0,
+            local_point: Vec2d::zero(),
+            id: incident[0].1,
+        };
+        let mut manifold = Manifold {
+            kind,
+            normal: Vec2d::zero(),
+            local_normal,
+            local_point,
+            radius_a: radii.0,
+            radius_b: radii.1,
+            points: [empty; 2],
+            point_count: incident.len(),
+        };
+        for (i, &(local, id)) in incident.iter().enumerate() {
+            let (normal, point, separation) = manifold.evaluate(local, at, bt);
+            manifold.normal = normal;
+            manifold.points[i] = ManifoldPoint {
+                point,
+                depth: -separation,
+                local_point: local,
+                id,
+            };
+        }
+        manifold
+    }
+
+    /// Returns the kind of reference geometry of this manifold
+    pub fn kind(&self) -> ManifoldKind {
+        self.kind
+    }
+
+    /// Returns the contact normal in world space pointing from the
+    /// first shape towards the second shape
+    pub fn normal(&self) -> &Vec2d {
+        &self.normal
+    }
+
+    /// Returns the normal of the reference face in the local
+    /// space of the reference shape
+    pub fn local_normal(&self) -> &Vec2d {
+        &self.local_normal
+    }
+
+    /// Returns the center of the reference face in the local
+    /// space of the reference shape. For `ManifoldKind::Circles`
+    /// this is the center of the first shape
+    pub fn local_point(&self) -> &Vec2d {
+        &self.local_point
+    }
+
+    /// Returns the contact points of this manifold
+    pub fn points(&self) -> &[ManifoldPoint] {
+        &self.points[..self.point_count]
+    }
+
+    /// Returns the maximum penetration depth over all contact points
+    pub fn depth(&self) -> f64 {
+        self.points().iter().fold(0.0, |depth, p| depth.max(p.depth))
+    }
+
+    /// Evaluates the contact for the incident point `local` under the
+    /// transforms `at` and `bt`, returning the world normal pointing from
+    /// the first to the second shape, the world contact point and the
+    /// separation of the shapes along the normal
+    pub(crate) fn evaluate(&self,
+                           local: Vec2d,
+                           at: &Transform,
+                           bt: &Transform)
+                           -> (Vec2d, Vec2d, f64) {
+        if self.kind == ManifoldKind::Circles {
+            let center_a = self.local_point.transform(at);
+            let center_b = local.transform(bt);
+            let d = center_b - center_a;
+            let normal = if d.len_sq() > util::TOLERANCE * util::TOLERANCE {
+                d.normalize()
+            } else {
+                Vec2d::new(1.0, 0.0)
+            };
+            let surface_a = center_a + normal * self.radius_a;
+            let surface_b = center_b - normal * self.radius_b;
+            return (normal, (surface_a + surface_b) * 0.5, (surface_b - surface_a) * normal);
+        }
+
+        let (ref_t, inc_t, ref_r, inc_r, sign) = match self.kind {
+            ManifoldKind::FaceB => (bt, at, self.radius_b, self.radius_a, -1.0),
+            _ => (at, bt, self.radius_a, self.radius_b, 1.0),
+        };
+        let normal = self.local_normal.rotate(ref_t.rotation());
+        let plane_point = self.local_point.transform(ref_t);
+        let clip_point = local.transform(inc_t);
+        let separation = (clip_point - plane_point) * normal - ref_r - inc_r;
+
+        // midway between the surfaces of the reference
+        // and incident shapes
+        let surface_ref = clip_point - normal * (separation + inc_r);
+        let surface_inc = clip_point - normal * inc_r;
+        (normal * sign, (surface_ref + surface_inc) * 0.5, separation)
+    }
+}
@@ -1,9 +1,24 @@
 pub use self::aabb::Aabb;
 pub use self::collides_with::CollidesWith;
+pub use self::contacts_with::ContactsWith;
+pub use self::distance::{distance, DistanceOutput};
 pub use self::has_aabb::HasAabb;
+pub use self::has_support::HasSupport;
+pub use self::manifold::{ContactId, FeatureType, Manifold, ManifoldKind, ManifoldPoint};
+pub use self::penetration::{penetration, PenetrationOutput};
+pub use self::ray_cast::{RayCast, RayCastInput, RayCastOutput};
+pub use self::time_of_impact::{time_of_impact, Sweep, ToiOutput, ToiState};
 
+pub mod broad_phase;
 pub mod shapes;
 
 mod aabb;
 mod collides_with;
-mod has_aabb;
\ No newline at end of file
+mod contacts_with;
+mod distance;
+mod has_aabb;
+mod has_support;
+mod manifold;
+mod penetration;
+mod ray_cast;
+mod time_of_impact;
\ No newline at end of file
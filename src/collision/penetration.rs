@@ -0,0 +1,206 @@
+use std::vec::Vec;
+use collision::HasSupport;
+use collision::distance::{self, SimplexVertex};
+use common::{Transform, Vec2d};
+use util;
+
+/// The maximum number of iterations of the EPA algorithm
+const MAX_ITERATIONS: usize = 32;
+
+/// PenetrationOutput holds the result of a penetration query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenetrationOutput {
+    /// The unit direction from the first shape to the second
+    /// shape along which the shapes overlap the least
+    pub normal: Vec2d,
+    /// The overlap of the shapes along `normal`
+    pub depth: f64,
+    /// The deepest point of the first shape inside the second shape
+    pub point_a: Vec2d,
+    /// The deepest point of the second shape inside the first shape
+    pub point_b: Vec2d,
+}
+
+impl PenetrationOutput {
+    /// Returns the minimum translation vector: the smallest translation
+    /// of the second shape that separates the shapes. Translating the
+    /// first shape by the negated vector separates the shapes as well
+    pub fn translation(&self) -> Vec2d {
+        self.normal * self.depth
+    }
+}
+
+/// Computes how deep the shapes `a` given transform `at` and `b` given
+/// transform `bt` overlap, returning `None` if the shapes do not overlap.
+///
+/// # Remarks
+///
+/// GJK is run on the core shapes first. If the cores are apart, the shapes
+/// only overlap by their radii. Otherwise the expanding polytope algorithm
+/// grows the final GJK simplex into the minkowski difference `b - a` until
+/// the face closest to the origin is found, which gives the direction and
+/// depth of least overlap of the cores
+pub fn penetration<A, B>(a: &A, at: &Transform, b: &B, bt: &Transform) -> Option<PenetrationOutput>
+    where A: HasSupport,
+          B: HasSupport
+{
+    let radii = a.radius() + b.radius();
+    let (simplex, _) = distance::gjk(a, at, b, bt);
+
+    if simplex.count < 3 {
+        // the cores are apart or touching, only the radii can overlap
+        let (core_a, core_b) = simplex.witness_points();
+        let d = core_b - core_a;
+        let dist = d.len();
+        if dist >= radii && dist > util::TOLERANCE {
+            return None;
+        }
+
+        let normal = if dist > util::TOLERANCE {
+            d / dist
+        } else {
+            // the cores touch, fall back to the direction between origins
+            let d = *bt.position() - *at.position();
+            if d.len_sq() > util::TOLERANCE {
+                d.normalize()
+            } else {
+                Vec2d::new(1.0, 0.0)
+            }
+        };
+        return Some(PenetrationOutput {
+            normal,
+            depth: radii - dist,
+            point_a: core_a + normal * a.radius(),
+            point_b: core_b - normal * b.radius(),
+        });
+    }
+
+    // counter-clockwise polytope on the minkowski difference
+    let mut polytope: Vec<SimplexVertex> = simplex.vertices().to_vec();
+    if (polytope[1].w - polytope[0].w).cross(polytope[2].w - polytope[0].w) < 0.0 {
+        polytope.swap(1, 2);
+    }
+
+    let mut best = closest_edge(&polytope);
+    for _ in 0..MAX_ITERATIONS {
+        let (i, n, dist) = best;
+        let vertex = SimplexVertex::new(a, at, b, bt, n);
+        if vertex.w * n - dist < util::LINEAR_SLOP * 0.01 {
+            break;
+        }
+        polytope.insert(i + 1, vertex);
+        best = closest_edge(&polytope);
+    }
+
+    // the closest point on the edge gives the deepest points of the cores
+    let (i, n, dist) = best;
+    let v1 = polytope[i];
+    let v2 = polytope[(i + 1) % polytope.len()];
+    let e = v2.w - v1.w;
+    let t = if e.len_sq() > util::TOLERANCE {
+        (-(v1.w * e) / e.len_sq()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let core_a = v1.wa + (v2.wa - v1.wa) * t;
+    let core_b = v1.wb + (v2.wb - v1.wb) * t;
+
+    // the origin is pushed out of the minkowski difference by moving
+    // the second shape against the outward normal of the closest face
+    let normal = -n;
+    Some(PenetrationOutput {
+        normal,
+        depth: dist + radii,
+        point_a: core_a + normal * a.radius(),
+        point_b: core_b - normal * b.radius(),
+    })
+}
+
+/// Returns the index, outward unit normal and distance to the
+/// origin of the edge of the polytope closest to the origin
+fn closest_edge(polytope: &[SimplexVertex]) -> (usize, Vec2d, f64) {
+    let mut best = (0, Vec2d::zero(), f64::MAX);
+    for i in 0..polytope.len() {
+        let p1 = polytope[i].w;
+        let p2 = polytope[(i + 1) % polytope.len()].w;
+        let e = p2 - p1;
+        if e.len_sq() < util::TOLERANCE * util::TOLERANCE {
+            continue;
+        }
+        let n = Vec2d::new(e.y, -e.x).normalize();
+        let dist = n * p1;
+        if dist < best.2 {
+            best = (i, n, dist);
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+    use collision::{distance, penetration, CollidesWith};
+    use collision::shapes::{Circle, Convex};
+    use common::{Rotation, Transform, Vec2d};
+
+    fn square(half: f64) -> Convex {
+        Convex::new(&[Vec2d::new(-half, -half),
+                      Vec2d::new(half, -half),
+                      Vec2d::new(half, half),
+                      Vec2d::new(-half, half)])
+            .unwrap()
+    }
+
+    fn at(x: f64, y: f64, angle: f64) -> Transform {
+        Transform::new(Vec2d::new(x, y), Rotation::new(angle))
+    }
+
+    #[test]
+    fn test_penetration_convex() {
+        let a = square(1.0);
+        let b = square(0.5);
+
+        let out = penetration(&a, &at(0.0, 0.0, 0.0), &b, &at(1.2, 0.3, 0.0)).unwrap();
+        assert!((out.depth - 0.3).abs() < 1e-9);
+        assert!((out.normal.x - 1.0).abs() < 1e-9 && out.normal.y.abs() < 1e-9);
+        assert!((out.point_a.x - 1.0).abs() < 1e-9);
+        assert!((out.point_b.x - 0.7).abs() < 1e-9);
+
+        // the minimum translation vector separates the shapes
+        let (at_a, mut at_b) = (at(0.0, 0.0, 0.0), at(0.4, -1.1, 0.3));
+        let out = penetration(&a, &at_a, &b, &at_b).unwrap();
+        assert!(out.normal.y < 0.0);
+        at_b.translate(out.translation() * 1.001);
+        assert!(!a.collides_with(&b, &at_a, &at_b));
+        assert!(distance(&a, &at_a, &b, &at_b).distance < 1e-2);
+
+        // rotated corner into a face
+        let out = penetration(&a, &at(0.0, 0.0, PI / 4.0), &b, &at(1.8, 0.0, 0.0)).unwrap();
+        assert!((out.depth - (2.0f64.sqrt() - 1.3)).abs() < 1e-9);
+
+        assert!(penetration(&a, &at(0.0, 0.0, 0.0), &b, &at(3.0, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn test_penetration_circle() {
+        let c = Circle::new(Vec2d::zero(), 1.0).unwrap();
+        let s = square(1.0);
+
+        let out = penetration(&c, &at(0.0, 0.0, 0.0), &c, &at(0.0, 1.5, 0.0)).unwrap();
+        assert!((out.depth - 0.5).abs() < 1e-9);
+        assert!((out.normal.y - 1.0).abs() < 1e-9);
+        assert!((out.point_a.y - 1.0).abs() < 1e-9);
+        assert!((out.point_b.y - 0.5).abs() < 1e-9);
+
+        // circle center inside the square
+        let out = penetration(&s, &at(0.0, 0.0, 0.0), &c, &at(0.8, 0.1, 0.0)).unwrap();
+        assert!((out.depth - 1.2).abs() < 1e-9);
+        assert!((out.normal.x - 1.0).abs() < 1e-9);
+
+        // circle center outside the square
+        let out = penetration(&s, &at(0.0, 0.0, 0.0), &c, &at(1.5, 0.0, 0.0)).unwrap();
+        assert!((out.depth - 0.5).abs() < 1e-9);
+
+        assert!(penetration(&s, &at(0.0, 0.0, 0.0), &c, &at(2.5, 0.0, 0.0)).is_none());
+    }
+}
@@ -0,0 +1,107 @@
+use common::{Transform, Vec2d};
+
+/// RayCastInput describes a ray cast from `p1` towards `p2`,
+/// ending at `p1 + max_fraction * (p2 - p1)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayCastInput {
+    /// The start of the ray in world space
+    pub p1: Vec2d,
+    /// The point the ray passes through at fraction 1
+    pub p2: Vec2d,
+    /// The fraction of `p2 - p1` at which the ray ends
+    pub max_fraction: f64,
+}
+
+impl RayCastInput {
+    /// Creates a ray cast along the segment from `p1` to `p2`
+    pub fn new(p1: Vec2d, p2: Vec2d) -> RayCastInput {
+        RayCastInput {
+            p1,
+            p2,
+            max_fraction: 1.0,
+        }
+    }
+
+    /// Returns the point on the ray at the given fraction
+    pub fn point_at(&self, fraction: f64) -> Vec2d {
+        self.p1 + (self.p2 - self.p1) * fraction
+    }
+
+    /// Returns a hit at the start of the ray, used when the
+    /// ray starts inside a shape. The normal opposes the ray
+    pub(crate) fn inside(&self) -> RayCastOutput {
+        let d = self.p2 - self.p1;
+        RayCastOutput {
+            fraction: 0.0,
+            point: self.p1,
+            normal: if d.len_sq() > 0.0 {
+                -d.normalize()
+            } else {
+                Vec2d::zero()
+            },
+        }
+    }
+}
+
+/// RayCastOutput holds where a ray first hit a shape
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayCastOutput {
+    /// The fraction of `p2 - p1` at which the ray hit the shape,
+    /// 0 if the ray started inside the shape
+    pub fraction: f64,
+    /// The point the ray hit the shape in world space
+    pub point: Vec2d,
+    /// The unit surface normal of the shape at the hit point
+    /// in world space
+    pub normal: Vec2d,
+}
+
+/// Trait representing shapes that can be hit by a ray
+pub trait RayCast {
+    /// Casts the ray against this shape given a transform, returning
+    /// the first hit along the ray or `None` if the ray misses the
+    /// shape. Rays starting inside the shape hit at fraction 0
+    fn ray_cast(&self, input: &RayCastInput, transform: &Transform) -> Option<RayCastOutput>;
+}
+
+#[cfg(test)]
+mod test {
+    use collision::{Aabb, RayCastInput};
+    use common::Vec2d;
+
+    #[test]
+    fn test_aabb_ray_cast() {
+        let aabb = Aabb::from_bounds(Vec2d::new(-1.0, -1.0), Vec2d::new(1.0, 2.0));
+
+        let hit = aabb.ray_cast(&RayCastInput::new(Vec2d::new(-3.0, 0.0), Vec2d::new(1.0, 0.0)))
+                      .unwrap();
+        assert!((hit.fraction - 0.5).abs() < 1e-9);
+        assert_eq!(Vec2d::new(-1.0, 0.0), hit.normal);
+        assert!((hit.point.x + 1.0).abs() < 1e-9);
+
+        // diagonal ray entering through the top face
+        let hit = aabb.ray_cast(&RayCastInput::new(Vec2d::new(0.0, 4.0), Vec2d::new(1.0, 0.0)))
+                      .unwrap();
+        assert!((hit.fraction - 0.5).abs() < 1e-9);
+        assert_eq!(Vec2d::new(0.0, 1.0), hit.normal);
+
+        // too short, pointing away and parallel outside the slab
+        assert!(aabb.ray_cast(&RayCastInput::new(Vec2d::new(-3.0, 0.0), Vec2d::new(-2.0, 0.0)))
+                    .is_none());
+        assert!(aabb.ray_cast(&RayCastInput::new(Vec2d::new(-3.0, 0.0), Vec2d::new(-5.0, 0.0)))
+                    .is_none());
+        assert!(aabb.ray_cast(&RayCastInput::new(Vec2d::new(-3.0, 3.0), Vec2d::new(3.0, 3.0)))
+                    .is_none());
+
+        // a ray starting inside hits immediately
+        let hit = aabb.ray_cast(&RayCastInput::new(Vec2d::new(0.0, 0.0), Vec2d::new(5.0, 0.0)))
+                      .unwrap();
+        assert_eq!(0.0, hit.fraction);
+        assert_eq!(Vec2d::new(0.0, 0.0), hit.point);
+        assert_eq!(Vec2d::new(-1.0, 0.0), hit.normal);
+
+        let mut input = RayCastInput::new(Vec2d::new(-3.0, 0.0), Vec2d::new(-2.0, 0.0));
+        input.max_fraction = 3.0;
+        assert!((aabb.ray_cast(&input).unwrap().fraction - 2.0).abs() < 1e-9);
+    }
+}
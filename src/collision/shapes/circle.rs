@@ -0,0 +1,413 @@
+use std::f64;
+use std::f64::consts::PI;
+use std::result::Result;
+use collision::{Aabb, CollidesWith, ContactId, ContactsWith, FeatureType, HasAabb, HasSupport,
+                Manifold, ManifoldKind, RayCast, RayCastInput, RayCastOutput};
+use collision::shapes::{Convex, MassData};
+use common::{Transform, Vec2d};
+use util;
+
+/// Circle represents a solid circle with a center
+/// offset from the origin of its local space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    center: Vec2d,
+    radius: f64,
+}
+
+impl Circle {
+    /// Creates a circle at the given local center with the given radius
+    /// or returns an error if the radius is not positive
+    pub fn new(center: Vec2d, radius: f64) -> Result<Circle, ()> {
+        if radius <= 0.0 || !radius.is_finite() {
+            return Err(());
+        }
+        Ok(Circle { center, radius })
+    }
+
+    /// Returns the center of the circle in local space
+    pub fn center(&self) -> &Vec2d {
+        &self.center
+    }
+
+    /// Returns the radius of the circle
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Computes the area, centroid and rotational inertia about
+    /// the centroid of this circle for the given density
+    pub fn mass_data(&self, density: f64) -> MassData {
+        let area = PI * self.radius * self.radius;
+        let mass = density * area;
+        MassData {
+            area,
+            mass,
+            center: self.center,
+            inertia: 0.5 * mass * self.radius * self.radius,
+        }
+    }
+}
+
+impl HasAabb for Circle {
+    fn aabb(&self, transform: &Transform) -> Aabb {
+        let center = self.center.transform(transform);
+        let extent = Vec2d::new(self.radius, self.radius);
+        Aabb::from_bounds(center - extent, center + extent)
+    }
+}
+
+impl HasSupport for Circle {
+    fn support(&self, _: Vec2d) -> Vec2d {
+        self.center
+    }
+
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+impl RayCast for Circle {
+    fn ray_cast(&self, input: &RayCastInput, transform: &Transform) -> Option<RayCastOutput> {
+        // solve |s + t * d| = radius for the smallest t
+        let center = self.center.transform(transform);
+        let s = input.p1 - center;
+        let b = s * s - self.radius * self.radius;
+        if b < 0.0 {
+            return Some(input.inside());
+        }
+
+        let d = input.p2 - input.p1;
+        let c = s * d;
+        let rr = d * d;
+        let sigma = c * c - rr * b;
+        if sigma < 0.0 || rr < f64::EPSILON {
+            return None;
+        }
+
+        let t = -(c + sigma.sqrt());
+        if t < 0.0 || input.max_fraction * rr < t {
+            return None;
+        }
+        let fraction = t / rr;
+        Some(RayCastOutput {
+            fraction,
+            point: input.point_at(fraction),
+            normal: (s + d * fraction).normalize(),
+        })
+    }
+}
+
+impl CollidesWith<Circle> for Circle {
+    fn collides_with(&self, other: &Circle, this_t: &Transform, other_t: &Transform) -> bool {
+        let d = other.center.transform(other_t) - self.center.transform(this_t);
+        d.len() - (self.radius + other.radius) <= util::TOLERANCE
+    }
+}
+
+impl CollidesWith<Convex> for Circle {
+    fn collides_with(&self, other: &Convex, this_t: &Transform, other_t: &Transform) -> bool {
+        collides_with_convex(self, other, this_t, other_t)
+    }
+}
+
+impl CollidesWith<Circle> for Convex {
+    fn collides_with(&self, other: &Circle, this_t: &Transform, other_t: &Transform) -> bool {
+        collides_with_convex(other, self, other_t, this_t)
+    }
+}
+
+impl ContactsWith<Circle> for Circle {
+    fn manifold(&self, other: &Circle, this_t: &Transform, other_t: &Transform) -> Option<Manifold> {
+        if !self.collides_with(other, this_t, other_t) {
+            return None;
+        }
+        let id = ContactId {
+            index_a: 0,
+            index_b: 0,
+            type_a: FeatureType::Vertex,
+            type_b: FeatureType::Vertex,
+        };
+        Some(Manifold::new(ManifoldKind::Circles,
+                           Vec2d::zero(),
+                           self.center,
+                           (self.radius, other.radius),
+                           &[(other.center, id)],
+                           this_t,
+                           other_t))
+    }
+}
+
+impl ContactsWith<Convex> for Circle {
+    fn manifold(&self, other: &Convex, this_t: &Transform, other_t: &Transform) -> Option<Manifold> {
+        convex_manifold(self, other, this_t, other_t, true)
+    }
+}
+
+impl ContactsWith<Circle> for Convex {
+    fn manifold(&self, other: &Circle, this_t: &Transform, other_t: &Transform) -> Option<Manifold> {
+        convex_manifold(other, self, other_t, this_t, false)
+    }
+}
+
+/// Builds the manifold of `circle` given transform `ct` against `poly` given
+/// transform `pt`. The contact is either against the face of maximum separation
+/// or, if the circle center lies in the voronoi region of a vertex, against that
+/// vertex. If `flip` is true the circle is the first shape of the manifold.
+/// Algorithm sourced from Erin Catto's Box2D: https://github.com/erincatto/Box2D
+fn convex_manifold(circle: &Circle,
+                   poly: &Convex,
+                   ct: &Transform,
+                   pt: &Transform,
+                   flip: bool)
+                   -> Option<Manifold> {
+    let c = circle.center.transform(ct).inv_transform(pt);
+    let r = circle.radius;
+    let vertices = poly.vertices();
+    let normals = poly.normals();
+
+    let mut best_i = 0;
+    let mut max_sep = f64::MIN;
+    for (i, (v, n)) in vertices.iter().zip(normals).enumerate() {
+        let sep = *n * (c - *v);
+        if sep - r > util::TOLERANCE {
+            return None;
+        }
+        if sep > max_sep {
+            best_i = i;
+            max_sep = sep;
+        }
+    }
+
+    let best_i2 = (best_i + 1) % vertices.len();
+    let v1 = vertices[best_i];
+    let v2 = vertices[best_i2];
+
+    let face = || {
+        (ManifoldKind::FaceA,
+         normals[best_i],
+         (v1 + v2) * 0.5,
+         ContactId {
+             index_a: best_i,
+             index_b: 0,
+             type_a: FeatureType::Face,
+             type_b: FeatureType::Vertex,
+         })
+    };
+    let vertex = |index: usize, v: Vec2d| {
+        if (c - v).len() - r > util::TOLERANCE {
+            return None;
+        }
+        Some((ManifoldKind::Circles,
+              Vec2d::zero(),
+              v,
+              ContactId {
+                  index_a: index,
+                  index_b: 0,
+                  type_a: FeatureType::Vertex,
+                  type_b: FeatureType::Vertex,
+              }))
+    };
+
+    let (kind, local_normal, local_point, id) = if max_sep < util::TOLERANCE {
+        // center is within the polygon
+        face()
+    } else if (c - v1) * (v2 - v1) <= 0.0 {
+        vertex(best_i, v1)?
+    } else if (c - v2) * (v1 - v2) <= 0.0 {
+        vertex(best_i2, v2)?
+    } else {
+        face()
+    };
+
+    Some(match (flip, kind) {
+        (false, _) => Manifold::new(kind, local_normal, local_point, (0.0, r), &[(circle.center, id)], pt, ct),
+        (true, ManifoldKind::Circles) => {
+            Manifold::new(kind,
+                          local_normal,
+                          circle.center,
+                          (r, 0.0),
+                          &[(local_point, id.flip())],
+                          ct,
+                          pt)
+        }
+        (true, _) => {
+            Manifold::new(ManifoldKind::FaceB,
+                          local_normal,
+                          local_point,
+                          (r, 0.0),
+                          &[(circle.center, id.flip())],
+                          ct,
+                          pt)
+        }
+    })
+}
+
+/// Returns if `circle` given transform `ct` overlaps `poly` given transform `pt`.
+/// The circle center is brought into the local space of the polygon where the
+/// edge of maximum separation is found. If the center lies outside that edge,
+/// the closest point on the edge segment decides the overlap
+fn collides_with_convex(circle: &Circle, poly: &Convex, ct: &Transform, pt: &Transform) -> bool {
+    let c = circle.center.transform(ct).inv_transform(pt);
+    let r = circle.radius;
+    let vertices = poly.vertices();
+    let normals = poly.normals();
+
+    let mut best_i = 0;
+    let mut max_sep = f64::MIN;
+    for (i, (v, n)) in vertices.iter().zip(normals).enumerate() {
+        let sep = *n * (c - *v);
+        if sep - r > util::TOLERANCE {
+            // early out, found separating axis
+            return false;
+        }
+        if sep > max_sep {
+            best_i = i;
+            max_sep = sep;
+        }
+    }
+
+    // center is within the polygon
+    if max_sep < util::TOLERANCE {
+        return true;
+    }
+
+    let v1 = vertices[best_i];
+    let v2 = vertices[(best_i + 1) % vertices.len()];
+    let closest = closest_on_segment(c, v1, v2);
+    (c - closest).len() - r <= util::TOLERANCE
+}
+
+/// Returns the point on the segment `a`-`b` closest to `p`
+fn closest_on_segment(p: Vec2d, a: Vec2d, b: Vec2d) -> Vec2d {
+    let e = b - a;
+    let t = (p - a) * e / e.len_sq();
+    a + e * t.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+    use collision::{CollidesWith, ContactsWith, HasAabb, RayCast, RayCastInput};
+    use collision::shapes::{Circle, Convex};
+    use common::{Rotation, Transform, Vec2d};
+
+    fn unit_square() -> Convex {
+        Convex::new(&[Vec2d::new(-1.0, -1.0),
+                      Vec2d::new(1.0, -1.0),
+                      Vec2d::new(1.0, 1.0),
+                      Vec2d::new(-1.0, 1.0)])
+            .unwrap()
+    }
+
+    fn at(x: f64, y: f64) -> Transform {
+        Transform::new(Vec2d::new(x, y), Rotation::identity())
+    }
+
+    #[test]
+    fn test_circle_new() {
+        assert!(Circle::new(Vec2d::zero(), 0.0).is_err());
+        assert!(Circle::new(Vec2d::zero(), -1.0).is_err());
+        assert!(Circle::new(Vec2d::zero(), 1.0).is_ok());
+    }
+
+    #[test]
+    fn test_circle_aabb() {
+        let c = Circle::new(Vec2d::new(1.0, 0.0), 0.5).unwrap();
+        let t = Transform::new(Vec2d::new(2.0, 2.0), Rotation::new(PI / 2.0));
+        let aabb = c.aabb(&t);
+        assert!((aabb.min().x - 1.5).abs() < 1e-9);
+        assert!((aabb.min().y - 2.5).abs() < 1e-9);
+        assert!((aabb.max().x - 2.5).abs() < 1e-9);
+        assert!((aabb.max().y - 3.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_circle_collides_with_circle() {
+        let a = Circle::new(Vec2d::zero(), 1.0).unwrap();
+        let b = Circle::new(Vec2d::new(0.5, 0.0), 0.5).unwrap();
+        assert!(a.collides_with(&b, &at(0.0, 0.0), &at(1.0, 0.0)));
+        assert!(!a.collides_with(&b, &at(0.0, 0.0), &at(1.1, 0.0)));
+        assert!(b.collides_with(&a, &at(0.0, 1.0), &at(0.0, 0.0)));
+    }
+
+    #[test]
+    fn test_circle_collides_with_convex() {
+        let c = Circle::new(Vec2d::zero(), 0.5).unwrap();
+        let square = unit_square();
+        let identity = Transform::identity();
+
+        // face region
+        assert!(c.collides_with(&square, &at(1.4, 0.0), &identity));
+        assert!(!c.collides_with(&square, &at(1.6, 0.0), &identity));
+
+        // vertex region: the corner is ~0.566 away
+        assert!(!c.collides_with(&square, &at(1.4, 1.4), &identity));
+        assert!(c.collides_with(&square, &at(1.3, 1.3), &identity));
+
+        // deep inside
+        assert!(c.collides_with(&square, &at(0.1, 0.2), &identity));
+
+        // rotating the square by 45 degrees brings a corner to x = sqrt(2)
+        let rotated = Transform::new(Vec2d::zero(), Rotation::new(PI / 4.0));
+        assert!(c.collides_with(&square, &at(1.8, 0.0), &rotated));
+        assert!(!c.collides_with(&square, &at(2.0, 0.0), &rotated));
+
+        // symmetric implementation on Convex
+        assert!(square.collides_with(&c, &rotated, &at(1.8, 0.0)));
+        assert!(!square.collides_with(&c, &rotated, &at(2.0, 0.0)));
+    }
+
+    #[test]
+    fn test_circle_manifolds() {
+        let a = Circle::new(Vec2d::zero(), 1.0).unwrap();
+        let b = Circle::new(Vec2d::zero(), 0.5).unwrap();
+        let m = a.manifold(&b, &at(0.0, 0.0), &at(0.0, 1.4)).unwrap();
+        assert_eq!(Vec2d::new(0.0, 1.0), *m.normal());
+        assert!((m.points()[0].depth() - 0.1).abs() < 1e-9);
+        assert!((m.points()[0].point().y - 0.95).abs() < 1e-9);
+        assert!(a.manifold(&b, &at(0.0, 0.0), &at(0.0, 1.6)).is_none());
+
+        let square = unit_square();
+        let identity = Transform::identity();
+
+        // face region
+        let m = square.manifold(&b, &identity, &at(0.2, 1.4)).unwrap();
+        assert!((m.normal().y - 1.0).abs() < 1e-9);
+        assert!((m.depth() - 0.1).abs() < 1e-9);
+        let m = b.manifold(&square, &at(0.2, 1.4), &identity).unwrap();
+        assert!((m.normal().y + 1.0).abs() < 1e-9);
+        assert!((m.depth() - 0.1).abs() < 1e-9);
+
+        // vertex region
+        let m = square.manifold(&b, &identity, &at(1.3, 1.3)).unwrap();
+        let s = 0.5f64.sqrt();
+        assert!((m.normal().x - s).abs() < 1e-9 && (m.normal().y - s).abs() < 1e-9);
+        assert!((m.depth() - (0.5 - 0.3 * 2.0f64.sqrt())).abs() < 1e-9);
+        let m = b.manifold(&square, &at(1.3, 1.3), &identity).unwrap();
+        assert!((m.normal().x + s).abs() < 1e-9 && (m.normal().y + s).abs() < 1e-9);
+        assert!(square.manifold(&b, &identity, &at(1.4, 1.4)).is_none());
+    }
+
+    #[test]
+    fn test_circle_ray_cast() {
+        let c = Circle::new(Vec2d::new(1.0, 0.0), 1.0).unwrap();
+        let t = Transform::new(Vec2d::new(2.0, 0.0), Rotation::new(PI / 2.0));
+
+        // the center sits at (2, 1) in world space
+        let hit = c.ray_cast(&RayCastInput::new(Vec2d::new(2.0, 5.0), Vec2d::new(2.0, -5.0)), &t)
+                   .unwrap();
+        assert!((hit.fraction - 0.3).abs() < 1e-9);
+        assert!((hit.point.y - 2.0).abs() < 1e-9);
+        assert!((hit.normal.y - 1.0).abs() < 1e-9);
+
+        assert!(c.ray_cast(&RayCastInput::new(Vec2d::new(3.5, 5.0), Vec2d::new(3.5, -5.0)), &t)
+                 .is_none());
+        assert!(c.ray_cast(&RayCastInput::new(Vec2d::new(2.0, 5.0), Vec2d::new(2.0, 4.0)), &t)
+                 .is_none());
+
+        let hit = c.ray_cast(&RayCastInput::new(Vec2d::new(2.0, 1.5), Vec2d::new(2.0, 5.0)), &t)
+                   .unwrap();
+        assert_eq!(0.0, hit.fraction);
+    }
+}
@@ -2,13 +2,16 @@ use std::cmp::Ordering;
 use std::f64;
 use std::result::Result;
 use std::vec::Vec;
-use collision::{Aabb, CollidesWith, HasAabb};
+use collision::{Aabb, CollidesWith, ContactId, ContactsWith, FeatureType, HasAabb, HasSupport,
+                Manifold, ManifoldKind, RayCast, RayCastInput, RayCastOutput};
+use collision::shapes::MassData;
 use common::{Transform, Vec2d};
 use util;
 
 /// Convex represents a convex polygon.
 /// It contains the necessary information to be used
 /// within collision detection algorithms
+#[derive(Debug, Clone)]
 pub struct Convex {
     vertices: Vec<Vec2d>,
     normals: Vec<Vec2d>,
@@ -22,7 +25,7 @@ impl Convex {
     pub fn new(vertices: &[Vec2d]) -> Result<Convex, ()> {
         // TODO: meld edges according to some line slop like in Box2d
         // TODO: switch graham scan out for gift-wrapping algorithm and test for speed
-        graham_scan(&vertices).map(|hull| {
+        graham_scan(vertices).map(|hull| {
             let mut normals = Vec::new();
             for i in 0..hull.len() {
                 let i2 = if i + 1 < hull.len() {
@@ -33,12 +36,12 @@ impl Convex {
 
                 let edge = hull[i2] - hull[i];
                 // TODO: assert non-0 length edge
-                normals.push(Vec2d::new(1.0 * edge.y, -1.0 * edge.x).normalize());
+                normals.push(Vec2d::new(edge.y, -edge.x).normalize());
             }
 
             Convex {
                 vertices: hull,
-                normals: normals,
+                normals,
             }
         })
     }
@@ -54,25 +57,123 @@ impl Convex {
     pub fn normals(&self) -> &[Vec2d] {
         &self.normals
     }
+
+    /// Computes the area, centroid and rotational inertia about
+    /// the centroid of this polygon for the given density.
+    ///
+    /// # Remarks
+    ///
+    /// The polygon is split into a fan of triangles sharing the
+    /// first vertex. The area, centroid and second moment of area
+    /// are accumulated per triangle and the inertia is shifted from
+    /// the fan origin to the centroid via the parallel axis theorem
+    pub fn mass_data(&self, density: f64) -> MassData {
+        let origin = self.vertices[0];
+        let mut area = 0.0;
+        let mut center = Vec2d::zero();
+        let mut inertia = 0.0;
+
+        for i in 1..self.vertices.len() - 1 {
+            let e1 = self.vertices[i] - origin;
+            let e2 = self.vertices[i + 1] - origin;
+            let d = e1.cross(e2);
+
+            let tri_area = 0.5 * d;
+            area += tri_area;
+            center += (e1 + e2) * (tri_area / 3.0);
+
+            let int_x2 = e1.x * e1.x + e2.x * e1.x + e2.x * e2.x;
+            let int_y2 = e1.y * e1.y + e2.y * e1.y + e2.y * e2.y;
+            inertia += (0.25 / 3.0 * d) * (int_x2 + int_y2);
+        }
+
+        let mass = density * area;
+        center /= area;
+        MassData {
+            area,
+            mass,
+            center: origin + center,
+            inertia: density * inertia - mass * center.len_sq(),
+        }
+    }
 }
 
 impl HasAabb for Convex {
     fn aabb(&self, transform: &Transform) -> Aabb {
         let transformed: Vec<Vec2d> = self.vertices()
-                                          .into_iter()
+                                          .iter()
                                           .map(|v| v.transform(transform))
                                           .collect();
         Aabb::new(&transformed).unwrap()
     }
 }
 
+impl HasSupport for Convex {
+    fn support(&self, direction: Vec2d) -> Vec2d {
+        let mut best = self.vertices[0];
+        let mut best_proj = best * direction;
+        for v in &self.vertices[1..] {
+            let proj = *v * direction;
+            if proj > best_proj {
+                best = *v;
+                best_proj = proj;
+            }
+        }
+        best
+    }
+}
+
+impl RayCast for Convex {
+    fn ray_cast(&self, input: &RayCastInput, transform: &Transform) -> Option<RayCastOutput> {
+        // clip the ray against the half-plane behind every edge in local space
+        let p1 = input.p1.inv_transform(transform);
+        let d = (input.p2 - input.p1).inv_rotate(transform.rotation());
+
+        let mut lower = 0.0;
+        let mut upper = input.max_fraction;
+        let mut index = None;
+        for (v, n) in self.vertices.iter().zip(&self.normals) {
+            let numerator = *n * (*v - p1);
+            let denominator = *n * d;
+            if denominator == 0.0 {
+                // parallel to the edge and outside of it
+                if numerator < 0.0 {
+                    return None;
+                }
+            } else if denominator < 0.0 && numerator < lower * denominator {
+                // entering the half-plane
+                lower = numerator / denominator;
+                index = Some(n);
+            } else if denominator > 0.0 && numerator < upper * denominator {
+                // leaving the half-plane
+                upper = numerator / denominator;
+            }
+
+            if upper < lower {
+                return None;
+            }
+        }
+
+        match index {
+            Some(n) => {
+                Some(RayCastOutput {
+                    fraction: lower,
+                    point: input.point_at(lower),
+                    normal: n.rotate(transform.rotation()),
+                })
+            }
+            None => Some(input.inside()),
+        }
+    }
+}
+
 impl CollidesWith<Convex> for Convex {
     fn collides_with(&self, other: &Convex, this_t: &Transform, other_t: &Transform) -> bool {
         let (_, sep_a) = find_max_separation(self, other, this_t, other_t);
         if sep_a > util::TOLERANCE {
             return false;
         }
-        let (_, sep_b) = find_max_separation(other, self, this_t, other_t);
+        let (_, sep_b) = find_max_separation(other, self, other_t, this_t);
         if sep_b > util::TOLERANCE {
             return false;
         }
@@ -80,6 +181,168 @@ impl CollidesWith<Convex> for Convex {
     }
 }
 
+impl ContactsWith<Convex> for Convex {
+    fn manifold(&self, other: &Convex, this_t: &Transform, other_t: &Transform) -> Option<Manifold> {
+        let (edge_a, sep_a) = find_max_separation(self, other, this_t, other_t);
+        if sep_a > util::TOLERANCE {
+            return None;
+        }
+        let (edge_b, sep_b) = find_max_separation(other, self, other_t, this_t);
+        if sep_b > util::TOLERANCE {
+            return None;
+        }
+
+        // prefer the first polygon as the reference unless the second
+        // is clearly better so the reference face doesn't flip-flop
+        // between steps for nearly parallel faces
+        if sep_b > sep_a + 0.1 * util::LINEAR_SLOP {
+            clip_polygons(other, self, other_t, this_t, edge_b, true)
+        } else {
+            clip_polygons(self, other, this_t, other_t, edge_a, false)
+        }
+    }
+}
+
+/// A vertex produced while clipping the incident edge along
+/// with the features it originated from
+#[derive(Debug, Clone, Copy)]
+struct ClipVertex {
+    v: Vec2d,
+    id: ContactId,
+}
+
+/// Builds the manifold of `reference` against `incident` by clipping the incident
+/// edge against the side planes of the reference edge `edge`. If `flip` is true
+/// `reference` is the second shape of the resulting manifold.
+/// Algorithm sourced from Erin Catto's Box2D: https://github.com/erincatto/Box2D
+fn clip_polygons(reference: &Convex,
+                 incident: &Convex,
+                 rt: &Transform,
+                 it: &Transform,
+                 edge: usize,
+                 flip: bool)
+                 -> Option<Manifold> {
+    let rv = reference.vertices();
+    let iv = incident.vertices();
+    let edge2 = if edge + 1 < rv.len() {
+        edge + 1
+    } else {
+        0
+    };
+
+    // find the incident edge: the edge on the incident
+    // polygon most anti-parallel to the reference normal
+    let normal = reference.normals()[edge].rotate(rt.rotation()).inv_rotate(it.rotation());
+    let mut inc = 0;
+    let mut min_dot = f64::MAX;
+    for (i, n) in incident.normals().iter().enumerate() {
+        let dot = normal * *n;
+        if dot < min_dot {
+            min_dot = dot;
+            inc = i;
+        }
+    }
+    let inc2 = if inc + 1 < iv.len() {
+        inc + 1
+    } else {
+        0
+    };
+    let incident_edge = [ClipVertex {
+                             v: iv[inc].transform(it),
+                             id: ContactId {
+                                 index_a: edge,
+                                 index_b: inc,
+                                 type_a: FeatureType::Face,
+                                 type_b: FeatureType::Vertex,
+                             },
+                         },
+                         ClipVertex {
+                             v: iv[inc2].transform(it),
+                             id: ContactId {
+                                 index_a: edge,
+                                 index_b: inc2,
+                                 type_a: FeatureType::Face,
+                                 type_b: FeatureType::Vertex,
+                             },
+                         }];
+
+    let v1 = rv[edge];
+    let v2 = rv[edge2];
+    let local_tangent = (v2 - v1).normalize();
+    let local_normal = Vec2d::new(local_tangent.y, -local_tangent.x);
+    let plane_point = (v1 + v2) * 0.5;
+
+    let tangent = local_tangent.rotate(rt.rotation());
+    let normal = Vec2d::new(tangent.y, -tangent.x);
+    let wv1 = v1.transform(rt);
+    let wv2 = v2.transform(rt);
+
+    // clip the incident edge against the side planes of the reference edge
+    let front_offset = normal * wv1;
+    let clip1 = clip_segment_to_line(&incident_edge, -tangent, -(tangent * wv1), edge)?;
+    let clip2 = clip_segment_to_line(&clip1, tangent, tangent * wv2, edge2)?;
+
+    // keep the points behind the reference face
+    let mut points = Vec::with_capacity(2);
+    for cv in &clip2 {
+        if normal * cv.v - front_offset <= util::TOLERANCE {
+            let local = cv.v.inv_transform(it);
+            let id = if flip {
+                cv.id.flip()
+            } else {
+                cv.id
+            };
+            points.push((local, id));
+        }
+    }
+    if points.is_empty() {
+        return None;
+    }
+
+    Some(if flip {
+        Manifold::new(ManifoldKind::FaceB, local_normal, plane_point, (0.0, 0.0), &points, it, rt)
+    } else {
+        Manifold::new(ManifoldKind::FaceA, local_normal, plane_point, (0.0, 0.0), &points, rt, it)
+    })
+}
+
+/// Clips the segment `input` against the half-plane `normal * v <= offset`,
+/// returning the clipped segment or `None` if less than two points remain.
+/// New vertices created by the clip are attributed to the reference vertex `index`
+fn clip_segment_to_line(input: &[ClipVertex; 2],
+                        normal: Vec2d,
+                        offset: f64,
+                        index: usize)
+                        -> Option<[ClipVertex; 2]> {
+    let mut output = Vec::with_capacity(2);
+    let dist0 = normal * input[0].v - offset;
+    let dist1 = normal * input[1].v - offset;
+
+    if dist0 <= 0.0 {
+        output.push(input[0]);
+    }
+    if dist1 <= 0.0 {
+        output.push(input[1]);
+    }
+    if dist0 * dist1 < 0.0 {
+        let interp = dist0 / (dist0 - dist1);
+        output.push(ClipVertex {
+            v: input[0].v + (input[1].v - input[0].v) * interp,
+            id: ContactId {
+                index_a: index,
+                index_b: input[0].id.index_b,
+                type_a: FeatureType::Vertex,
+                type_b: FeatureType::Face,
+            },
+        });
+    }
+
+    if output.len() < 2 {
+        return None;
+    }
+    Some([output[0], output[1]])
+}
+
 /// Calulcates and returns the maximum separation value on a separating axis
 /// for the two Convex polygons and returns the index of the edge normal representing
 /// the separating axis and the value of the separation using the GJK algorithm.
@@ -102,8 +365,8 @@ fn find_max_separation(a: &Convex, b: &Convex, at: &Transform, bt: &Transform) -
         // the reversed edge normal for the edge
         let mut support = vb[0].transform(bt);
         let mut best_proj = neg_normal * support;
-        for j in 1..vb.len() {
-            let vertex_b = vb[j].transform(bt);
+        for v in &vb[1..] {
+            let vertex_b = v.transform(bt);
             let proj = neg_normal * vertex_b; // scalar projection via: https://en.wikipedia.org/wiki/Scalar_projection
             if proj > best_proj {
                 best_proj = proj;
@@ -159,9 +422,10 @@ fn graham_scan(vertices: &[Vec2d]) -> Result<Vec<Vec2d>, ()> {
                 let ds1 = dist_sq(sentinel, *p1);
                 let ds2 = dist_sq(sentinel, *p2);
                 if ds2 >= ds1 {
-                    return Ordering::Less;
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
                 }
-                return Ordering::Greater;
             }
             VertexAngle::Clockwise => Ordering::Greater,
             VertexAngle::CounterClockwise => Ordering::Less,
@@ -191,13 +455,13 @@ fn graham_scan(vertices: &[Vec2d]) -> Result<Vec<Vec2d>, ()> {
     // Graham Scan
     // assertion: At this point, we have at the minimum 3 vertices
     // necessary to create a convex hull
-    for i in second.unwrap()..n {
+    for &v in &clone[second.unwrap()..n] {
         let mut top = hull.pop().unwrap();
-        while vertex_angle(hull[hull.len() - 1], top, clone[i]) != VertexAngle::CounterClockwise {
+        while vertex_angle(hull[hull.len() - 1], top, v) != VertexAngle::CounterClockwise {
             top = hull.pop().unwrap();
         }
         hull.push(top);
-        hull.push(clone[i]);
+        hull.push(v);
     }
     Ok(hull)
 }
@@ -211,7 +475,7 @@ fn vertex_angle(p1: Vec2d, p2: Vec2d, p3: Vec2d) -> VertexAngle {
     if x < 0.0 {
         return VertexAngle::Clockwise;
     }
-    return VertexAngle::CounterClockwise;
+    VertexAngle::CounterClockwise
 }
 
 /// Returns the square of the distance
@@ -224,116 +488,221 @@ fn dist_sq(p1: Vec2d, p2: Vec2d) -> f64 {
 /// with the lowest y coordinate or the one with the left-most
 /// x coordinate in case of a tie
 fn lowest_y_index(vertices: &[Vec2d]) -> usize {
-    let mut i: usize = 0;
     let mut j: usize = 0;
     let mut lowest = vertices[j];
-    for p in vertices {
+    for (i, p) in vertices.iter().enumerate() {
         if p.y < lowest.y || (util::feq(p.y, lowest.y) && p.x < lowest.x) {
             lowest = *p;
             j = i;
         }
-        i += 1;
     }
-    return j;
+    j
 }
 
 #[cfg(test)]
 mod test {
+    use std::f64::consts::PI;
     use std::vec::Vec;
+    use collision::{CollidesWith, ContactsWith, RayCast, RayCastInput};
     use collision::shapes::Convex;
-    use common::Vec2d;
+    use common::{Rotation, Transform, Vec2d};
+
+    fn square(half: f64) -> Convex {
+        Convex::new(&[Vec2d::new(-half, -half),
+                      Vec2d::new(half, -half),
+                      Vec2d::new(half, half),
+                      Vec2d::new(-half, half)])
+            .unwrap()
+    }
+
+    fn approx(v: Vec2d, x: f64, y: f64) -> bool {
+        (v.x - x).abs() < 1e-9 && (v.y - y).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_convex_mass_data() {
+        let rect = Convex::new(&[Vec2d::new(1.0, 1.0),
+                                 Vec2d::new(5.0, 1.0),
+                                 Vec2d::new(5.0, 3.0),
+                                 Vec2d::new(1.0, 3.0)])
+                       .unwrap();
+        let md = rect.mass_data(2.0);
+        assert!((md.area - 8.0).abs() < 1e-9);
+        assert!((md.mass - 16.0).abs() < 1e-9);
+        assert!(approx(md.center, 3.0, 2.0));
+        assert!((md.inertia - 16.0 * (16.0 + 4.0) / 12.0).abs() < 1e-9);
+
+        let tri = Convex::new(&[Vec2d::new(0.0, 0.0), Vec2d::new(3.0, 0.0), Vec2d::new(0.0, 3.0)])
+                      .unwrap();
+        let md = tri.mass_data(1.0);
+        assert!((md.area - 4.5).abs() < 1e-9);
+        assert!(approx(md.center, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_convex_collides_with_convex() {
+        let a = square(1.0);
+        let b = square(0.5);
+        let at = Transform::identity();
+        assert!(a.collides_with(&b, &at, &Transform::new(Vec2d::new(1.4, 0.0), Rotation::identity())));
+        assert!(!a.collides_with(&b, &at, &Transform::new(Vec2d::new(1.6, 0.0), Rotation::identity())));
+
+        // the corner of b rotated by 45 degrees reaches sqrt(0.5) ~ 0.707
+        let bt = Transform::new(Vec2d::new(1.6, 0.0), Rotation::new(PI / 4.0));
+        assert!(a.collides_with(&b, &at, &bt));
+        assert!(b.collides_with(&a, &bt, &at));
+
+        // only the faces of the rotated square separate the squares
+        let a = square(1.0);
+        let bt = Transform::new(Vec2d::new(2.2, 2.2), Rotation::new(PI / 4.0));
+        assert!(!a.collides_with(&a, &at, &bt));
+        assert!(!a.collides_with(&a, &bt, &at));
+    }
+
+    #[test]
+    fn test_convex_manifold() {
+        let ground = square(1.0);
+        let small = square(0.5);
+        let at = Transform::identity();
+
+        // separated
+        let bt = Transform::new(Vec2d::new(0.0, 1.6), Rotation::identity());
+        assert!(ground.manifold(&small, &at, &bt).is_none());
+
+        // resting with a slight overlap gives two points along the face
+        let bt = Transform::new(Vec2d::new(0.25, 1.4), Rotation::identity());
+        let m = ground.manifold(&small, &at, &bt).unwrap();
+        assert!(approx(*m.normal(), 0.0, 1.0));
+        assert_eq!(2, m.points().len());
+        for p in m.points() {
+            assert!((p.depth() - 0.1).abs() < 1e-9);
+            assert!((p.point().y - 0.95).abs() < 1e-9);
+        }
+        let mut xs: Vec<f64> = m.points().iter().map(|p| p.point().x).collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!((xs[0] + 0.25).abs() < 1e-9);
+        assert!((xs[1] - 0.75).abs() < 1e-9);
+
+        // swapping the shapes flips the normal
+        let m = small.manifold(&ground, &bt, &at).unwrap();
+        assert!(approx(*m.normal(), 0.0, -1.0));
+        assert_eq!(2, m.points().len());
+
+        // a corner pushed into the face gives a single point
+        let bt = Transform::new(Vec2d::new(0.0, 1.6), Rotation::new(PI / 4.0));
+        let m = ground.manifold(&small, &at, &bt).unwrap();
+        assert!(approx(*m.normal(), 0.0, 1.0));
+        assert_eq!(1, m.points().len());
+        let depth = 1.0 - (1.6 - 0.5f64.sqrt());
+        assert!((m.points()[0].depth() - depth).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_convex_manifold_ids_stable() {
+        let ground = square(1.0);
+        let small = square(0.5);
+        let at = Transform::identity();
+        let m1 = ground.manifold(&small, &at, &Transform::new(Vec2d::new(0.0, 1.45), Rotation::identity()))
+                       .unwrap();
+        let m2 = ground.manifold(&small, &at, &Transform::new(Vec2d::new(0.01, 1.44), Rotation::identity()))
+                       .unwrap();
+        let ids1: Vec<_> = m1.points().iter().map(|p| p.id()).collect();
+        let ids2: Vec<_> = m2.points().iter().map(|p| p.id()).collect();
+        assert_eq!(ids1, ids2);
+    }
 
     #[test]
     fn test_convex_from_vertices() {
         // test too few vertices
         let mut v: Vec<Vec2d> = Vec::new();
-        assert!(Convex::new(&mut v).is_err());
+        assert!(Convex::new(&v).is_err());
         v.push(Vec2d::new(0.0, 0.0));
         v.push(Vec2d::new(1.0, 1.0));
-        assert!(Convex::new(&mut v).is_err());
+        assert!(Convex::new(&v).is_err());
 
         // test line
         v.push(Vec2d::new(2.0, 2.0));
         v.push(Vec2d::new(3.0, 3.0));
-        assert!(Convex::new(&mut v).is_err());
+        assert!(Convex::new(&v).is_err());
         v.pop();
         v.pop();
 
         // test basic hull
         v.push(Vec2d::new(1.0, 0.0));
-        let mut r = Convex::new(&mut v);
+        let mut r = Convex::new(&v);
         let mut r_ok = r.ok().unwrap();
         {
             let r_vertices = r_ok.vertices();
             assert_eq!(3, r_vertices.len());
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(0.0, 0.0)).is_none() {
-                assert!(false);
-            }
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(1.0, 1.0)).is_none() {
-                assert!(false);
-            }
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(1.0, 0.0)).is_none() {
-                assert!(false);
-            }
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(0.0, 0.0)));
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(1.0, 1.0)));
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(1.0, 0.0)));
         }
 
         // test add point at cw angle to last point
         v.push(Vec2d::new(2.0, 1.0));
-        r = Convex::new(&mut v);
+        r = Convex::new(&v);
         r_ok = r.ok().unwrap();
         {
             let r_vertices = r_ok.vertices();
             assert_eq!(4, r_vertices.len());
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(0.0, 0.0)).is_none() {
-                assert!(false);
-            }
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(1.0, 1.0)).is_none() {
-                assert!(false);
-            }
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(1.0, 0.0)).is_none() {
-                assert!(false);
-            }
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(2.0, 1.0)).is_none() {
-                assert!(false);
-            }
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(0.0, 0.0)));
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(1.0, 1.0)));
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(1.0, 0.0)));
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(2.0, 1.0)));
         }
         v.pop();
 
         // add internal point
         v.push(Vec2d::new(0.5, 0.5));
-        r = Convex::new(&mut v);
+        r = Convex::new(&v);
         r_ok = r.ok().unwrap();
         {
             let r_vertices = r_ok.vertices();
             assert_eq!(3, r_vertices.len());
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(0.0, 0.0)).is_none() {
-                assert!(false);
-            }
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(1.0, 1.0)).is_none() {
-                assert!(false);
-            }
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(1.0, 0.0)).is_none() {
-                assert!(false);
-            }
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(0.0, 0.0)));
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(1.0, 1.0)));
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(1.0, 0.0)));
         }
 
         // add collinear points
         v.push(Vec2d::new(1.0, 0.5));
         v.push(Vec2d::new(1.0, 0.25));
-        r = Convex::new(&mut v);
+        r = Convex::new(&v);
         r_ok = r.ok().unwrap();
         {
             let r_vertices = r_ok.vertices();
             assert_eq!(3, r_vertices.len());
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(0.0, 0.0)).is_none() {
-                assert!(false);
-            }
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(1.0, 1.0)).is_none() {
-                assert!(false);
-            }
-            if r_vertices.iter().find(|&x| *x == Vec2d::new(1.0, 0.0)).is_none() {
-                assert!(false);
-            }
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(0.0, 0.0)));
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(1.0, 1.0)));
+            assert!(r_vertices.iter().any(|&x| x == Vec2d::new(1.0, 0.0)));
         }
     }
+
+    #[test]
+    fn test_convex_ray_cast() {
+        let s = square(1.0);
+        let t = Transform::new(Vec2d::new(2.0, 0.0), Rotation::new(PI / 4.0));
+
+        // the corner of the rotated square sits at x = 2 - sqrt(2)
+        let hit = s.ray_cast(&RayCastInput::new(Vec2d::new(-2.0, 0.1), Vec2d::new(2.0, 0.1)), &t)
+                   .unwrap();
+        let x = 2.0 - 2.0f64.sqrt() + 0.1;
+        assert!((hit.fraction - (x + 2.0) / 4.0).abs() < 1e-9);
+        assert!(approx(hit.point, x, 0.1));
+        assert!(approx(hit.normal, -0.5f64.sqrt(), 0.5f64.sqrt()));
+
+        // misses above the corner and falls short
+        assert!(s.ray_cast(&RayCastInput::new(Vec2d::new(-2.0, 1.5), Vec2d::new(4.0, 1.5)), &t)
+                 .is_none());
+        assert!(s.ray_cast(&RayCastInput::new(Vec2d::new(-2.0, 0.0), Vec2d::new(0.0, 0.0)), &t)
+                 .is_none());
+
+        // a ray starting inside hits immediately
+        let hit = s.ray_cast(&RayCastInput::new(Vec2d::new(2.0, 0.5), Vec2d::new(5.0, 0.5)), &t)
+                   .unwrap();
+        assert_eq!(0.0, hit.fraction);
+        assert!(approx(hit.point, 2.0, 0.5));
+        assert!(approx(hit.normal, -1.0, 0.0));
+    }
 }
@@ -0,0 +1,15 @@
+use common::Vec2d;
+
+/// MassData holds the mass properties of a shape
+/// computed for a given density
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MassData {
+    /// The area of the shape
+    pub area: f64,
+    /// The mass of the shape
+    pub mass: f64,
+    /// The centroid of the shape in local space
+    pub center: Vec2d,
+    /// The rotational inertia of the shape about its centroid
+    pub inertia: f64,
+}
\ No newline at end of file
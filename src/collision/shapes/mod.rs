@@ -1,3 +1,9 @@
+pub use self::circle::Circle;
 pub use self::convex::Convex;
+pub use self::mass_data::MassData;
+pub use self::shape::Shape;
 
-mod convex;
\ No newline at end of file
+mod circle;
+mod convex;
+mod mass_data;
+mod shape;
\ No newline at end of file
@@ -0,0 +1,92 @@
+use collision::{Aabb, CollidesWith, ContactsWith, HasAabb, HasSupport, Manifold, RayCast,
+                RayCastInput, RayCastOutput};
+use collision::shapes::{Circle, Convex, MassData};
+use common::{Transform, Vec2d};
+
+/// Shape wraps every shape supported by the engine so
+/// that differently shaped objects may be stored and tested
+/// against each other uniformly
+#[derive(Debug, Clone)]
+pub enum Shape {
+    Circle(Circle),
+    Convex(Convex),
+}
+
+impl Shape {
+    /// Computes the mass properties of the wrapped shape
+    /// for the given density
+    pub fn mass_data(&self, density: f64) -> MassData {
+        match *self {
+            Shape::Circle(ref c) => c.mass_data(density),
+            Shape::Convex(ref c) => c.mass_data(density),
+        }
+    }
+}
+
+impl From<Circle> for Shape {
+    fn from(circle: Circle) -> Shape {
+        Shape::Circle(circle)
+    }
+}
+
+impl From<Convex> for Shape {
+    fn from(convex: Convex) -> Shape {
+        Shape::Convex(convex)
+    }
+}
+
+impl HasAabb for Shape {
+    fn aabb(&self, transform: &Transform) -> Aabb {
+        match *self {
+            Shape::Circle(ref c) => c.aabb(transform),
+            Shape::Convex(ref c) => c.aabb(transform),
+        }
+    }
+}
+
+impl HasSupport for Shape {
+    fn support(&self, direction: Vec2d) -> Vec2d {
+        match *self {
+            Shape::Circle(ref c) => c.support(direction),
+            Shape::Convex(ref c) => c.support(direction),
+        }
+    }
+
+    fn radius(&self) -> f64 {
+        match *self {
+            Shape::Circle(ref c) => HasSupport::radius(c),
+            Shape::Convex(ref c) => HasSupport::radius(c),
+        }
+    }
+}
+
+impl RayCast for Shape {
+    fn ray_cast(&self, input: &RayCastInput, transform: &Transform) -> Option<RayCastOutput> {
+        match *self {
+            Shape::Circle(ref c) => c.ray_cast(input, transform),
+            Shape::Convex(ref c) => c.ray_cast(input, transform),
+        }
+    }
+}
+
+impl CollidesWith<Shape> for Shape {
+    fn collides_with(&self, other: &Shape, this_t: &Transform, other_t: &Transform) -> bool {
+        match (self, other) {
+            (Shape::Circle(a), Shape::Circle(b)) => a.collides_with(b, this_t, other_t),
+            (Shape::Circle(a), Shape::Convex(b)) => a.collides_with(b, this_t, other_t),
+            (Shape::Convex(a), Shape::Circle(b)) => a.collides_with(b, this_t, other_t),
+            (Shape::Convex(a), Shape::Convex(b)) => a.collides_with(b, this_t, other_t),
+        }
+    }
+}
+
+impl ContactsWith<Shape> for Shape {
+    fn manifold(&self, other: &Shape, this_t: &Transform, other_t: &Transform) -> Option<Manifold> {
+        match (self, other) {
+            (Shape::Circle(a), Shape::Circle(b)) => a.manifold(b, this_t, other_t),
+            (Shape::Circle(a), Shape::Convex(b)) => a.manifold(b, this_t, other_t),
+            (Shape::Convex(a), Shape::Circle(b)) => a.manifold(b, this_t, other_t),
+            (Shape::Convex(a), Shape::Convex(b)) => a.manifold(b, this_t, other_t),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,210 @@
+use collision::{distance, HasAabb, HasSupport};
+use common::{Rotation, Transform, Vec2d};
+use util;
+
+/// The maximum number of iterations of conservative advancement
+const MAX_ITERATIONS: usize = 30;
+
+/// Sweep describes the motion of a shape over a step. The center
+/// moves linearly and the shape rotates about it at a constant rate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sweep {
+    local_center: Vec2d,
+    c0: Vec2d,
+    c: Vec2d,
+    a0: f64,
+    a: f64,
+}
+
+impl Sweep {
+    /// Creates a sweep from the `start` to the `end` transform rotating
+    /// about `local_center`, given in the local space of the shape.
+    /// The shortest rotation between the transforms is taken
+    pub fn new(local_center: Vec2d, start: &Transform, end: &Transform) -> Sweep {
+        let a0 = start.rotation().angle();
+        let da = util::wrap_angle(end.rotation().angle() - a0);
+        Sweep {
+            local_center,
+            c0: local_center.transform(start),
+            c: local_center.transform(end),
+            a0,
+            a: a0 + da,
+        }
+    }
+
+    /// Returns the transform at the fraction `t` of the sweep
+    pub fn transform_at(&self, t: f64) -> Transform {
+        let rotation = Rotation::new(self.a0 + (self.a - self.a0) * t);
+        let center = self.c0 + (self.c - self.c0) * t;
+        Transform::new(center - self.local_center.rotate(&rotation), rotation)
+    }
+
+    /// Returns the center at the fraction `t` of the sweep
+    pub fn center_at(&self, t: f64) -> Vec2d {
+        self.c0 + (self.c - self.c0) * t
+    }
+}
+
+/// The outcome of a time of impact query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToiState {
+    /// The shapes already overlap at the start of the sweeps
+    Overlapped,
+    /// The shapes touch at the returned fraction
+    Touching,
+    /// The shapes stay apart over the whole sweeps
+    Separated,
+    /// The iteration limit was reached before converging
+    Failed,
+}
+
+/// ToiOutput holds the result of a time of impact query
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToiOutput {
+    /// The outcome of the query
+    pub state: ToiState,
+    /// The fraction of the sweeps at which the shapes first touch,
+    /// or how far the query got if they never touch
+    pub fraction: f64,
+}
+
+/// Computes the earliest fraction of the sweeps `sa` and `sb` at which
+/// the shapes `a` and `b` come within `LINEAR_SLOP` of each other.
+///
+/// # Remarks
+///
+/// Uses conservative advancement: the distance between the shapes is
+/// divided by an upper bound of their approach speed along the closest
+/// points, giving a step that can't overshoot the first contact. The
+/// rotation of each shape is bounded by the radius of its `Aabb` about
+/// the center of its sweep
+pub fn time_of_impact<A, B>(a: &A, sa: &Sweep, b: &B, sb: &Sweep) -> ToiOutput
+    where A: HasAabb + HasSupport,
+          B: HasAabb + HasSupport
+{
+    let target = util::LINEAR_SLOP;
+    let tolerance = 0.25 * util::LINEAR_SLOP;
+
+    let ra = bounding_radius(a, &sa.local_center);
+    let rb = bounding_radius(b, &sb.local_center);
+    let angular = (sa.a - sa.a0).abs() * ra + (sb.a - sb.a0).abs() * rb;
+    let linear = (sb.c - sb.c0) - (sa.c - sa.c0);
+
+    let mut t = 0.0;
+    for _ in 0..MAX_ITERATIONS {
+        let out = distance(a, &sa.transform_at(t), b, &sb.transform_at(t));
+        if out.distance <= 0.0 && t == 0.0 {
+            return ToiOutput {
+                state: ToiState::Overlapped,
+                fraction: 0.0,
+            };
+        }
+        if out.distance < target + tolerance {
+            return ToiOutput {
+                state: ToiState::Touching,
+                fraction: t,
+            };
+        }
+
+        // how fast the gap along the closest points can close at most
+        let normal = (out.point_b - out.point_a).normalize();
+        let speed = -(linear * normal) + angular;
+        if speed > 0.0 {
+            t += (out.distance - target) / speed;
+        }
+        if speed <= 0.0 || t >= 1.0 {
+            return ToiOutput {
+                state: ToiState::Separated,
+                fraction: 1.0,
+            };
+        }
+    }
+
+    ToiOutput {
+        state: ToiState::Failed,
+        fraction: t,
+    }
+}
+
+/// Returns the distance from `center` to the furthest
+/// corner of the local `Aabb` of the shape
+fn bounding_radius<S: HasAabb>(shape: &S, center: &Vec2d) -> f64 {
+    let aabb = shape.aabb(&Transform::identity());
+    let min = *aabb.min() - *center;
+    let max = *aabb.max() - *center;
+    let x = min.x.abs().max(max.x.abs());
+    let y = min.y.abs().max(max.y.abs());
+    Vec2d::new(x, y).len()
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+    use collision::{distance, time_of_impact, Sweep, ToiState};
+    use collision::shapes::{Circle, Convex};
+    use common::{Rotation, Transform, Vec2d};
+    use util;
+
+    fn rect(hx: f64, hy: f64) -> Convex {
+        Convex::new(&[Vec2d::new(-hx, -hy), Vec2d::new(hx, -hy), Vec2d::new(hx, hy), Vec2d::new(-hx, hy)])
+            .unwrap()
+    }
+
+    fn at(x: f64, y: f64, angle: f64) -> Transform {
+        Transform::new(Vec2d::new(x, y), Rotation::new(angle))
+    }
+
+    #[test]
+    fn test_sweep_transform_at() {
+        let sweep = Sweep::new(Vec2d::new(1.0, 0.0), &at(0.0, 0.0, 0.0), &at(-1.0, 1.0, PI / 2.0));
+        let t = sweep.transform_at(0.5);
+        // the center moves from (1, 0) to (-1, 2) while rotating about it
+        assert!((Vec2d::new(1.0, 0.0).transform(&t) - Vec2d::new(0.0, 1.0)).len() < 1e-9);
+        assert!((t.rotation().angle() - PI / 4.0).abs() < 1e-9);
+        assert!((sweep.center_at(0.5) - Vec2d::new(0.0, 1.0)).len() < 1e-9);
+
+        // the shortest way round is taken
+        let sweep = Sweep::new(Vec2d::zero(), &at(0.0, 0.0, 0.9 * PI), &at(0.0, 0.0, -0.9 * PI));
+        assert!((sweep.transform_at(0.5).rotation().angle().abs() - PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_time_of_impact_thin_wall() {
+        let wall = rect(0.05, 2.0);
+        let bullet = Circle::new(Vec2d::zero(), 0.1).unwrap();
+        let still = Sweep::new(Vec2d::zero(), &at(0.0, 0.0, 0.0), &at(0.0, 0.0, 0.0));
+
+        // both ends of the sweep are clear of the wall
+        let sweep = Sweep::new(Vec2d::zero(), &at(-5.0, 0.5, 0.0), &at(5.0, 0.5, 0.0));
+        let toi = time_of_impact(&bullet, &sweep, &wall, &still);
+        assert_eq!(ToiState::Touching, toi.state);
+        assert!((toi.fraction - 0.485).abs() < 1e-3);
+        let d = distance(&bullet, &sweep.transform_at(toi.fraction), &wall, &at(0.0, 0.0, 0.0));
+        assert!(d.distance > 0.0 && d.distance < 2.0 * util::LINEAR_SLOP);
+
+        // passing above the wall
+        let sweep = Sweep::new(Vec2d::zero(), &at(-5.0, 2.5, 0.0), &at(5.0, 2.5, 0.0));
+        assert_eq!(ToiState::Separated, time_of_impact(&bullet, &sweep, &wall, &still).state);
+
+        // already overlapping
+        let sweep = Sweep::new(Vec2d::zero(), &at(0.0, 0.0, 0.0), &at(5.0, 0.0, 0.0));
+        assert_eq!(ToiState::Overlapped, time_of_impact(&bullet, &sweep, &wall, &still).state);
+    }
+
+    #[test]
+    fn test_time_of_impact_rotating() {
+        // a long bar spinning a quarter turn in place sweeps into a box
+        let bar = rect(2.0, 0.1);
+        let block = rect(0.2, 0.2);
+        let spin = Sweep::new(Vec2d::zero(), &at(0.0, 0.0, 0.0), &at(0.0, 0.0, PI / 2.0));
+        let still = Sweep::new(Vec2d::zero(), &at(0.0, 1.5, 0.0), &at(0.0, 1.5, 0.0));
+
+        let toi = time_of_impact(&bar, &spin, &block, &still);
+        assert_eq!(ToiState::Touching, toi.state);
+        assert!(toi.fraction > 0.0 && toi.fraction < 1.0);
+        let d = distance(&bar, &spin.transform_at(toi.fraction), &block, &at(0.0, 1.5, 0.0));
+        assert!(d.distance < 2.0 * util::LINEAR_SLOP);
+        let earlier = distance(&bar, &spin.transform_at(toi.fraction * 0.9), &block, &at(0.0, 1.5, 0.0));
+        assert!(earlier.distance > 0.0);
+    }
+}
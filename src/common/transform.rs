@@ -12,8 +12,8 @@ impl Transform {
     /// and rotation
     pub fn new(position: Vec2d, rotation: Rotation) -> Transform {
         Transform {
-            position: position,
-            rotation: rotation,
+            position,
+            rotation,
         }
     }
 
@@ -11,7 +11,7 @@ pub struct Vec2d {
 impl Vec2d {
     /// Creates a new `Vec2d` with the specified `x` and `y` values
     pub fn new(x: f64, y: f64) -> Vec2d {
-        Vec2d { x: x, y: y }
+        Vec2d { x, y }
     }
 
     /// Creates a new zero `Vec2d` vector
@@ -24,15 +24,28 @@ impl Vec2d {
         self.x * rhs.x + self.y * rhs.y
     }
 
+    /// Returns the 2d cross product (e.g. the z component of the 3d cross
+    /// product) of this `Vec2d` with another `Vec2d`
+    pub fn cross(self, rhs: Vec2d) -> f64 {
+        self.x * rhs.y - self.y * rhs.x
+    }
+
+    /// Returns this `Vec2d` rotated 90 degrees counter-clockwise.
+    /// Equivalent to the cross product of a unit z-axis scalar with
+    /// this vector
+    pub fn perp(&self) -> Vec2d {
+        Vec2d::new(-self.y, self.x)
+    }
+
     /// Returns the length of this `Vec2d`
     pub fn len(&self) -> f64 {
-        (self.x * self.x + self.y + self.y).sqrt()
+        (self.x * self.x + self.y * self.y).sqrt()
     }
 
     /// Returns the length squared of this `Vec2d`. Useful
     /// for avoiding expensive sqrt calculations
     pub fn len_sq(&self) -> f64 {
-        self.x * self.x + self.y + self.y
+        self.x * self.x + self.y * self.y
     }
 
     /// normalize this vector (e.g. for Vector `v`, `v.x /= |v|`, `v.y /= |v|`)
@@ -51,16 +64,23 @@ impl Vec2d {
                    (rotation.sin() * self.x + rotation.cos() * self.y) + position.y)
     }
 
+    /// inverse transform a `Vec2d` using a `Transform` and return the result.
+    /// Useful for taking a point from world space into the local space of
+    /// the transform
+    pub fn inv_transform(&self, transform: &Transform) -> Vec2d {
+        (*self - *transform.position()).inv_rotate(transform.rotation())
+    }
+
     /// rotate a `Vec2d` using a `Rotation` and return the result
     pub fn rotate(&self, rotation: &Rotation) -> Vec2d {
         Vec2d::new(rotation.cos() * self.x - rotation.sin() * self.y,
-                   rotation.sin() + self.x + rotation.cos() * self.y)
+                   rotation.sin() * self.x + rotation.cos() * self.y)
     }
 
     /// inverse rotation of a `Vec2d` using a `Rotation` and return the result
     pub fn inv_rotate(&self, rotation: &Rotation) -> Vec2d {
         Vec2d::new(rotation.cos() * self.x + rotation.sin() * self.y,
-                   -rotation.sin() + self.x + rotation.cos() * self.y)
+                   -rotation.sin() * self.x + rotation.cos() * self.y)
     }
 }
 
@@ -151,3 +171,35 @@ impl ops::Neg for Vec2d {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+    use common::{Rotation, Transform, Vec2d};
+
+    fn approx(v: Vec2d, x: f64, y: f64) -> bool {
+        (v.x - x).abs() < 1e-9 && (v.y - y).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_vec2d_len() {
+        let v = Vec2d::new(3.0, 4.0);
+        assert_eq!(25.0, v.len_sq());
+        assert_eq!(5.0, v.len());
+        assert!(approx(v.normalize(), 0.6, 0.8));
+        assert_eq!(13.0, Vec2d::new(-5.0, 12.0).len());
+    }
+
+    #[test]
+    fn test_vec2d_rotate() {
+        let rotation = Rotation::new(PI / 2.0);
+        let v = Vec2d::new(2.0, 1.0);
+        assert!(approx(v.rotate(&rotation), -1.0, 2.0));
+        assert!(approx(v.inv_rotate(&rotation), 1.0, -2.0));
+        assert!(approx(v.rotate(&rotation).inv_rotate(&rotation), 2.0, 1.0));
+
+        let transform = Transform::new(Vec2d::new(1.0, -1.0), Rotation::new(PI));
+        assert!(approx(v.transform(&transform), -1.0, -2.0));
+        assert!(approx(v.transform(&transform).inv_transform(&transform), 2.0, 1.0));
+    }
+}
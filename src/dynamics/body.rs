@@ -0,0 +1,491 @@
+use collision::shapes::MassData;
+use common::{Rotation, Transform, Vec2d};
+use dynamics::Fixture;
+
+/// BodyType decides how a body is moved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyType {
+    /// The body never moves and has infinite mass. Meant for ground geometry
+    Static,
+    /// The body moves with the velocity set by the user and has infinite
+    /// mass, so it pushes dynamic bodies around without being affected by
+    /// them or by gravity and forces. Meant for moving platforms
+    Kinematic,
+    /// The body is moved by gravity, forces and contacts
+    Dynamic,
+}
+
+/// RigidBody represents a rigid body in 2d space. It holds
+/// the transform of the body origin, the velocity of the center
+/// of mass, accumulated forces, the mass properties of the body
+/// and the fixtures attached to it
+#[derive(Debug, Clone)]
+pub struct RigidBody {
+    body_type: BodyType,
+    transform: Transform,
+    fixtures: Vec<Fixture>,
+    local_center: Vec2d,
+    linear_velocity: Vec2d,
+    angular_velocity: f64,
+    force: Vec2d,
+    torque: f64,
+    mass: f64,
+    inv_mass: f64,
+    inertia: f64,
+    inv_inertia: f64,
+    bullet: bool,
+    awake: bool,
+    sleeping_allowed: bool,
+    sleep_time: f64,
+}
+
+impl RigidBody {
+    /// Creates a new dynamic body at the given transform with a unit mass,
+    /// no rotational inertia and the center of mass at the body origin
+    pub fn new(transform: Transform) -> RigidBody {
+        RigidBody {
+            body_type: BodyType::Dynamic,
+            transform,
+            fixtures: Vec::new(),
+            local_center: Vec2d::zero(),
+            linear_velocity: Vec2d::zero(),
+            angular_velocity: 0.0,
+            force: Vec2d::zero(),
+            torque: 0.0,
+            mass: 1.0,
+            inv_mass: 1.0,
+            inertia: 0.0,
+            inv_inertia: 0.0,
+            bullet: false,
+            awake: true,
+            sleeping_allowed: true,
+            sleep_time: 0.0,
+        }
+    }
+
+    /// Returns the type of the body
+    pub fn body_type(&self) -> BodyType {
+        self.body_type
+    }
+
+    /// Sets the type of the body, recomputing its mass from the attached
+    /// fixtures and clearing the accumulated forces. Static bodies are
+    /// stopped. Static and kinematic bodies have infinite mass and rotate
+    /// about their origin
+    pub fn set_body_type(&mut self, body_type: BodyType) {
+        self.body_type = body_type;
+        if body_type == BodyType::Static {
+            self.linear_velocity = Vec2d::zero();
+            self.angular_velocity = 0.0;
+        }
+        self.reset_mass_data();
+        self.clear_forces();
+        self.set_awake(true);
+    }
+
+    /// Returns the transform of the body origin
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    /// Sets the transform of the body origin, waking the body
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+        self.set_awake(true);
+    }
+
+    /// Returns the position of the body origin
+    pub fn position(&self) -> &Vec2d {
+        self.transform.position()
+    }
+
+    /// Returns the rotation of the body
+    pub fn rotation(&self) -> &Rotation {
+        self.transform.rotation()
+    }
+
+    /// Returns if the body is a bullet
+    pub fn is_bullet(&self) -> bool {
+        self.bullet
+    }
+
+    /// Sets if the body is a bullet. The motion of bullets is swept
+    /// against other bodies every step so fast bullets can't tunnel
+    /// through thin bodies. This is expensive, so only flag fast,
+    /// small bodies
+    pub fn set_bullet(&mut self, bullet: bool) {
+        self.bullet = bullet;
+    }
+
+    /// Returns if the body is awake
+    pub fn is_awake(&self) -> bool {
+        self.awake
+    }
+
+    /// Wakes the body or puts it to sleep. A sleeping body is neither
+    /// moved nor collided until it's woken up again. Putting a body to
+    /// sleep stops it and clears the accumulated forces
+    pub fn set_awake(&mut self, awake: bool) {
+        if awake == self.awake {
+            return;
+        }
+        self.awake = awake;
+        self.sleep_time = 0.0;
+        if !awake {
+            self.linear_velocity = Vec2d::zero();
+            self.angular_velocity = 0.0;
+            self.clear_forces();
+        }
+    }
+
+    /// Returns if the body may fall asleep
+    pub fn is_sleeping_allowed(&self) -> bool {
+        self.sleeping_allowed
+    }
+
+    /// Sets if the body may fall asleep once it comes to rest.
+    /// Disallowing sleep wakes the body
+    pub fn set_sleeping_allowed(&mut self, allowed: bool) {
+        self.sleeping_allowed = allowed;
+        if !allowed {
+            self.set_awake(true);
+        }
+    }
+
+    /// Returns how long the body has been resting in seconds
+    pub(crate) fn sleep_time(&self) -> f64 {
+        self.sleep_time
+    }
+
+    /// Sets how long the body has been resting in seconds
+    pub(crate) fn set_sleep_time(&mut self, time: f64) {
+        self.sleep_time = time;
+    }
+
+    /// Returns the fixtures attached to the body
+    pub fn fixtures(&self) -> &[Fixture] {
+        &self.fixtures
+    }
+
+    /// Returns the fixtures attached to the body mutably
+    pub(crate) fn fixtures_mut(&mut self) -> &mut [Fixture] {
+        &mut self.fixtures
+    }
+
+    /// Returns the fixture at `index` mutably or `None` if there is no such
+    /// fixture, waking the body so changes to the filter or the sensor and
+    /// one way settings are picked up. Contacts keep the friction and
+    /// restitution they had when they were created
+    pub fn fixture_mut(&mut self, index: usize) -> Option<&mut Fixture> {
+        self.set_awake(true);
+        self.fixtures.get_mut(index)
+    }
+
+    /// Attaches a fixture to the body, returning the index of the fixture,
+    /// and recomputes the mass of the body from all attached fixtures
+    pub fn add_fixture(&mut self, fixture: Fixture) -> usize {
+        self.fixtures.push(fixture);
+        self.reset_mass_data();
+        self.fixtures.len() - 1
+    }
+
+    /// Recomputes the mass properties of the body from the attached
+    /// fixtures. Dynamic bodies whose fixtures have no mass get a mass of 1
+    pub fn reset_mass_data(&mut self) {
+        let mut area = 0.0;
+        let mut mass = 0.0;
+        let mut center = Vec2d::zero();
+        for md in self.fixtures.iter().map(|f| f.mass_data()) {
+            area += md.area;
+            mass += md.mass;
+            center += md.center * md.mass;
+        }
+        if mass > 0.0 {
+            center /= mass;
+        }
+
+        // shift the inertia of every fixture to the
+        // shared center via the parallel axis theorem
+        let mut inertia = 0.0;
+        for md in self.fixtures.iter().map(|f| f.mass_data()) {
+            inertia += md.inertia + md.mass * (md.center - center).len_sq();
+        }
+
+        self.set_mass_data(&MassData {
+            area,
+            mass,
+            center,
+            inertia,
+        });
+    }
+
+    /// Returns the center of mass in the local space of the body
+    pub fn local_center(&self) -> &Vec2d {
+        &self.local_center
+    }
+
+    /// Returns the center of mass in world space
+    pub fn world_center(&self) -> Vec2d {
+        self.local_center.transform(&self.transform)
+    }
+
+    /// Returns the linear velocity of the center of mass
+    pub fn linear_velocity(&self) -> &Vec2d {
+        &self.linear_velocity
+    }
+
+    /// Sets the linear velocity of the center of mass.
+    /// A non-zero velocity wakes the body. Static bodies can't move
+    pub fn set_linear_velocity(&mut self, velocity: Vec2d) {
+        if self.body_type == BodyType::Static {
+            return;
+        }
+        if velocity != Vec2d::zero() {
+            self.set_awake(true);
+        }
+        self.linear_velocity = velocity;
+    }
+
+    /// Returns the angular velocity in radians per second
+    pub fn angular_velocity(&self) -> f64 {
+        self.angular_velocity
+    }
+
+    /// Sets the angular velocity in radians per second.
+    /// A non-zero velocity wakes the body. Static bodies can't move
+    pub fn set_angular_velocity(&mut self, velocity: f64) {
+        if self.body_type == BodyType::Static {
+            return;
+        }
+        if velocity != 0.0 {
+            self.set_awake(true);
+        }
+        self.angular_velocity = velocity;
+    }
+
+    /// Returns the velocity of the body at the given point in world space
+    pub fn velocity_at(&self, point: Vec2d) -> Vec2d {
+        self.linear_velocity + (point - self.world_center()).perp() * self.angular_velocity
+    }
+
+    /// Returns the force accumulated since the last step
+    pub fn force(&self) -> &Vec2d {
+        &self.force
+    }
+
+    /// Returns the torque accumulated since the last step
+    pub fn torque(&self) -> f64 {
+        self.torque
+    }
+
+    /// Returns the mass of the body
+    pub fn mass(&self) -> f64 {
+        self.mass
+    }
+
+    /// Returns the inverse mass of the body or 0 if the
+    /// body has infinite mass
+    pub fn inv_mass(&self) -> f64 {
+        self.inv_mass
+    }
+
+    /// Returns the rotational inertia of the body about its center of mass
+    pub fn inertia(&self) -> f64 {
+        self.inertia
+    }
+
+    /// Returns the inverse rotational inertia of the body or 0
+    /// if the body cannot rotate
+    pub fn inv_inertia(&self) -> f64 {
+        self.inv_inertia
+    }
+
+    /// Sets the mass properties of the body, typically computed from
+    /// the shapes attached to it. A dynamic body without positive mass is
+    /// given a mass of 1 so it stays movable, as in Box2D, and a
+    /// non-positive inertia prevents the body from rotating.
+    /// The velocity of the body origin is preserved when the center of mass
+    /// moves. Static and kinematic bodies keep their infinite mass
+    pub fn set_mass_data(&mut self, mass_data: &MassData) {
+        let infinite = MassData {
+            area: mass_data.area,
+            mass: 0.0,
+            center: Vec2d::zero(),
+            inertia: 0.0,
+        };
+        let mass_data = if self.body_type == BodyType::Dynamic {
+            mass_data
+        } else {
+            &infinite
+        };
+
+        self.mass = if mass_data.mass > 0.0 {
+            mass_data.mass
+        } else if self.body_type == BodyType::Dynamic {
+            1.0
+        } else {
+            0.0
+        };
+        self.inv_mass = if self.mass > 0.0 {
+            1.0 / self.mass
+        } else {
+            0.0
+        };
+        self.inertia = mass_data.inertia.max(0.0);
+        self.inv_inertia = if mass_data.inertia > 0.0 {
+            1.0 / mass_data.inertia
+        } else {
+            0.0
+        };
+
+        let old_center = self.world_center();
+        self.local_center = mass_data.center;
+        let new_center = self.world_center();
+        self.linear_velocity += (new_center - old_center).perp() * self.angular_velocity;
+    }
+
+    /// Applies a force at a point in world space, waking the body. If
+    /// the point is not the center of mass a torque is generated as well.
+    /// Only dynamic bodies respond to forces and impulses
+    pub fn apply_force(&mut self, force: Vec2d, point: Vec2d) {
+        if self.body_type != BodyType::Dynamic {
+            return;
+        }
+        self.set_awake(true);
+        self.force += force;
+        self.torque += (point - self.world_center()).cross(force);
+    }
+
+    /// Applies a force to the center of mass, waking the body
+    pub fn apply_force_to_center(&mut self, force: Vec2d) {
+        if self.body_type != BodyType::Dynamic {
+            return;
+        }
+        self.set_awake(true);
+        self.force += force;
+    }
+
+    /// Applies a torque about the center of mass, waking the body
+    pub fn apply_torque(&mut self, torque: f64) {
+        if self.body_type != BodyType::Dynamic {
+            return;
+        }
+        self.set_awake(true);
+        self.torque += torque;
+    }
+
+    /// Applies an impulse at a point in world space, waking the body and
+    /// immediately changing its linear and angular velocity
+    pub fn apply_linear_impulse(&mut self, impulse: Vec2d, point: Vec2d) {
+        if self.body_type != BodyType::Dynamic {
+            return;
+        }
+        self.set_awake(true);
+        self.linear_velocity += impulse * self.inv_mass;
+        self.angular_velocity += self.inv_inertia * (point - self.world_center()).cross(impulse);
+    }
+
+    /// Applies an angular impulse, waking the body and
+    /// immediately changing its angular velocity
+    pub fn apply_angular_impulse(&mut self, impulse: f64) {
+        if self.body_type != BodyType::Dynamic {
+            return;
+        }
+        self.set_awake(true);
+        self.angular_velocity += self.inv_inertia * impulse;
+    }
+
+    /// Clears the accumulated force and torque
+    pub fn clear_forces(&mut self) {
+        self.force = Vec2d::zero();
+        self.torque = 0.0;
+    }
+
+    /// Integrates the velocity of the body over `dt` from gravity and
+    /// the accumulated forces. Only movable dynamic bodies are affected
+    pub(crate) fn integrate_velocity(&mut self, gravity: Vec2d, dt: f64) {
+        if self.body_type != BodyType::Dynamic || self.inv_mass == 0.0 {
+            return;
+        }
+        self.linear_velocity += (gravity + self.force * self.inv_mass) * dt;
+        self.angular_velocity += self.torque * self.inv_inertia * dt;
+    }
+
+    /// Sets the transform and velocities solved by the world,
+    /// leaving the body as awake or asleep as it was
+    pub(crate) fn set_solved_state(&mut self, transform: Transform, linear_velocity: Vec2d, angular_velocity: f64) {
+        self.transform = transform;
+        self.linear_velocity = linear_velocity;
+        self.angular_velocity = angular_velocity;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use collision::shapes::Convex;
+    use common::{Transform, Vec2d};
+    use dynamics::{BodyType, Fixture, RigidBody};
+
+    #[test]
+    fn test_body_mass_from_convex() {
+        let shape = Convex::new(&[Vec2d::new(0.0, 0.0),
+                                  Vec2d::new(2.0, 0.0),
+                                  Vec2d::new(2.0, 2.0),
+                                  Vec2d::new(0.0, 2.0)])
+                        .unwrap();
+        let mut body = RigidBody::new(Transform::identity());
+        body.add_fixture(Fixture::new(shape, 0.5));
+        assert!((body.mass() - 2.0).abs() < 1e-9);
+        assert!((body.inv_mass() - 0.5).abs() < 1e-9);
+        assert_eq!(Vec2d::new(1.0, 1.0), body.world_center());
+
+        // an off-center impulse spins the body
+        body.apply_linear_impulse(Vec2d::new(0.0, 1.0), Vec2d::new(2.0, 1.0));
+        assert!((body.linear_velocity().y - 0.5).abs() < 1e-9);
+        assert!((body.angular_velocity() - body.inv_inertia()).abs() < 1e-9);
+        assert!(body.angular_velocity() > 0.0);
+    }
+
+    #[test]
+    fn test_body_types() {
+        let shape = Convex::new(&[Vec2d::new(0.0, 0.0),
+                                  Vec2d::new(2.0, 0.0),
+                                  Vec2d::new(2.0, 2.0),
+                                  Vec2d::new(0.0, 2.0)])
+                        .unwrap();
+        let mut body = RigidBody::new(Transform::identity());
+        body.add_fixture(Fixture::new(shape, 0.5));
+        body.set_linear_velocity(Vec2d::new(1.0, 0.0));
+
+        // kinematic bodies keep their velocity under forces and impulses
+        body.set_body_type(BodyType::Kinematic);
+        assert_eq!(0.0, body.inv_mass());
+        assert_eq!(0.0, body.inv_inertia());
+        assert_eq!(Vec2d::zero(), *body.local_center());
+        body.apply_force_to_center(Vec2d::new(5.0, 0.0));
+        body.apply_linear_impulse(Vec2d::new(0.0, 1.0), Vec2d::new(2.0, 1.0));
+        assert_eq!(Vec2d::zero(), *body.force());
+        assert_eq!(Vec2d::new(1.0, 0.0), *body.linear_velocity());
+
+        // static bodies don't move at all
+        body.set_body_type(BodyType::Static);
+        assert_eq!(Vec2d::zero(), *body.linear_velocity());
+        body.set_linear_velocity(Vec2d::new(1.0, 0.0));
+        assert_eq!(Vec2d::zero(), *body.linear_velocity());
+
+        body.set_body_type(BodyType::Dynamic);
+        assert!((body.mass() - 2.0).abs() < 1e-9);
+        assert_eq!(Vec2d::new(1.0, 1.0), body.world_center());
+
+        // massless dynamic bodies still move
+        let mut body = RigidBody::new(Transform::identity());
+        body.add_fixture(Fixture::new(Convex::new(&[Vec2d::new(0.0, 0.0),
+                                                     Vec2d::new(1.0, 0.0),
+                                                     Vec2d::new(0.0, 1.0)])
+                                          .unwrap(),
+                                      0.0));
+        assert_eq!(1.0, body.mass());
+        assert_eq!(1.0, body.inv_mass());
+        assert_eq!(0.0, body.inv_inertia());
+    }
+}
@@ -0,0 +1,193 @@
+use collision::Manifold;
+use collision::broad_phase::ProxyId;
+use dynamics::{BodyHandle, Fixture};
+
+/// Contact describes a pair of fixtures on different bodies whose
+/// `Aabb`s overlap in the broad phase of a `World`. The contact persists
+/// for as long as the `Aabb`s overlap, so the impulses applied in one
+/// step can be carried over to the next step
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contact {
+    body_a: BodyHandle,
+    fixture_a: usize,
+    body_b: BodyHandle,
+    fixture_b: usize,
+    proxies: (ProxyId, ProxyId),
+    manifold: Option<Manifold>,
+    friction: f64,
+    restitution: f64,
+    tangent_speed: f64,
+    enabled: bool,
+    passing: bool,
+    normal_impulses: [f64; 2],
+    tangent_impulses: [f64; 2],
+}
+
+impl Contact {
+    /// Creates a new contact between fixture `fixture_a` of `body_a`
+    /// and fixture `fixture_b` of `body_b` for the broad phase pair
+    /// `proxies`, mixing the friction and restitution of both fixtures.
+    /// The contact is not touching until it is updated with a manifold
+    pub(crate) fn new(body_a: BodyHandle,
+                      fixture_a: (usize, &Fixture),
+                      body_b: BodyHandle,
+                      fixture_b: (usize, &Fixture),
+                      proxies: (ProxyId, ProxyId))
+                      -> Contact {
+        Contact {
+            body_a,
+            fixture_a: fixture_a.0,
+            body_b,
+            fixture_b: fixture_b.0,
+            proxies,
+            manifold: None,
+            friction: (fixture_a.1.friction() * fixture_b.1.friction()).sqrt(),
+            restitution: fixture_a.1.restitution().max(fixture_b.1.restitution()),
+            tangent_speed: 0.0,
+            enabled: true,
+            passing: false,
+            normal_impulses: [0.0; 2],
+            tangent_impulses: [0.0; 2],
+        }
+    }
+
+    /// Returns the handle of the first body
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    /// Returns the index of the touching fixture on the first body
+    pub fn fixture_a(&self) -> usize {
+        self.fixture_a
+    }
+
+    /// Returns the handle of the second body
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    /// Returns the index of the touching fixture on the second body
+    pub fn fixture_b(&self) -> usize {
+        self.fixture_b
+    }
+
+    /// Returns the broad phase pair of the contact
+    pub(crate) fn proxies(&self) -> (ProxyId, ProxyId) {
+        self.proxies
+    }
+
+    /// Returns if the fixtures touched during the last step
+    pub fn is_touching(&self) -> bool {
+        self.manifold.is_some()
+    }
+
+    /// Returns the contact manifold or `None` if the fixtures did not
+    /// touch during the last step. The normal points from the first
+    /// body towards the second body
+    pub fn manifold(&self) -> Option<&Manifold> {
+        self.manifold.as_ref()
+    }
+
+    /// Replaces the manifold of the contact and enables the contact.
+    /// The impulses of points produced by the same features as in the
+    /// previous manifold are kept to warm start the solver, the others
+    /// are reset
+    pub(crate) fn update(&mut self, manifold: Option<Manifold>) {
+        self.enabled = true;
+        // a contact only passes through a one way fixture while touching
+        self.passing &= manifold.is_some();
+        let mut normal_impulses = [0.0; 2];
+        let mut tangent_impulses = [0.0; 2];
+        if let (Some(old), Some(new)) = (self.manifold.as_ref(), manifold.as_ref()) {
+            for (i, p) in new.points().iter().enumerate() {
+                if let Some(j) = old.points().iter().position(|o| o.id() == p.id()) {
+                    normal_impulses[i] = self.normal_impulses[j];
+                    tangent_impulses[i] = self.tangent_impulses[j];
+                }
+            }
+        }
+        self.manifold = manifold;
+        self.normal_impulses = normal_impulses;
+        self.tangent_impulses = tangent_impulses;
+    }
+
+    /// Returns the friction of the contact
+    pub fn friction(&self) -> f64 {
+        self.friction
+    }
+
+    /// Sets the friction of the contact, overriding the mixed
+    /// friction of the fixtures for as long as the contact persists
+    pub fn set_friction(&mut self, friction: f64) {
+        debug_assert!(friction >= 0.0);
+        self.friction = friction;
+    }
+
+    /// Returns the restitution of the contact
+    pub fn restitution(&self) -> f64 {
+        self.restitution
+    }
+
+    /// Sets the restitution of the contact, overriding the mixed
+    /// restitution of the fixtures for as long as the contact persists
+    pub fn set_restitution(&mut self, restitution: f64) {
+        debug_assert!(restitution >= 0.0);
+        self.restitution = restitution;
+    }
+
+    /// Returns the speed the surfaces of the contact slide along each other
+    pub fn tangent_speed(&self) -> f64 {
+        self.tangent_speed
+    }
+
+    /// Sets the speed the surfaces of the contact slide along each other
+    /// like a conveyor belt. A positive speed moves the second body along
+    /// the normal rotated clockwise relative to the first body
+    pub fn set_tangent_speed(&mut self, speed: f64) {
+        self.tangent_speed = speed;
+    }
+
+    /// Returns if the contact is solved
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Sets if the contact is solved. Contacts are enabled
+    /// again every step before they're handed to pre-solve
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Returns if one fixture passes through the other one way fixture
+    pub(crate) fn is_passing(&self) -> bool {
+        self.passing
+    }
+
+    /// Sets if one fixture passes through the other one way fixture
+    pub(crate) fn set_passing(&mut self, passing: bool) {
+        self.passing = passing;
+    }
+
+    /// Returns the impulses applied along the normal at each
+    /// contact point during the last step
+    pub fn normal_impulses(&self) -> &[f64] {
+        &self.normal_impulses[..self.point_count()]
+    }
+
+    /// Returns the friction impulses applied along the tangent
+    /// at each contact point during the last step
+    pub fn tangent_impulses(&self) -> &[f64] {
+        &self.tangent_impulses[..self.point_count()]
+    }
+
+    /// Returns the number of points of the manifold
+    fn point_count(&self) -> usize {
+        self.manifold.as_ref().map_or(0, |m| m.points().len())
+    }
+
+    /// Stores the impulses applied at the contact point `i`
+    pub(crate) fn set_impulses(&mut self, i: usize, normal: f64, tangent: f64) {
+        self.normal_impulses[i] = normal;
+        self.tangent_impulses[i] = tangent;
+    }
+}
\ No newline at end of file
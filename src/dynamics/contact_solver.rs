@@ -0,0 +1,221 @@
+use std::vec::Vec;
+use collision::Manifold;
+use common::Vec2d;
+use dynamics::{BodyHandle, Contact};
+use dynamics::solver_body::SolverBody;
+use util;
+
+/// Relative normal velocities below this threshold in
+/// meters per second are treated as inelastic
+const VELOCITY_THRESHOLD: f64 = 1.0;
+
+/// The fraction of the overlap removed per position iteration
+const BAUMGARTE: f64 = 0.2;
+
+/// The solver state of a single contact point
+#[derive(Debug, Clone, Copy)]
+struct ConstraintPoint {
+    local_point: Vec2d,
+    ra: Vec2d,
+    rb: Vec2d,
+    normal_impulse: f64,
+    tangent_impulse: f64,
+    normal_mass: f64,
+    tangent_mass: f64,
+    velocity_bias: f64,
+}
+
+/// The solver state of a contact
+#[derive(Debug, Clone)]
+struct ContactConstraint {
+    contact: usize,
+    a: usize,
+    b: usize,
+    normal: Vec2d,
+    friction: f64,
+    tangent_speed: f64,
+    manifold: Manifold,
+    points: Vec<ConstraintPoint>,
+}
+
+/// ContactSolver resolves contacts with sequential impulses. Velocities
+/// are solved by accumulating clamped impulses at every contact point,
+/// then the remaining overlap is pushed apart in a separate position phase.
+/// Algorithm sourced from Erin Catto's Box2D: https://github.com/erincatto/Box2D
+#[derive(Debug, Clone)]
+pub(crate) struct ContactSolver {
+    constraints: Vec<ContactConstraint>,
+}
+
+impl ContactSolver {
+    /// Prepares the touching and enabled contacts at the indices `solved`
+    /// of `contacts` for solving, starting from the impulses stored in the
+    /// contacts. `index` maps the handle of a body to the index of its
+    /// state in `bodies`
+    pub fn new(contacts: &[Contact],
+               solved: &[usize],
+               bodies: &[SolverBody],
+               index: &dyn Fn(BodyHandle) -> usize)
+               -> ContactSolver {
+        let mut constraints = Vec::with_capacity(solved.len());
+        for &ci in solved {
+            let contact = &contacts[ci];
+            let manifold = match contact.manifold() {
+                Some(manifold) if contact.is_enabled() => *manifold,
+                _ => continue,
+            };
+            let a = index(contact.body_a());
+            let b = index(contact.body_b());
+            let (ba, bb) = (&bodies[a], &bodies[b]);
+            let normal = *manifold.normal();
+            let tangent = -normal.perp();
+
+            let mut points = Vec::with_capacity(2);
+            for (i, p) in manifold.points().iter().enumerate() {
+                let ra = *p.point() - ba.center;
+                let rb = *p.point() - bb.center;
+                let mass = |axis: Vec2d| {
+                    let rna = ra.cross(axis);
+                    let rnb = rb.cross(axis);
+                    let k = ba.inv_mass + bb.inv_mass + ba.inv_inertia * rna * rna +
+                            bb.inv_inertia * rnb * rnb;
+                    if k > 0.0 { 1.0 / k } else { 0.0 }
+                };
+
+                // bounce off with the approach velocity when closing in fast enough
+                let vn = (bb.velocity_at(rb) - ba.velocity_at(ra)) * normal;
+                let velocity_bias = if vn < -VELOCITY_THRESHOLD {
+                    -contact.restitution() * vn
+                } else {
+                    0.0
+                };
+
+                points.push(ConstraintPoint {
+                    local_point: *p.local_point(),
+                    ra,
+                    rb,
+                    normal_impulse: contact.normal_impulses()[i],
+                    tangent_impulse: contact.tangent_impulses()[i],
+                    normal_mass: mass(normal),
+                    tangent_mass: mass(tangent),
+                    velocity_bias,
+                });
+            }
+
+            constraints.push(ContactConstraint {
+                contact: ci,
+                a,
+                b,
+                normal,
+                friction: contact.friction(),
+                tangent_speed: contact.tangent_speed(),
+                manifold,
+                points,
+            });
+        }
+        ContactSolver { constraints }
+    }
+
+    /// Applies the impulses the solver starts from to the bodies. The
+    /// impulses of the previous step are usually close to the solution,
+    /// so the iterations only need to account for what has changed
+    pub fn warm_start(&self, bodies: &mut [SolverBody]) {
+        for c in &self.constraints {
+            let tangent = -c.normal.perp();
+            for p in &c.points {
+                let impulse = c.normal * p.normal_impulse + tangent * p.tangent_impulse;
+                bodies[c.a].apply_impulse(-impulse, p.ra);
+                bodies[c.b].apply_impulse(impulse, p.rb);
+            }
+        }
+    }
+
+    /// Discards the impulses the solver starts from
+    pub fn reset_impulses(&mut self) {
+        for p in self.constraints.iter_mut().flat_map(|c| c.points.iter_mut()) {
+            p.normal_impulse = 0.0;
+            p.tangent_impulse = 0.0;
+        }
+    }
+
+    /// Runs a single velocity iteration over every contact. Friction is
+    /// solved first as non-penetration matters most
+    pub fn solve_velocity(&mut self, bodies: &mut [SolverBody]) {
+        for c in &mut self.constraints {
+            let mut ba = bodies[c.a];
+            let mut bb = bodies[c.b];
+            let tangent = -c.normal.perp();
+
+            for p in &mut c.points {
+                // coulomb friction bounded by the current normal impulse
+                let vt = (bb.velocity_at(p.rb) - ba.velocity_at(p.ra)) * tangent - c.tangent_speed;
+                let max_friction = c.friction * p.normal_impulse;
+                let impulse = (p.tangent_impulse - p.tangent_mass * vt)
+                                  .clamp(-max_friction, max_friction);
+                let lambda = impulse - p.tangent_impulse;
+                p.tangent_impulse = impulse;
+                ba.apply_impulse(tangent * -lambda, p.ra);
+                bb.apply_impulse(tangent * lambda, p.rb);
+            }
+
+            for p in &mut c.points {
+                // the accumulated impulse may only push the bodies apart
+                let vn = (bb.velocity_at(p.rb) - ba.velocity_at(p.ra)) * c.normal;
+                let impulse = (p.normal_impulse - p.normal_mass * (vn - p.velocity_bias))
+                                  .max(0.0);
+                let lambda = impulse - p.normal_impulse;
+                p.normal_impulse = impulse;
+                ba.apply_impulse(c.normal * -lambda, p.ra);
+                bb.apply_impulse(c.normal * lambda, p.rb);
+            }
+
+            bodies[c.a] = ba;
+            bodies[c.b] = bb;
+        }
+    }
+
+    /// Runs a single position iteration over every contact, returning
+    /// true once no contact overlaps by more than a few `LINEAR_SLOP`
+    pub fn solve_position(&self, bodies: &mut [SolverBody]) -> bool {
+        let mut min_separation: f64 = 0.0;
+        for c in &self.constraints {
+            let mut ba = bodies[c.a];
+            let mut bb = bodies[c.b];
+
+            for p in &c.points {
+                let (normal, point, separation) = c.manifold.evaluate(p.local_point,
+                                                                      &ba.transform(),
+                                                                      &bb.transform());
+                let ra = point - ba.center;
+                let rb = point - bb.center;
+                min_separation = min_separation.min(separation);
+
+                // leave a slop of overlap so contacts persist between steps
+                let correction = (BAUMGARTE * (separation + util::LINEAR_SLOP))
+                                     .clamp(-util::MAX_LINEAR_CORRECTION, 0.0);
+                let rna = ra.cross(normal);
+                let rnb = rb.cross(normal);
+                let k = ba.inv_mass + bb.inv_mass + ba.inv_inertia * rna * rna +
+                        bb.inv_inertia * rnb * rnb;
+                let impulse = if k > 0.0 { -correction / k } else { 0.0 };
+
+                ba.apply_position_impulse(normal * -impulse, ra);
+                bb.apply_position_impulse(normal * impulse, rb);
+            }
+
+            bodies[c.a] = ba;
+            bodies[c.b] = bb;
+        }
+        min_separation >= -3.0 * util::LINEAR_SLOP
+    }
+
+    /// Stores the accumulated impulses in the contacts
+    /// the solver was created from
+    pub fn store_impulses(&self, contacts: &mut [Contact]) {
+        for c in &self.constraints {
+            for (i, p) in c.points.iter().enumerate() {
+                contacts[c.contact].set_impulses(i, p.normal_impulse, p.tangent_impulse);
+            }
+        }
+    }
+}
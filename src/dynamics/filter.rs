@@ -0,0 +1,114 @@
+use std::fmt;
+use dynamics::{BodyHandle, Fixture};
+
+/// Filter decides which fixtures collide with each other. Two fixtures
+/// collide if each one's category is part of the other one's mask,
+/// unless they share a group index other than 0. Fixtures in the same
+/// positive group always collide, fixtures in the same negative group
+/// never collide
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Filter {
+    /// The categories the fixture belongs to, usually a single bit
+    pub category_bits: u16,
+    /// The categories the fixture collides with
+    pub mask_bits: u16,
+    /// The group of the fixture, overriding the categories
+    /// and masks for fixtures of the same group
+    pub group_index: i16,
+}
+
+impl Filter {
+    /// Returns if fixtures with this and the other filter collide
+    pub fn should_collide(&self, other: &Filter) -> bool {
+        if self.group_index == other.group_index && self.group_index != 0 {
+            return self.group_index > 0;
+        }
+        self.mask_bits & other.category_bits != 0 && self.category_bits & other.mask_bits != 0
+    }
+}
+
+impl Default for Filter {
+    /// Returns a filter in the first category colliding
+    /// with all categories and belonging to no group
+    fn default() -> Filter {
+        Filter {
+            category_bits: 0x0001,
+            mask_bits: 0xFFFF,
+            group_index: 0,
+        }
+    }
+}
+
+/// ContactFilter implements game specific rules deciding which fixtures
+/// of a `World` collide, on top of the `Filter` of each fixture. It is
+/// asked for every pair of fixtures whose `Aabb`s overlap every step
+/// before their contact is updated. Closures taking the same arguments
+/// as `should_collide` are contact filters
+pub trait ContactFilter {
+    /// Returns if fixture `fixture_a` of `body_a` collides with fixture
+    /// `fixture_b` of `body_b`, given that their filters let them collide
+    fn should_collide(&self,
+                      body_a: BodyHandle,
+                      fixture_a: &Fixture,
+                      body_b: BodyHandle,
+                      fixture_b: &Fixture)
+                      -> bool;
+}
+
+impl<F> ContactFilter for F
+    where F: Fn(BodyHandle, &Fixture, BodyHandle, &Fixture) -> bool
+{
+    fn should_collide(&self,
+                      body_a: BodyHandle,
+                      fixture_a: &Fixture,
+                      body_b: BodyHandle,
+                      fixture_b: &Fixture)
+                      -> bool {
+        self(body_a, fixture_a, body_b, fixture_b)
+    }
+}
+
+impl fmt::Debug for dyn ContactFilter + Send {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ContactFilter")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dynamics::Filter;
+
+    #[test]
+    fn test_filter_should_collide() {
+        let player = Filter {
+            category_bits: 0x0002,
+            mask_bits: !0x0004,
+            group_index: 0,
+        };
+        let bullet = Filter {
+            category_bits: 0x0004,
+            ..Filter::default()
+        };
+        let wall = Filter::default();
+        assert!(wall.should_collide(&player) && player.should_collide(&wall));
+        assert!(wall.should_collide(&bullet));
+
+        // either mask may rule out the pair
+        assert!(!player.should_collide(&bullet) && !bullet.should_collide(&player));
+
+        // a shared group overrides the masks
+        let ragdoll = Filter {
+            group_index: -1,
+            ..Filter::default()
+        };
+        assert!(!ragdoll.should_collide(&ragdoll));
+        assert!(ragdoll.should_collide(&wall));
+        let glued = Filter {
+            group_index: 1,
+            mask_bits: 0,
+            ..Filter::default()
+        };
+        assert!(glued.should_collide(&glued));
+        assert!(!glued.should_collide(&wall));
+    }
+}
@@ -0,0 +1,135 @@
+use collision::{Aabb, HasAabb};
+use collision::broad_phase::ProxyId;
+use collision::shapes::{MassData, Shape};
+use common::{Transform, Vec2d};
+use dynamics::Filter;
+
+/// The default friction coefficient of a `Fixture`
+pub const DEFAULT_FRICTION: f64 = 0.2;
+
+/// Fixture attaches a shape to a body along with
+/// the material properties of the shape
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    shape: Shape,
+    density: f64,
+    friction: f64,
+    restitution: f64,
+    filter: Filter,
+    sensor: bool,
+    one_way: Option<Vec2d>,
+    proxy: Option<ProxyId>,
+}
+
+impl Fixture {
+    /// Creates a new fixture for the given shape and density with
+    /// `DEFAULT_FRICTION`, no restitution and the default `Filter`
+    pub fn new<S: Into<Shape>>(shape: S, density: f64) -> Fixture {
+        Fixture {
+            shape: shape.into(),
+            density,
+            friction: DEFAULT_FRICTION,
+            restitution: 0.0,
+            filter: Filter::default(),
+            sensor: false,
+            one_way: None,
+            proxy: None,
+        }
+    }
+
+    /// Returns the shape of the fixture
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    /// Returns the density of the fixture
+    pub fn density(&self) -> f64 {
+        self.density
+    }
+
+    /// Returns the coulomb friction coefficient of the fixture
+    pub fn friction(&self) -> f64 {
+        self.friction
+    }
+
+    /// Sets the coulomb friction coefficient of the fixture, usually
+    /// in `[0, 1]`. The friction of a contact is the geometric mean
+    /// of the friction of both fixtures
+    pub fn set_friction(&mut self, friction: f64) {
+        debug_assert!(friction >= 0.0);
+        self.friction = friction;
+    }
+
+    /// Returns the restitution of the fixture
+    pub fn restitution(&self) -> f64 {
+        self.restitution
+    }
+
+    /// Sets the restitution of the fixture in `[0, 1]` where 0 means
+    /// no bounce and 1 means a perfectly elastic bounce. The restitution
+    /// of a contact is the larger restitution of both fixtures
+    pub fn set_restitution(&mut self, restitution: f64) {
+        debug_assert!(restitution >= 0.0);
+        self.restitution = restitution;
+    }
+
+    /// Returns the collision filter of the fixture
+    pub fn filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    /// Sets the collision filter of the fixture
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+    }
+
+    /// Returns if the fixture is a sensor
+    pub fn is_sensor(&self) -> bool {
+        self.sensor
+    }
+
+    /// Sets if the fixture is a sensor. Sensors detect the fixtures
+    /// overlapping them without colliding with them
+    pub fn set_sensor(&mut self, sensor: bool) {
+        self.sensor = sensor;
+    }
+
+    /// Returns the normal of a one way fixture in the
+    /// local space of its body or `None` if the fixture
+    /// collides from every direction
+    pub fn one_way(&self) -> Option<Vec2d> {
+        self.one_way
+    }
+
+    /// Makes the fixture one way, so that it only collides with fixtures
+    /// that start touching it from the side the normal points to. The
+    /// normal is given in the local space of the body and turns with it.
+    /// Fixtures that start touching it from any other side pass through
+    /// for as long as they keep touching it. `None` makes the fixture
+    /// collide from every direction
+    pub fn set_one_way(&mut self, normal: Option<Vec2d>) {
+        self.one_way = normal.map(|n| n.normalize());
+    }
+
+    /// Returns the mass properties of the fixture
+    pub fn mass_data(&self) -> MassData {
+        self.shape.mass_data(self.density)
+    }
+
+    /// Returns the broad phase proxy of the fixture or `None`
+    /// if the fixture is not part of a world
+    pub(crate) fn proxy(&self) -> Option<ProxyId> {
+        self.proxy
+    }
+
+    /// Sets the broad phase proxy of the fixture
+    pub(crate) fn set_proxy(&mut self, proxy: Option<ProxyId>) {
+        self.proxy = proxy;
+    }
+}
+
+impl HasAabb for Fixture {
+    fn aabb(&self, transform: &Transform) -> Aabb {
+        self.shape.aabb(transform)
+    }
+}
\ No newline at end of file
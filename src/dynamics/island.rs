@@ -0,0 +1,139 @@
+use std::vec::Vec;
+
+/// The part a body plays in building islands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Node {
+    /// The body never moves, so islands don't grow across it and
+    /// it may be part of several islands. Vacant slots are static
+    Static,
+    /// The body is asleep and only joins an island connected to an awake body
+    Asleep,
+    /// The body is awake and starts an island
+    Awake,
+}
+
+/// Island is a set of bodies connected through touching contacts and
+/// joints, along with those contacts and joints. Bodies of different
+/// islands can't affect each other during a step, so every island is
+/// solved and put to sleep on its own
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Island {
+    pub bodies: Vec<usize>,
+    pub contacts: Vec<usize>,
+    pub joints: Vec<usize>,
+}
+
+/// An edge of the constraint graph
+#[derive(Debug, Clone, Copy)]
+enum Edge {
+    Contact(usize),
+    Joint(usize),
+}
+
+/// Builds the islands of every awake body. `contacts` holds the bodies of
+/// each touching contact or `None` for contacts that don't touch, `joints`
+/// the bodies of each joint. Bodies, contacts and joints are listed in the
+/// islands by index, sorted so they are solved in a consistent order
+pub(crate) fn build(nodes: &[Node],
+                    contacts: &[Option<(usize, usize)>],
+                    joints: &[(usize, usize)])
+                    -> Vec<Island> {
+    let mut edges = vec![Vec::new(); nodes.len()];
+    for (i, (a, b)) in contacts.iter().enumerate().filter_map(|(i, c)| c.map(|c| (i, c))) {
+        edges[a].push((Edge::Contact(i), b));
+        edges[b].push((Edge::Contact(i), a));
+    }
+    for (i, &(a, b)) in joints.iter().enumerate() {
+        edges[a].push((Edge::Joint(i), b));
+        edges[b].push((Edge::Joint(i), a));
+    }
+
+    let mut visited = vec![false; nodes.len()];
+    let mut contact_visited = vec![false; contacts.len()];
+    let mut joint_visited = vec![false; joints.len()];
+    let mut islands = Vec::new();
+    let mut stack = Vec::new();
+    for (seed, &node) in nodes.iter().enumerate() {
+        if node != Node::Awake || visited[seed] {
+            continue;
+        }
+
+        let mut island = Island::default();
+        visited[seed] = true;
+        stack.push(seed);
+        while let Some(body) = stack.pop() {
+            island.bodies.push(body);
+            if nodes[body] == Node::Static {
+                continue;
+            }
+            for &(edge, other) in &edges[body] {
+                match edge {
+                    Edge::Contact(i) if !contact_visited[i] => {
+                        contact_visited[i] = true;
+                        island.contacts.push(i);
+                    }
+                    Edge::Joint(i) if !joint_visited[i] => {
+                        joint_visited[i] = true;
+                        island.joints.push(i);
+                    }
+                    _ => continue,
+                }
+                if !visited[other] {
+                    visited[other] = true;
+                    stack.push(other);
+                }
+            }
+        }
+
+        // static bodies may be part of other islands as well
+        for &body in &island.bodies {
+            if nodes[body] == Node::Static {
+                visited[body] = false;
+            }
+        }
+        island.bodies.sort_unstable();
+        island.contacts.sort_unstable();
+        island.joints.sort_unstable();
+        islands.push(island);
+    }
+    islands
+}
+
+#[cfg(test)]
+mod test {
+    use dynamics::island::{build, Node};
+
+    #[test]
+    fn test_island_build() {
+        // two piles on the same ground, one of them asleep,
+        // and a sleeping body jointed to an awake one
+        let nodes = [Node::Static,
+                     Node::Awake,
+                     Node::Asleep,
+                     Node::Asleep,
+                     Node::Asleep,
+                     Node::Awake,
+                     Node::Asleep];
+        let contacts = [Some((0, 1)), Some((1, 2)), None, Some((0, 3)), Some((3, 4)), Some((2, 0))];
+        let joints = [(6, 5)];
+        let islands = build(&nodes, &contacts, &joints);
+        assert_eq!(2, islands.len());
+
+        assert_eq!(vec![0, 1, 2], islands[0].bodies);
+        assert_eq!(vec![0, 1, 5], islands[0].contacts);
+        assert!(islands[0].joints.is_empty());
+        assert_eq!(vec![5, 6], islands[1].bodies);
+        assert!(islands[1].contacts.is_empty());
+        assert_eq!(vec![0], islands[1].joints);
+
+        // the sleeping pile only joins an island once it's touched
+        let nodes = [Node::Static, Node::Asleep, Node::Asleep, Node::Awake];
+        let contacts = [Some((0, 1)), Some((1, 2)), Some((3, 0))];
+        let islands = build(&nodes, &contacts, &[]);
+        assert_eq!(1, islands.len());
+        assert_eq!(vec![0, 3], islands[0].bodies);
+        let contacts = [Some((0, 1)), Some((1, 2)), Some((3, 2))];
+        let islands = build(&nodes, &contacts, &[]);
+        assert_eq!(vec![0, 1, 2, 3], islands[0].bodies);
+    }
+}
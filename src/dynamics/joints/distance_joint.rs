@@ -0,0 +1,289 @@
+use std::f64::consts::PI;
+use common::Vec2d;
+use dynamics::BodyHandle;
+use dynamics::solver_body::{SolverBody, TimeStep};
+use util;
+
+/// DistanceJoint keeps an anchor point on each of two bodies at a
+/// fixed distance, as if they were connected by a massless rod.
+///
+/// # Remarks
+///
+/// Given a frequency the rod becomes a damped spring that oscillates
+/// about its length with that frequency, using the soft constraint
+/// formulation of Erin Catto's Box2D: https://github.com/erincatto/Box2D
+#[derive(Debug, Clone)]
+pub struct DistanceJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    local_anchor_a: Vec2d,
+    local_anchor_b: Vec2d,
+    length: f64,
+    frequency: f64,
+    damping_ratio: f64,
+    collide_connected: bool,
+
+    // solver state
+    impulse: f64,
+    a: usize,
+    b: usize,
+    ra: Vec2d,
+    rb: Vec2d,
+    u: Vec2d,
+    mass: f64,
+    gamma: f64,
+    bias: f64,
+}
+
+impl DistanceJoint {
+    /// Creates a new rigid distance joint keeping `local_anchor_a` in
+    /// the local space of `body_a` at `length` from `local_anchor_b`
+    /// in the local space of `body_b`
+    pub fn new(body_a: BodyHandle,
+               body_b: BodyHandle,
+               local_anchor_a: Vec2d,
+               local_anchor_b: Vec2d,
+               length: f64)
+               -> DistanceJoint {
+        debug_assert!(length >= 0.0);
+        DistanceJoint {
+            body_a,
+            body_b,
+            local_anchor_a,
+            local_anchor_b,
+            length,
+            frequency: 0.0,
+            damping_ratio: 0.0,
+            collide_connected: false,
+            impulse: 0.0,
+            a: 0,
+            b: 0,
+            ra: Vec2d::zero(),
+            rb: Vec2d::zero(),
+            u: Vec2d::zero(),
+            mass: 0.0,
+            gamma: 0.0,
+            bias: 0.0,
+        }
+    }
+
+    /// Returns the handle of the first body
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    /// Returns the handle of the second body
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    /// Returns the anchor in the local space of the first body
+    pub fn local_anchor_a(&self) -> &Vec2d {
+        &self.local_anchor_a
+    }
+
+    /// Returns the anchor in the local space of the second body
+    pub fn local_anchor_b(&self) -> &Vec2d {
+        &self.local_anchor_b
+    }
+
+    /// Returns the distance kept between the anchors
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    /// Sets the distance kept between the anchors
+    pub fn set_length(&mut self, length: f64) {
+        debug_assert!(length >= 0.0);
+        self.length = length;
+    }
+
+    /// Returns the oscillation frequency in Hertz, 0 for a rigid joint
+    pub fn frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// Sets the oscillation frequency in Hertz. A frequency of 0
+    /// makes the joint rigid, any other frequency turns it into a
+    /// spring. Frequencies above half the step rate are unstable
+    pub fn set_frequency(&mut self, frequency: f64) {
+        debug_assert!(frequency >= 0.0);
+        self.frequency = frequency;
+    }
+
+    /// Returns the damping ratio of the spring
+    pub fn damping_ratio(&self) -> f64 {
+        self.damping_ratio
+    }
+
+    /// Sets the damping ratio of the spring. 0 doesn't damp
+    /// at all, 1 damps critically
+    pub fn set_damping_ratio(&mut self, ratio: f64) {
+        debug_assert!(ratio >= 0.0);
+        self.damping_ratio = ratio;
+    }
+
+    /// Returns if the fixtures of the jointed bodies collide with each other
+    pub fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    /// Sets if the fixtures of the jointed bodies collide with each other
+    pub fn set_collide_connected(&mut self, collide: bool) {
+        self.collide_connected = collide;
+    }
+
+    /// Returns the force the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2d {
+        self.u * (self.impulse * inv_dt)
+    }
+
+    /// Returns the torque the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_torque(&self, _inv_dt: f64) -> f64 {
+        0.0
+    }
+
+    pub(crate) fn init_velocity(&mut self,
+                                bodies: &mut [SolverBody],
+                                index: &dyn Fn(BodyHandle) -> usize,
+                                step: &TimeStep) {
+        self.a = index(self.body_a);
+        self.b = index(self.body_b);
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        self.ra = a.offset(self.local_anchor_a);
+        self.rb = b.offset(self.local_anchor_b);
+        let d = (b.center + self.rb) - (a.center + self.ra);
+        let length = d.len();
+        self.u = if length > util::LINEAR_SLOP {
+            d / length
+        } else {
+            Vec2d::zero()
+        };
+
+        let cra = self.ra.cross(self.u);
+        let crb = self.rb.cross(self.u);
+        let mut inv_mass = a.inv_mass + a.inv_inertia * cra * cra + b.inv_mass + b.inv_inertia * crb * crb;
+        self.mass = if inv_mass != 0.0 { 1.0 / inv_mass } else { 0.0 };
+
+        if self.frequency > 0.0 {
+            // spring stiffness and damping of the effective mass
+            let omega = 2.0 * PI * self.frequency;
+            let damping = 2.0 * self.mass * self.damping_ratio * omega;
+            let stiffness = self.mass * omega * omega;
+
+            let h = step.dt;
+            self.gamma = h * (damping + h * stiffness);
+            self.gamma = if self.gamma != 0.0 { 1.0 / self.gamma } else { 0.0 };
+            self.bias = (length - self.length) * h * stiffness * self.gamma;
+
+            inv_mass += self.gamma;
+            self.mass = if inv_mass != 0.0 { 1.0 / inv_mass } else { 0.0 };
+        } else {
+            self.gamma = 0.0;
+            self.bias = 0.0;
+        }
+
+        if step.warm_starting {
+            let impulse = self.u * self.impulse;
+            a.apply_impulse(-impulse, self.ra);
+            b.apply_impulse(impulse, self.rb);
+        } else {
+            self.impulse = 0.0;
+        }
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_velocity(&mut self, bodies: &mut [SolverBody], _step: &TimeStep) {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        let cdot = self.u * (b.velocity_at(self.rb) - a.velocity_at(self.ra));
+        let lambda = -self.mass * (cdot + self.bias + self.gamma * self.impulse);
+        self.impulse += lambda;
+        let impulse = self.u * lambda;
+        a.apply_impulse(-impulse, self.ra);
+        b.apply_impulse(impulse, self.rb);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_position(&mut self, bodies: &mut [SolverBody]) -> bool {
+        // springs are meant to stretch
+        if self.frequency > 0.0 {
+            return true;
+        }
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        let ra = a.offset(self.local_anchor_a);
+        let rb = b.offset(self.local_anchor_b);
+        let d = (b.center + rb) - (a.center + ra);
+        let length = d.len();
+        let u = if length > 0.0 { d / length } else { Vec2d::zero() };
+        let c = (length - self.length).clamp(-util::MAX_LINEAR_CORRECTION, util::MAX_LINEAR_CORRECTION);
+        let impulse = u * (-self.mass * c);
+        a.apply_position_impulse(-impulse, ra);
+        b.apply_position_impulse(impulse, rb);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+        c.abs() < util::LINEAR_SLOP
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use collision::shapes::Circle;
+    use common::{Rotation, Transform, Vec2d};
+    use dynamics::{BodyType, Fixture, RigidBody, World};
+    use dynamics::joints::DistanceJoint;
+
+    fn ball(x: f64, y: f64, density: f64) -> RigidBody {
+        let mut body = RigidBody::new(Transform::new(Vec2d::new(x, y), Rotation::identity()));
+        body.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.1).unwrap(), density));
+        body
+    }
+
+    #[test]
+    fn test_distance_joint_rigid() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let mut ceiling = ball(0.0, 0.0, 0.0);
+        ceiling.set_body_type(BodyType::Static);
+        let ceiling = world.add_body(ceiling);
+        let bob = world.add_body(ball(1.0, 1.0, 1.0));
+        world.add_joint(DistanceJoint::new(ceiling, bob, Vec2d::zero(), Vec2d::zero(), 2.0_f64.sqrt()))
+             .unwrap();
+
+        for _ in 0..120 {
+            world.step(world.time_step());
+            let distance = world.body(bob).unwrap().position().len();
+            assert!((distance - 2.0_f64.sqrt()).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_distance_joint_spring() {
+        let mut world = World::new(Vec2d::zero());
+        let mut wall = ball(0.0, 0.0, 0.0);
+        wall.set_body_type(BodyType::Static);
+        let wall = world.add_body(wall);
+        let bob = world.add_body(ball(2.0, 0.0, 1.0));
+        let mut joint = DistanceJoint::new(wall, bob, Vec2d::zero(), Vec2d::zero(), 1.0);
+        joint.set_frequency(1.0);
+        joint.set_damping_ratio(0.0);
+        world.add_joint(joint).unwrap();
+
+        // released one unit stretched, an undamped spring swings
+        // through its rest length and back within a period
+        let mut shortest = f64::MAX;
+        for _ in 0..60 {
+            world.step(world.time_step());
+            shortest = shortest.min(world.body(bob).unwrap().position().x);
+        }
+        assert!(shortest < 0.2);
+        assert!(world.body(bob).unwrap().position().x > 1.5);
+    }
+}
@@ -0,0 +1,364 @@
+use common::{Rotation, Vec2d};
+use dynamics::{BodyHandle, JointHandle};
+use dynamics::joints::Joint;
+use dynamics::solver_body::{SolverBody, TimeStep};
+
+/// One of the two joints coupled by a gear. The gear moves the second
+/// body of the joint relative to its first body, the ground
+#[derive(Debug, Clone)]
+struct Coupling {
+    joint: JointHandle,
+    body: BodyHandle,
+    ground: BodyHandle,
+    local_anchor: Vec2d,
+    local_anchor_ground: Vec2d,
+    reference_angle: f64,
+    // the axis of a prismatic joint, revolute joints have none
+    local_axis: Option<Vec2d>,
+
+    // solver state
+    index: usize,
+    ground_index: usize,
+    jv: Vec2d,
+    jw: f64,
+    jw_ground: f64,
+}
+
+impl Coupling {
+    fn new(joint: JointHandle, j: &Joint) -> Result<Coupling, ()> {
+        let (local_anchor_ground, local_anchor, reference_angle, local_axis) = match *j {
+            Joint::Revolute(ref j) => (*j.local_anchor_a(), *j.local_anchor_b(), j.reference_angle(), None),
+            Joint::Prismatic(ref j) => {
+                (*j.local_anchor_a(), *j.local_anchor_b(), j.reference_angle(), Some(*j.local_axis_a()))
+            }
+            _ => return Err(()),
+        };
+        Ok(Coupling {
+            joint,
+            body: j.body_b(),
+            ground: j.body_a(),
+            local_anchor,
+            local_anchor_ground,
+            reference_angle,
+            local_axis,
+            index: 0,
+            ground_index: 0,
+            jv: Vec2d::zero(),
+            jw: 0.0,
+            jw_ground: 0.0,
+        })
+    }
+
+    /// Returns the angle of a revolute joint or
+    /// the translation of a prismatic joint
+    fn coordinate(&self, body: &SolverBody, ground: &SolverBody) -> f64 {
+        match self.local_axis {
+            None => body.angle - ground.angle - self.reference_angle,
+            Some(axis) => {
+                let pg = self.local_anchor_ground - ground.local_center;
+                let p = (body.offset(self.local_anchor) + (body.center - ground.center))
+                            .inv_rotate(&Rotation::new(ground.angle));
+                (p - pg) * axis
+            }
+        }
+    }
+
+    /// Computes the jacobian of the coordinate, returning the inverse mass along it
+    fn jacobian(&mut self, body: &SolverBody, ground: &SolverBody) -> f64 {
+        match self.local_axis {
+            None => {
+                self.jv = Vec2d::zero();
+                self.jw = 1.0;
+                self.jw_ground = 1.0;
+                body.inv_inertia + ground.inv_inertia
+            }
+            Some(axis) => {
+                let u = axis.rotate(&Rotation::new(ground.angle));
+                self.jv = u;
+                self.jw = body.offset(self.local_anchor).cross(u);
+                self.jw_ground = ground.offset(self.local_anchor_ground).cross(u);
+                body.inv_mass + ground.inv_mass + body.inv_inertia * self.jw * self.jw +
+                ground.inv_inertia * self.jw_ground * self.jw_ground
+            }
+        }
+    }
+
+    /// Returns the rate of change of the coordinate
+    fn velocity(&self, bodies: &[SolverBody]) -> f64 {
+        let (body, ground) = (&bodies[self.index], &bodies[self.ground_index]);
+        self.jv * (body.v - ground.v) + self.jw * body.w - self.jw_ground * ground.w
+    }
+
+    /// Applies the impulse along the coordinate to the velocities
+    fn apply_impulse(&self, bodies: &mut [SolverBody], impulse: f64) {
+        let body = &mut bodies[self.index];
+        body.v += self.jv * (body.inv_mass * impulse);
+        body.w += body.inv_inertia * self.jw * impulse;
+        let ground = &mut bodies[self.ground_index];
+        ground.v -= self.jv * (ground.inv_mass * impulse);
+        ground.w -= ground.inv_inertia * self.jw_ground * impulse;
+    }
+
+    /// Applies the impulse along the coordinate to the positions
+    fn apply_position_impulse(&self, bodies: &mut [SolverBody], impulse: f64) {
+        let body = &mut bodies[self.index];
+        body.center += self.jv * (body.inv_mass * impulse);
+        body.angle += body.inv_inertia * self.jw * impulse;
+        let ground = &mut bodies[self.ground_index];
+        ground.center -= self.jv * (ground.inv_mass * impulse);
+        ground.angle -= ground.inv_inertia * self.jw_ground * impulse;
+    }
+}
+
+/// GearJoint couples two revolute or prismatic joints, so that moving
+/// one of them moves the other one. Coupling two revolute joints makes
+/// a pair of gears, a revolute and a prismatic joint a rack and pinion.
+///
+/// # Remarks
+///
+/// The joint keeps `coordinate_a + ratio * coordinate_b` constant, where
+/// the coordinates are the angles of revolute joints and the translations
+/// of prismatic joints. The constant is taken from the positions of the
+/// bodies when the joint is first solved. The gear moves the second
+/// bodies of the coupled joints, which should be attached to static
+/// bodies with their first bodies. Removing either coupled joint from
+/// the world removes the gear as well
+#[derive(Debug, Clone)]
+pub struct GearJoint {
+    coupling_a: Coupling,
+    coupling_b: Coupling,
+    ratio: f64,
+    constant: Option<f64>,
+    collide_connected: bool,
+
+    // solver state
+    impulse: f64,
+    mass: f64,
+}
+
+impl GearJoint {
+    /// Creates a new gear joint coupling the revolute or prismatic joints
+    /// given with their handles at the `ratio`, or returns an error if
+    /// either of them is another kind of joint
+    pub fn new(joint_a: (JointHandle, &Joint),
+               joint_b: (JointHandle, &Joint),
+               ratio: f64)
+               -> Result<GearJoint, ()> {
+        Ok(GearJoint {
+            coupling_a: Coupling::new(joint_a.0, joint_a.1)?,
+            coupling_b: Coupling::new(joint_b.0, joint_b.1)?,
+            ratio,
+            constant: None,
+            collide_connected: false,
+            impulse: 0.0,
+            mass: 0.0,
+        })
+    }
+
+    /// Returns the second body of the first coupled joint
+    pub fn body_a(&self) -> BodyHandle {
+        self.coupling_a.body
+    }
+
+    /// Returns the second body of the second coupled joint
+    pub fn body_b(&self) -> BodyHandle {
+        self.coupling_b.body
+    }
+
+    /// Returns the handle of the first coupled joint
+    pub fn joint_a(&self) -> JointHandle {
+        self.coupling_a.joint
+    }
+
+    /// Returns the handle of the second coupled joint
+    pub fn joint_b(&self) -> JointHandle {
+        self.coupling_b.joint
+    }
+
+    /// Returns the gear ratio
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Sets the gear ratio
+    pub fn set_ratio(&mut self, ratio: f64) {
+        self.ratio = ratio;
+    }
+
+    /// Returns if the fixtures of the geared bodies collide with each other
+    pub fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    /// Sets if the fixtures of the geared bodies collide with each other
+    pub fn set_collide_connected(&mut self, collide: bool) {
+        self.collide_connected = collide;
+    }
+
+    /// Returns the current value of `coordinate_a + ratio * coordinate_b`
+    fn position(&self, bodies: &[SolverBody]) -> f64 {
+        let (a, b) = (&self.coupling_a, &self.coupling_b);
+        a.coordinate(&bodies[a.index], &bodies[a.ground_index]) +
+        self.ratio * b.coordinate(&bodies[b.index], &bodies[b.ground_index])
+    }
+
+    /// Computes the jacobians of both couplings, returning the effective mass
+    fn jacobian(&mut self, bodies: &[SolverBody]) -> f64 {
+        let (a, b) = (&mut self.coupling_a, &mut self.coupling_b);
+        let mut inv_mass = a.jacobian(&bodies[a.index], &bodies[a.ground_index]);
+        inv_mass += self.ratio * self.ratio * b.jacobian(&bodies[b.index], &bodies[b.ground_index]);
+        b.jv *= self.ratio;
+        b.jw *= self.ratio;
+        b.jw_ground *= self.ratio;
+        if inv_mass > 0.0 { 1.0 / inv_mass } else { 0.0 }
+    }
+
+    /// Returns the force the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2d {
+        self.coupling_b.jv * (self.impulse * inv_dt)
+    }
+
+    /// Returns the torque the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_torque(&self, inv_dt: f64) -> f64 {
+        inv_dt * self.impulse * self.coupling_b.jw
+    }
+
+    pub(crate) fn init_velocity(&mut self,
+                                bodies: &mut [SolverBody],
+                                index: &dyn Fn(BodyHandle) -> usize,
+                                step: &TimeStep) {
+        self.coupling_a.index = index(self.coupling_a.body);
+        self.coupling_a.ground_index = index(self.coupling_a.ground);
+        self.coupling_b.index = index(self.coupling_b.body);
+        self.coupling_b.ground_index = index(self.coupling_b.ground);
+        if self.constant.is_none() {
+            self.constant = Some(self.position(bodies));
+        }
+        self.mass = self.jacobian(bodies);
+
+        if step.warm_starting {
+            self.coupling_a.apply_impulse(bodies, self.impulse);
+            self.coupling_b.apply_impulse(bodies, self.impulse);
+        } else {
+            self.impulse = 0.0;
+        }
+    }
+
+    pub(crate) fn solve_velocity(&mut self, bodies: &mut [SolverBody], _step: &TimeStep) {
+        let cdot = self.coupling_a.velocity(bodies) + self.coupling_b.velocity(bodies);
+        let impulse = -self.mass * cdot;
+        self.impulse += impulse;
+        self.coupling_a.apply_impulse(bodies, impulse);
+        self.coupling_b.apply_impulse(bodies, impulse);
+    }
+
+    pub(crate) fn solve_position(&mut self, bodies: &mut [SolverBody]) -> bool {
+        let mass = self.jacobian(bodies);
+        let c = self.position(bodies) - self.constant.unwrap_or(0.0);
+        let impulse = -mass * c;
+        self.coupling_a.apply_position_impulse(bodies, impulse);
+        self.coupling_b.apply_position_impulse(bodies, impulse);
+
+        // the coupled joints report their own errors
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use collision::shapes::{Circle, Convex};
+    use common::{Rotation, Transform, Vec2d};
+    use dynamics::{BodyType, Fixture, JointEvent, RigidBody, World};
+    use dynamics::joints::{GearJoint, Joint, PrismaticJoint, RevoluteJoint};
+
+    fn wheel(x: f64, radius: f64) -> RigidBody {
+        let mut body = RigidBody::new(Transform::new(Vec2d::new(x, 0.0), Rotation::identity()));
+        body.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), radius).unwrap(), 1.0));
+        body
+    }
+
+    fn ground() -> RigidBody {
+        let mut body = RigidBody::new(Transform::identity());
+        body.add_fixture(Fixture::new(Circle::new(Vec2d::new(0.0, -10.0), 0.1).unwrap(), 0.0));
+        body.set_body_type(BodyType::Static);
+        body
+    }
+
+    #[test]
+    fn test_gear_joint_revolute() {
+        let mut world = World::new(Vec2d::zero());
+        let ground = world.add_body(ground());
+        let small = world.add_body(wheel(0.0, 0.5));
+        let large = world.add_body(wheel(1.5, 1.0));
+        let mut motor = RevoluteJoint::new(ground, small, Vec2d::zero(), Vec2d::zero());
+        motor.enable_motor(true);
+        motor.set_motor_speed(2.0);
+        motor.set_max_motor_torque(1000.0);
+        let a = world.add_joint(motor).unwrap();
+        let b = world.add_joint(RevoluteJoint::new(ground, large, Vec2d::new(1.5, 0.0), Vec2d::zero()))
+                     .unwrap();
+
+        // the large wheel turns the other way at half the speed
+        let gear = GearJoint::new((a, world.joint(a).unwrap()), (b, world.joint(b).unwrap()), 2.0).unwrap();
+        let gear = world.add_joint(gear).unwrap();
+        for _ in 0..60 {
+            world.step(world.time_step());
+        }
+        let (small, large) = (world.body(small).unwrap(), world.body(large).unwrap());
+        assert!((small.angular_velocity() - 2.0).abs() < 0.01);
+        assert!((large.angular_velocity() + 1.0).abs() < 0.01);
+        assert!((small.rotation().angle() + 2.0 * large.rotation().angle()).abs() < 0.01);
+
+        // only revolute and prismatic joints can be coupled
+        assert!(GearJoint::new((gear, world.joint(gear).unwrap()), (b, world.joint(b).unwrap()), 1.0).is_err());
+
+        // removing a coupled joint removes the gear
+        world.remove_joint(a);
+        assert!(world.joint(gear).is_none());
+        assert!(world.joint(b).is_some());
+        let events: Vec<JointEvent> = world.drain_joint_events().collect();
+        assert_eq!(1, events.len());
+        match events[0] {
+            JointEvent::Removed { handle, joint: Joint::Gear(_) } => assert_eq!(gear, handle),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_gear_joint_rack_and_pinion() {
+        let mut world = World::new(Vec2d::zero());
+        let ground = world.add_body(ground());
+        let pinion = world.add_body(wheel(0.0, 0.5));
+        let shape = Convex::new(&[Vec2d::new(-2.0, -0.1),
+                                  Vec2d::new(2.0, -0.1),
+                                  Vec2d::new(2.0, 0.1),
+                                  Vec2d::new(-2.0, 0.1)])
+                        .unwrap();
+        let mut rack = RigidBody::new(Transform::new(Vec2d::new(0.0, -0.6), Rotation::identity()));
+        rack.add_fixture(Fixture::new(shape, 1.0));
+        let rack = world.add_body(rack);
+
+        let a = world.add_joint(RevoluteJoint::new(ground, pinion, Vec2d::zero(), Vec2d::zero())).unwrap();
+        let b = world.add_joint(PrismaticJoint::new(ground,
+                                                    rack,
+                                                    Vec2d::new(0.0, -0.6),
+                                                    Vec2d::zero(),
+                                                    Vec2d::new(1.0, 0.0)))
+                     .unwrap();
+        let gear = GearJoint::new((a, world.joint(a).unwrap()), (b, world.joint(b).unwrap()), 2.0).unwrap();
+        world.add_joint(gear).unwrap();
+
+        // spinning the pinion counter-clockwise drives the rack to the left
+        world.body_mut(pinion).unwrap().set_angular_velocity(1.0);
+        for _ in 0..60 {
+            world.step(world.time_step());
+            let angle = world.body(pinion).unwrap().rotation().angle();
+            let x = world.body(rack).unwrap().position().x;
+            assert!((angle + 2.0 * x).abs() < 0.01);
+        }
+        assert!(world.body(rack).unwrap().position().x < -0.1);
+        assert!((world.body(rack).unwrap().position().y + 0.6).abs() < 1e-6);
+    }
+}
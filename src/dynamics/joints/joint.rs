@@ -0,0 +1,277 @@
+use common::Vec2d;
+use dynamics::BodyHandle;
+use dynamics::joints::{DistanceJoint, GearJoint, MotorJoint, MouseJoint, PrismaticJoint, PulleyJoint, RevoluteJoint,
+                       RopeJoint, WeldJoint, WheelJoint};
+use dynamics::solver_body::{SolverBody, TimeStep};
+
+/// Joint wraps every joint supported by the engine so that
+/// differently constrained bodies may be stored and solved uniformly
+#[derive(Debug, Clone)]
+pub enum Joint {
+    Distance(DistanceJoint),
+    Gear(GearJoint),
+    Motor(MotorJoint),
+    Mouse(MouseJoint),
+    Prismatic(PrismaticJoint),
+    Pulley(PulleyJoint),
+    Revolute(RevoluteJoint),
+    Rope(RopeJoint),
+    Weld(WeldJoint),
+    Wheel(WheelJoint),
+}
+
+impl Joint {
+    /// Returns the handle of the first body
+    pub fn body_a(&self) -> BodyHandle {
+        match *self {
+            Joint::Distance(ref j) => j.body_a(),
+            Joint::Gear(ref j) => j.body_a(),
+            Joint::Motor(ref j) => j.body_a(),
+            Joint::Mouse(ref j) => j.body_a(),
+            Joint::Prismatic(ref j) => j.body_a(),
+            Joint::Pulley(ref j) => j.body_a(),
+            Joint::Revolute(ref j) => j.body_a(),
+            Joint::Rope(ref j) => j.body_a(),
+            Joint::Weld(ref j) => j.body_a(),
+            Joint::Wheel(ref j) => j.body_a(),
+        }
+    }
+
+    /// Returns the handle of the second body
+    pub fn body_b(&self) -> BodyHandle {
+        match *self {
+            Joint::Distance(ref j) => j.body_b(),
+            Joint::Gear(ref j) => j.body_b(),
+            Joint::Motor(ref j) => j.body_b(),
+            Joint::Mouse(ref j) => j.body_b(),
+            Joint::Prismatic(ref j) => j.body_b(),
+            Joint::Pulley(ref j) => j.body_b(),
+            Joint::Revolute(ref j) => j.body_b(),
+            Joint::Rope(ref j) => j.body_b(),
+            Joint::Weld(ref j) => j.body_b(),
+            Joint::Wheel(ref j) => j.body_b(),
+        }
+    }
+
+    /// Returns if the fixtures of the jointed bodies collide with each other
+    pub fn collide_connected(&self) -> bool {
+        match *self {
+            Joint::Distance(ref j) => j.collide_connected(),
+            Joint::Gear(ref j) => j.collide_connected(),
+            Joint::Motor(ref j) => j.collide_connected(),
+            Joint::Mouse(ref j) => j.collide_connected(),
+            Joint::Prismatic(ref j) => j.collide_connected(),
+            Joint::Pulley(ref j) => j.collide_connected(),
+            Joint::Revolute(ref j) => j.collide_connected(),
+            Joint::Rope(ref j) => j.collide_connected(),
+            Joint::Weld(ref j) => j.collide_connected(),
+            Joint::Wheel(ref j) => j.collide_connected(),
+        }
+    }
+
+    /// Returns the force the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2d {
+        match *self {
+            Joint::Distance(ref j) => j.reaction_force(inv_dt),
+            Joint::Gear(ref j) => j.reaction_force(inv_dt),
+            Joint::Motor(ref j) => j.reaction_force(inv_dt),
+            Joint::Mouse(ref j) => j.reaction_force(inv_dt),
+            Joint::Prismatic(ref j) => j.reaction_force(inv_dt),
+            Joint::Pulley(ref j) => j.reaction_force(inv_dt),
+            Joint::Revolute(ref j) => j.reaction_force(inv_dt),
+            Joint::Rope(ref j) => j.reaction_force(inv_dt),
+            Joint::Weld(ref j) => j.reaction_force(inv_dt),
+            Joint::Wheel(ref j) => j.reaction_force(inv_dt),
+        }
+    }
+
+    /// Returns the torque the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_torque(&self, inv_dt: f64) -> f64 {
+        match *self {
+            Joint::Distance(ref j) => j.reaction_torque(inv_dt),
+            Joint::Gear(ref j) => j.reaction_torque(inv_dt),
+            Joint::Motor(ref j) => j.reaction_torque(inv_dt),
+            Joint::Mouse(ref j) => j.reaction_torque(inv_dt),
+            Joint::Prismatic(ref j) => j.reaction_torque(inv_dt),
+            Joint::Pulley(ref j) => j.reaction_torque(inv_dt),
+            Joint::Revolute(ref j) => j.reaction_torque(inv_dt),
+            Joint::Rope(ref j) => j.reaction_torque(inv_dt),
+            Joint::Weld(ref j) => j.reaction_torque(inv_dt),
+            Joint::Wheel(ref j) => j.reaction_torque(inv_dt),
+        }
+    }
+
+    /// Prepares the joint for solving the velocities of `bodies`.
+    /// `index` maps the handle of a body to the index of its state
+    pub(crate) fn init_velocity(&mut self,
+                                bodies: &mut [SolverBody],
+                                index: &dyn Fn(BodyHandle) -> usize,
+                                step: &TimeStep) {
+        match *self {
+            Joint::Distance(ref mut j) => j.init_velocity(bodies, index, step),
+            Joint::Gear(ref mut j) => j.init_velocity(bodies, index, step),
+            Joint::Motor(ref mut j) => j.init_velocity(bodies, index, step),
+            Joint::Mouse(ref mut j) => j.init_velocity(bodies, index, step),
+            Joint::Prismatic(ref mut j) => j.init_velocity(bodies, index, step),
+            Joint::Pulley(ref mut j) => j.init_velocity(bodies, index, step),
+            Joint::Revolute(ref mut j) => j.init_velocity(bodies, index, step),
+            Joint::Rope(ref mut j) => j.init_velocity(bodies, index, step),
+            Joint::Weld(ref mut j) => j.init_velocity(bodies, index, step),
+            Joint::Wheel(ref mut j) => j.init_velocity(bodies, index, step),
+        }
+    }
+
+    /// Runs a single velocity iteration of the joint
+    pub(crate) fn solve_velocity(&mut self, bodies: &mut [SolverBody], step: &TimeStep) {
+        match *self {
+            Joint::Distance(ref mut j) => j.solve_velocity(bodies, step),
+            Joint::Gear(ref mut j) => j.solve_velocity(bodies, step),
+            Joint::Motor(ref mut j) => j.solve_velocity(bodies, step),
+            Joint::Mouse(ref mut j) => j.solve_velocity(bodies, step),
+            Joint::Prismatic(ref mut j) => j.solve_velocity(bodies, step),
+            Joint::Pulley(ref mut j) => j.solve_velocity(bodies, step),
+            Joint::Revolute(ref mut j) => j.solve_velocity(bodies, step),
+            Joint::Rope(ref mut j) => j.solve_velocity(bodies, step),
+            Joint::Weld(ref mut j) => j.solve_velocity(bodies, step),
+            Joint::Wheel(ref mut j) => j.solve_velocity(bodies, step),
+        }
+    }
+
+    /// Runs a single position iteration of the joint,
+    /// returning true once the joint is within the slop
+    pub(crate) fn solve_position(&mut self, bodies: &mut [SolverBody]) -> bool {
+        match *self {
+            Joint::Distance(ref mut j) => j.solve_position(bodies),
+            Joint::Gear(ref mut j) => j.solve_position(bodies),
+            Joint::Motor(ref mut j) => j.solve_position(bodies),
+            Joint::Mouse(ref mut j) => j.solve_position(bodies),
+            Joint::Prismatic(ref mut j) => j.solve_position(bodies),
+            Joint::Pulley(ref mut j) => j.solve_position(bodies),
+            Joint::Revolute(ref mut j) => j.solve_position(bodies),
+            Joint::Rope(ref mut j) => j.solve_position(bodies),
+            Joint::Weld(ref mut j) => j.solve_position(bodies),
+            Joint::Wheel(ref mut j) => j.solve_position(bodies),
+        }
+    }
+}
+
+impl From<DistanceJoint> for Joint {
+    fn from(joint: DistanceJoint) -> Joint {
+        Joint::Distance(joint)
+    }
+}
+
+impl From<GearJoint> for Joint {
+    fn from(joint: GearJoint) -> Joint {
+        Joint::Gear(joint)
+    }
+}
+
+impl From<MotorJoint> for Joint {
+    fn from(joint: MotorJoint) -> Joint {
+        Joint::Motor(joint)
+    }
+}
+
+impl From<MouseJoint> for Joint {
+    fn from(joint: MouseJoint) -> Joint {
+        Joint::Mouse(joint)
+    }
+}
+
+impl From<PrismaticJoint> for Joint {
+    fn from(joint: PrismaticJoint) -> Joint {
+        Joint::Prismatic(joint)
+    }
+}
+
+impl From<PulleyJoint> for Joint {
+    fn from(joint: PulleyJoint) -> Joint {
+        Joint::Pulley(joint)
+    }
+}
+
+impl From<RevoluteJoint> for Joint {
+    fn from(joint: RevoluteJoint) -> Joint {
+        Joint::Revolute(joint)
+    }
+}
+
+impl From<RopeJoint> for Joint {
+    fn from(joint: RopeJoint) -> Joint {
+        Joint::Rope(joint)
+    }
+}
+
+impl From<WeldJoint> for Joint {
+    fn from(joint: WeldJoint) -> Joint {
+        Joint::Weld(joint)
+    }
+}
+
+impl From<WheelJoint> for Joint {
+    fn from(joint: WheelJoint) -> Joint {
+        Joint::Wheel(joint)
+    }
+}
+
+/// Solves `k * x = b` for the symmetric 2x2 matrix `k` given as
+/// `[k11, k12, k22]`, returning zero if `k` is singular
+pub(crate) fn solve22(k: [f64; 3], b: Vec2d) -> Vec2d {
+    let det = k[0] * k[2] - k[1] * k[1];
+    if det == 0.0 {
+        return Vec2d::zero();
+    }
+    let inv = 1.0 / det;
+    Vec2d::new(inv * (k[2] * b.x - k[1] * b.y), inv * (k[0] * b.y - k[1] * b.x))
+}
+
+/// Returns the effective mass matrix `[k11, k12, k22]` of a point
+/// constraint between the anchors at the offsets `ra` and `rb` from
+/// the centers of mass of `a` and `b`
+pub(crate) fn point_mass(a: &SolverBody, b: &SolverBody, ra: Vec2d, rb: Vec2d) -> [f64; 3] {
+    let (ma, mb, ia, ib) = (a.inv_mass, b.inv_mass, a.inv_inertia, b.inv_inertia);
+    [ma + mb + ra.y * ra.y * ia + rb.y * rb.y * ib,
+     -ra.y * ra.x * ia - rb.y * rb.x * ib,
+     ma + mb + ra.x * ra.x * ia + rb.x * rb.x * ib]
+}
+
+/// Solves `k * x = b` for the symmetric 3x3 matrix `k` given as
+/// `[k11, k12, k13, k22, k23, k33]`, returning zero if `k` is singular
+pub(crate) fn solve33(k: [f64; 6], b: [f64; 3]) -> [f64; 3] {
+    let [k11, k12, k13, k22, k23, k33] = k;
+    let c11 = k22 * k33 - k23 * k23;
+    let c12 = k13 * k23 - k12 * k33;
+    let c13 = k12 * k23 - k13 * k22;
+    let det = k11 * c11 + k12 * c12 + k13 * c13;
+    if det == 0.0 {
+        return [0.0; 3];
+    }
+    let inv = 1.0 / det;
+    let c22 = k11 * k33 - k13 * k13;
+    let c23 = k12 * k13 - k11 * k23;
+    let c33 = k11 * k22 - k12 * k12;
+    [inv * (c11 * b[0] + c12 * b[1] + c13 * b[2]),
+     inv * (c12 * b[0] + c22 * b[1] + c23 * b[2]),
+     inv * (c13 * b[0] + c23 * b[1] + c33 * b[2])]
+}
+
+/// Applies the linear impulse `p` and the angular impulses `la`
+/// and `lb` to the velocities, pushing `a` back and `b` forward
+pub(crate) fn apply_impulses(a: &mut SolverBody, b: &mut SolverBody, p: Vec2d, la: f64, lb: f64) {
+    a.v -= p * a.inv_mass;
+    a.w -= a.inv_inertia * la;
+    b.v += p * b.inv_mass;
+    b.w += b.inv_inertia * lb;
+}
+
+/// Applies the linear impulse `p` and the angular impulses `la`
+/// and `lb` to the positions, pushing `a` back and `b` forward
+pub(crate) fn apply_position_impulses(a: &mut SolverBody, b: &mut SolverBody, p: Vec2d, la: f64, lb: f64) {
+    a.center -= p * a.inv_mass;
+    a.angle -= a.inv_inertia * la;
+    b.center += p * b.inv_mass;
+    b.angle += b.inv_inertia * lb;
+}
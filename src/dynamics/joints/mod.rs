@@ -0,0 +1,23 @@
+pub use self::distance_joint::DistanceJoint;
+pub use self::gear_joint::GearJoint;
+pub use self::joint::Joint;
+pub use self::motor_joint::MotorJoint;
+pub use self::mouse_joint::MouseJoint;
+pub use self::prismatic_joint::PrismaticJoint;
+pub use self::pulley_joint::PulleyJoint;
+pub use self::revolute_joint::RevoluteJoint;
+pub use self::rope_joint::RopeJoint;
+pub use self::weld_joint::WeldJoint;
+pub use self::wheel_joint::WheelJoint;
+
+mod distance_joint;
+mod gear_joint;
+mod joint;
+mod motor_joint;
+mod mouse_joint;
+mod prismatic_joint;
+mod pulley_joint;
+mod revolute_joint;
+mod rope_joint;
+mod weld_joint;
+mod wheel_joint;
\ No newline at end of file
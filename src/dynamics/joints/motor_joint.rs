@@ -0,0 +1,271 @@
+use common::{Rotation, Vec2d};
+use dynamics::BodyHandle;
+use dynamics::joints::joint::{apply_impulses, point_mass, solve22};
+use dynamics::solver_body::{SolverBody, TimeStep};
+
+/// MotorJoint drives the second body toward a target position and
+/// rotation relative to the first body, using at most a maximum force
+/// and torque. Lets a body follow a path while still being pushed
+/// around by collisions.
+///
+/// # Remarks
+///
+/// The linear offset is the position of the origin of the second body
+/// in the local space of the first body, the angular offset the rotation
+/// of the second body relative to the first body
+#[derive(Debug, Clone)]
+pub struct MotorJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    linear_offset: Vec2d,
+    angular_offset: f64,
+    max_force: f64,
+    max_torque: f64,
+    correction_factor: f64,
+    collide_connected: bool,
+
+    // solver state
+    linear_impulse: Vec2d,
+    angular_impulse: f64,
+    a: usize,
+    b: usize,
+    ra: Vec2d,
+    rb: Vec2d,
+    linear_error: Vec2d,
+    angular_error: f64,
+    linear_mass: [f64; 3],
+    angular_mass: f64,
+}
+
+impl MotorJoint {
+    /// Creates a new motor joint driving the origin of `body_b` to
+    /// the origin of `body_a` at the same rotation, with a maximum
+    /// force and torque of 1 and a correction factor of 0.3
+    pub fn new(body_a: BodyHandle, body_b: BodyHandle) -> MotorJoint {
+        MotorJoint {
+            body_a,
+            body_b,
+            linear_offset: Vec2d::zero(),
+            angular_offset: 0.0,
+            max_force: 1.0,
+            max_torque: 1.0,
+            correction_factor: 0.3,
+            collide_connected: false,
+            linear_impulse: Vec2d::zero(),
+            angular_impulse: 0.0,
+            a: 0,
+            b: 0,
+            ra: Vec2d::zero(),
+            rb: Vec2d::zero(),
+            linear_error: Vec2d::zero(),
+            angular_error: 0.0,
+            linear_mass: [0.0; 3],
+            angular_mass: 0.0,
+        }
+    }
+
+    /// Returns the handle of the first body
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    /// Returns the handle of the second body
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    /// Returns the target position of the second body
+    /// in the local space of the first body
+    pub fn linear_offset(&self) -> &Vec2d {
+        &self.linear_offset
+    }
+
+    /// Sets the target position of the second body
+    /// in the local space of the first body
+    pub fn set_linear_offset(&mut self, offset: Vec2d) {
+        self.linear_offset = offset;
+    }
+
+    /// Returns the target rotation of the second body relative to the first body
+    pub fn angular_offset(&self) -> f64 {
+        self.angular_offset
+    }
+
+    /// Sets the target rotation of the second body relative to the first body
+    pub fn set_angular_offset(&mut self, offset: f64) {
+        self.angular_offset = offset;
+    }
+
+    /// Returns the maximum force of the motor
+    pub fn max_force(&self) -> f64 {
+        self.max_force
+    }
+
+    /// Sets the maximum force the motor may apply to reach its target
+    pub fn set_max_force(&mut self, force: f64) {
+        debug_assert!(force >= 0.0);
+        self.max_force = force;
+    }
+
+    /// Returns the maximum torque of the motor
+    pub fn max_torque(&self) -> f64 {
+        self.max_torque
+    }
+
+    /// Sets the maximum torque the motor may apply to reach its target
+    pub fn set_max_torque(&mut self, torque: f64) {
+        debug_assert!(torque >= 0.0);
+        self.max_torque = torque;
+    }
+
+    /// Returns the fraction of the error corrected per step
+    pub fn correction_factor(&self) -> f64 {
+        self.correction_factor
+    }
+
+    /// Sets the fraction of the error corrected per step, from 0 to 1
+    pub fn set_correction_factor(&mut self, factor: f64) {
+        debug_assert!((0.0..=1.0).contains(&factor));
+        self.correction_factor = factor;
+    }
+
+    /// Returns if the fixtures of the jointed bodies collide with each other
+    pub fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    /// Sets if the fixtures of the jointed bodies collide with each other
+    pub fn set_collide_connected(&mut self, collide: bool) {
+        self.collide_connected = collide;
+    }
+
+    /// Returns the force the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2d {
+        self.linear_impulse * inv_dt
+    }
+
+    /// Returns the torque the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_torque(&self, inv_dt: f64) -> f64 {
+        inv_dt * self.angular_impulse
+    }
+
+    pub(crate) fn init_velocity(&mut self,
+                                bodies: &mut [SolverBody],
+                                index: &dyn Fn(BodyHandle) -> usize,
+                                step: &TimeStep) {
+        self.a = index(self.body_a);
+        self.b = index(self.body_b);
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        // the motor acts on the body origins
+        self.ra = a.offset(Vec2d::zero());
+        self.rb = b.offset(Vec2d::zero());
+        self.linear_mass = point_mass(&a, &b, self.ra, self.rb);
+        let inv_inertia = a.inv_inertia + b.inv_inertia;
+        self.angular_mass = if inv_inertia > 0.0 { 1.0 / inv_inertia } else { 0.0 };
+
+        self.linear_error = (b.center + self.rb) - (a.center + self.ra) -
+                            self.linear_offset.rotate(&Rotation::new(a.angle));
+        self.angular_error = b.angle - a.angle - self.angular_offset;
+
+        if step.warm_starting {
+            let p = self.linear_impulse;
+            apply_impulses(&mut a,
+                           &mut b,
+                           p,
+                           self.ra.cross(p) + self.angular_impulse,
+                           self.rb.cross(p) + self.angular_impulse);
+        } else {
+            self.linear_impulse = Vec2d::zero();
+            self.angular_impulse = 0.0;
+        }
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_velocity(&mut self, bodies: &mut [SolverBody], step: &TimeStep) {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+        let correction = step.inv_dt * self.correction_factor;
+
+        let cdot = b.w - a.w + correction * self.angular_error;
+        let max = step.dt * self.max_torque;
+        let old = self.angular_impulse;
+        self.angular_impulse = (old - self.angular_mass * cdot).clamp(-max, max);
+        let impulse = self.angular_impulse - old;
+        apply_impulses(&mut a, &mut b, Vec2d::zero(), impulse, impulse);
+
+        let cdot = b.velocity_at(self.rb) - a.velocity_at(self.ra) + self.linear_error * correction;
+        let max = step.dt * self.max_force;
+        let old = self.linear_impulse;
+        self.linear_impulse += solve22(self.linear_mass, -cdot);
+        if self.linear_impulse.len() > max {
+            self.linear_impulse = self.linear_impulse.normalize() * max;
+        }
+        let p = self.linear_impulse - old;
+        apply_impulses(&mut a, &mut b, p, self.ra.cross(p), self.rb.cross(p));
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_position(&mut self, _bodies: &mut [SolverBody]) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use collision::shapes::Convex;
+    use common::{Rotation, Transform, Vec2d};
+    use dynamics::{BodyType, Fixture, RigidBody, World};
+    use dynamics::joints::MotorJoint;
+
+    #[test]
+    fn test_motor_joint() {
+        let square = |x: f64, density: f64| {
+            let shape = Convex::new(&[Vec2d::new(-0.5, -0.5),
+                                      Vec2d::new(0.5, -0.5),
+                                      Vec2d::new(0.5, 0.5),
+                                      Vec2d::new(-0.5, 0.5)])
+                            .unwrap();
+            let mut body = RigidBody::new(Transform::new(Vec2d::new(x, 0.0), Rotation::identity()));
+            body.add_fixture(Fixture::new(shape, density));
+            body
+        };
+        let mut world = World::new(Vec2d::zero());
+        let mut ground = square(-5.0, 0.0);
+        ground.set_body_type(BodyType::Static);
+        let ground = world.add_body(ground);
+        let platform = world.add_body(square(0.0, 1.0));
+        let mut joint = MotorJoint::new(ground, platform);
+        joint.set_linear_offset(Vec2d::new(5.0, 2.0));
+        joint.set_angular_offset(1.0);
+        joint.set_max_force(100.0);
+        joint.set_max_torque(100.0);
+        world.add_joint(joint).unwrap();
+
+        for _ in 0..120 {
+            world.step(world.time_step());
+        }
+        let body = world.body(platform).unwrap();
+        assert!((*body.position() - Vec2d::new(0.0, 2.0)).len() < 0.01);
+        assert!((body.rotation().angle() - 1.0).abs() < 0.01);
+
+        // a weak motor only gets there slowly
+        let mut world = World::new(Vec2d::zero());
+        let mut ground = square(-5.0, 0.0);
+        ground.set_body_type(BodyType::Static);
+        let ground = world.add_body(ground);
+        let platform = world.add_body(square(0.0, 1.0));
+        let mut joint = MotorJoint::new(ground, platform);
+        joint.set_linear_offset(Vec2d::new(5.0, 2.0));
+        joint.set_max_force(0.1);
+        world.add_joint(joint).unwrap();
+        for _ in 0..60 {
+            world.step(world.time_step());
+        }
+        assert!(world.body(platform).unwrap().linear_velocity().len() <= 0.1 + 1e-9);
+    }
+}
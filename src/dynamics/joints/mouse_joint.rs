@@ -0,0 +1,228 @@
+use std::f64::consts::PI;
+use common::Vec2d;
+use dynamics::BodyHandle;
+use dynamics::joints::joint::solve22;
+use dynamics::solver_body::{SolverBody, TimeStep};
+
+/// MouseJoint pulls a point on a body toward a target in world space
+/// with a damped spring of limited force, as when dragging the body
+/// around with the mouse.
+///
+/// # Remarks
+///
+/// Only the second body is moved. The first body is usually a static
+/// ground body and only serves to attach the joint to the world
+#[derive(Debug, Clone)]
+pub struct MouseJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    local_anchor_b: Vec2d,
+    target: Vec2d,
+    max_force: f64,
+    frequency: f64,
+    damping_ratio: f64,
+
+    // solver state
+    impulse: Vec2d,
+    b: usize,
+    rb: Vec2d,
+    mass: [f64; 3],
+    c: Vec2d,
+    gamma: f64,
+}
+
+impl MouseJoint {
+    /// Creates a new mouse joint pulling `local_anchor_b` in the local
+    /// space of `body_b` toward `target`. The spring oscillates with
+    /// 5 Hz at a damping ratio of 0.7 and the maximum force is 0
+    pub fn new(body_a: BodyHandle, body_b: BodyHandle, local_anchor_b: Vec2d, target: Vec2d) -> MouseJoint {
+        MouseJoint {
+            body_a,
+            body_b,
+            local_anchor_b,
+            target,
+            max_force: 0.0,
+            frequency: 5.0,
+            damping_ratio: 0.7,
+            impulse: Vec2d::zero(),
+            b: 0,
+            rb: Vec2d::zero(),
+            mass: [0.0; 3],
+            c: Vec2d::zero(),
+            gamma: 0.0,
+        }
+    }
+
+    /// Returns the handle of the ground body
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    /// Returns the handle of the dragged body
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    /// Returns the dragged point in the local space of the second body
+    pub fn local_anchor_b(&self) -> &Vec2d {
+        &self.local_anchor_b
+    }
+
+    /// Returns the target in world space
+    pub fn target(&self) -> &Vec2d {
+        &self.target
+    }
+
+    /// Sets the target in world space
+    pub fn set_target(&mut self, target: Vec2d) {
+        self.target = target;
+    }
+
+    /// Returns the maximum force of the spring
+    pub fn max_force(&self) -> f64 {
+        self.max_force
+    }
+
+    /// Sets the maximum force the spring may apply. A few
+    /// times the weight of the body makes for snappy dragging
+    pub fn set_max_force(&mut self, force: f64) {
+        debug_assert!(force >= 0.0);
+        self.max_force = force;
+    }
+
+    /// Returns the oscillation frequency in Hertz
+    pub fn frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// Sets the oscillation frequency in Hertz. Frequencies
+    /// above half the step rate are unstable
+    pub fn set_frequency(&mut self, frequency: f64) {
+        debug_assert!(frequency > 0.0);
+        self.frequency = frequency;
+    }
+
+    /// Returns the damping ratio of the spring
+    pub fn damping_ratio(&self) -> f64 {
+        self.damping_ratio
+    }
+
+    /// Sets the damping ratio of the spring. 0 doesn't
+    /// damp at all, 1 damps critically
+    pub fn set_damping_ratio(&mut self, ratio: f64) {
+        debug_assert!(ratio >= 0.0);
+        self.damping_ratio = ratio;
+    }
+
+    /// Returns if the fixtures of the jointed bodies collide with each
+    /// other, which is always the case as the ground body is a bystander
+    pub fn collide_connected(&self) -> bool {
+        true
+    }
+
+    /// Returns the force the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2d {
+        self.impulse * inv_dt
+    }
+
+    /// Returns the torque the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_torque(&self, _inv_dt: f64) -> f64 {
+        0.0
+    }
+
+    pub(crate) fn init_velocity(&mut self,
+                                bodies: &mut [SolverBody],
+                                index: &dyn Fn(BodyHandle) -> usize,
+                                step: &TimeStep) {
+        self.b = index(self.body_b);
+        let mut b = bodies[self.b];
+
+        // spring stiffness and damping of the body mass
+        let mass = if b.inv_mass > 0.0 { 1.0 / b.inv_mass } else { 0.0 };
+        let omega = 2.0 * PI * self.frequency;
+        let damping = 2.0 * mass * self.damping_ratio * omega;
+        let stiffness = mass * omega * omega;
+
+        let h = step.dt;
+        self.gamma = h * (damping + h * stiffness);
+        self.gamma = if self.gamma != 0.0 { 1.0 / self.gamma } else { 0.0 };
+        let beta = h * stiffness * self.gamma;
+
+        self.rb = b.offset(self.local_anchor_b);
+        let (mb, ib, rb) = (b.inv_mass, b.inv_inertia, self.rb);
+        self.mass = [mb + ib * rb.y * rb.y + self.gamma,
+                     -ib * rb.x * rb.y,
+                     mb + ib * rb.x * rb.x + self.gamma];
+        self.c = (b.center + rb - self.target) * beta;
+
+        // damp the spin a little so the body doesn't keep twirling around the target
+        b.w *= 0.98;
+
+        if step.warm_starting {
+            b.apply_impulse(self.impulse, self.rb);
+        } else {
+            self.impulse = Vec2d::zero();
+        }
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_velocity(&mut self, bodies: &mut [SolverBody], step: &TimeStep) {
+        let mut b = bodies[self.b];
+
+        let cdot = b.velocity_at(self.rb);
+        let old = self.impulse;
+        self.impulse += solve22(self.mass, -(cdot + self.c + self.impulse * self.gamma));
+        let max = step.dt * self.max_force;
+        if self.impulse.len() > max {
+            self.impulse = self.impulse.normalize() * max;
+        }
+        b.apply_impulse(self.impulse - old, self.rb);
+
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_position(&mut self, _bodies: &mut [SolverBody]) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use collision::shapes::Circle;
+    use common::{Rotation, Transform, Vec2d};
+    use dynamics::{BodyType, Fixture, RigidBody, World};
+    use dynamics::joints::{Joint, MouseJoint};
+
+    #[test]
+    fn test_mouse_joint() {
+        let ball = |x: f64, y: f64, density: f64| {
+            let mut body = RigidBody::new(Transform::new(Vec2d::new(x, y), Rotation::identity()));
+            body.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.5).unwrap(), density));
+            body
+        };
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let mut ground = ball(0.0, -10.0, 0.0);
+        ground.set_body_type(BodyType::Static);
+        let ground = world.add_body(ground);
+        let body = world.add_body(ball(0.0, 0.0, 1.0));
+        let weight = 10.0 * world.body(body).unwrap().mass();
+        let mut joint = MouseJoint::new(ground, body, Vec2d::zero(), Vec2d::zero());
+        joint.set_max_force(10.0 * weight);
+        let handle = world.add_joint(joint).unwrap();
+
+        // drag the ball up and to the right
+        if let Some(&mut Joint::Mouse(ref mut joint)) = world.joint_mut(handle) {
+            joint.set_target(Vec2d::new(3.0, 2.0));
+        }
+        for _ in 0..120 {
+            world.step(world.time_step());
+        }
+        let body = world.body(body).unwrap();
+
+        // the spring carries the weight by stretching a little
+        assert!((*body.position() - Vec2d::new(3.0, 2.0)).len() < 0.05);
+        assert!(body.linear_velocity().len() < 0.05);
+    }
+}
@@ -0,0 +1,430 @@
+use common::{Rotation, Vec2d};
+use dynamics::BodyHandle;
+use dynamics::joints::joint::{apply_impulses, apply_position_impulses, solve22, solve33};
+use dynamics::solver_body::{SolverBody, TimeStep};
+use util;
+
+/// PrismaticJoint lets the second body slide along an axis fixed in the
+/// first body while locking their relative rotation, like a piston in
+/// its cylinder. The translation may be limited to a range and driven
+/// by a motor.
+///
+/// # Remarks
+///
+/// The joint translation is the distance of the second anchor from the
+/// first anchor along the axis
+#[derive(Debug, Clone)]
+pub struct PrismaticJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    local_anchor_a: Vec2d,
+    local_anchor_b: Vec2d,
+    local_axis_a: Vec2d,
+    reference_angle: f64,
+    collide_connected: bool,
+    limits: Option<(f64, f64)>,
+    motor_enabled: bool,
+    motor_speed: f64,
+    max_motor_force: f64,
+
+    // solver state
+    impulse: Vec2d,
+    motor_impulse: f64,
+    lower_impulse: f64,
+    upper_impulse: f64,
+    a: usize,
+    b: usize,
+    axis: Vec2d,
+    perp: Vec2d,
+    s1: f64,
+    s2: f64,
+    a1: f64,
+    a2: f64,
+    mass: [f64; 3],
+    axial_mass: f64,
+    translation: f64,
+}
+
+impl PrismaticJoint {
+    /// Creates a new prismatic joint letting `local_anchor_b` in the
+    /// local space of `body_b` slide along `local_axis_a` through
+    /// `local_anchor_a` in the local space of `body_a`, without
+    /// limits or motor
+    pub fn new(body_a: BodyHandle,
+               body_b: BodyHandle,
+               local_anchor_a: Vec2d,
+               local_anchor_b: Vec2d,
+               local_axis_a: Vec2d)
+               -> PrismaticJoint {
+        PrismaticJoint {
+            body_a,
+            body_b,
+            local_anchor_a,
+            local_anchor_b,
+            local_axis_a: local_axis_a.normalize(),
+            reference_angle: 0.0,
+            collide_connected: false,
+            limits: None,
+            motor_enabled: false,
+            motor_speed: 0.0,
+            max_motor_force: 0.0,
+            impulse: Vec2d::zero(),
+            motor_impulse: 0.0,
+            lower_impulse: 0.0,
+            upper_impulse: 0.0,
+            a: 0,
+            b: 0,
+            axis: Vec2d::zero(),
+            perp: Vec2d::zero(),
+            s1: 0.0,
+            s2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            mass: [0.0; 3],
+            axial_mass: 0.0,
+            translation: 0.0,
+        }
+    }
+
+    /// Returns the handle of the first body
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    /// Returns the handle of the second body
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    /// Returns the anchor in the local space of the first body
+    pub fn local_anchor_a(&self) -> &Vec2d {
+        &self.local_anchor_a
+    }
+
+    /// Returns the anchor in the local space of the second body
+    pub fn local_anchor_b(&self) -> &Vec2d {
+        &self.local_anchor_b
+    }
+
+    /// Returns the unit axis of translation in the local space of the first body
+    pub fn local_axis_a(&self) -> &Vec2d {
+        &self.local_axis_a
+    }
+
+    /// Returns the rotation of the second body relative to the first body
+    pub fn reference_angle(&self) -> f64 {
+        self.reference_angle
+    }
+
+    /// Sets the rotation of the second body relative to the first body
+    pub fn set_reference_angle(&mut self, angle: f64) {
+        self.reference_angle = angle;
+    }
+
+    /// Returns if the fixtures of the jointed bodies collide with each other
+    pub fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    /// Sets if the fixtures of the jointed bodies collide with each other
+    pub fn set_collide_connected(&mut self, collide: bool) {
+        self.collide_connected = collide;
+    }
+
+    /// Returns the lower and upper limit of the joint translation
+    /// or `None` if the joint translation is unlimited
+    pub fn limits(&self) -> Option<(f64, f64)> {
+        self.limits
+    }
+
+    /// Limits the joint translation to the range from `lower` to `upper`
+    pub fn set_limits(&mut self, lower: f64, upper: f64) {
+        debug_assert!(lower <= upper);
+        self.limits = Some((lower, upper));
+    }
+
+    /// Removes the limits of the joint translation
+    pub fn clear_limits(&mut self) {
+        self.limits = None;
+    }
+
+    /// Returns if the motor is enabled
+    pub fn is_motor_enabled(&self) -> bool {
+        self.motor_enabled
+    }
+
+    /// Enables or disables the motor
+    pub fn enable_motor(&mut self, enabled: bool) {
+        self.motor_enabled = enabled;
+    }
+
+    /// Returns the target speed of the motor in meters per second
+    pub fn motor_speed(&self) -> f64 {
+        self.motor_speed
+    }
+
+    /// Sets the target speed of the motor in meters per second
+    pub fn set_motor_speed(&mut self, speed: f64) {
+        self.motor_speed = speed;
+    }
+
+    /// Returns the maximum force of the motor
+    pub fn max_motor_force(&self) -> f64 {
+        self.max_motor_force
+    }
+
+    /// Sets the maximum force the motor may apply to reach its speed
+    pub fn set_max_motor_force(&mut self, force: f64) {
+        debug_assert!(force >= 0.0);
+        self.max_motor_force = force;
+    }
+
+    /// Returns the force the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2d {
+        let axial = self.motor_impulse + self.lower_impulse - self.upper_impulse;
+        (self.perp * self.impulse.x + self.axis * axial) * inv_dt
+    }
+
+    /// Returns the torque the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_torque(&self, inv_dt: f64) -> f64 {
+        inv_dt * self.impulse.y
+    }
+
+    pub(crate) fn init_velocity(&mut self,
+                                bodies: &mut [SolverBody],
+                                index: &dyn Fn(BodyHandle) -> usize,
+                                step: &TimeStep) {
+        self.a = index(self.body_a);
+        self.b = index(self.body_b);
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+        let (ma, mb, ia, ib) = (a.inv_mass, b.inv_mass, a.inv_inertia, b.inv_inertia);
+
+        let ra = a.offset(self.local_anchor_a);
+        let rb = b.offset(self.local_anchor_b);
+        let d = (b.center + rb) - (a.center + ra);
+
+        self.axis = self.local_axis_a.rotate(&Rotation::new(a.angle));
+        self.a1 = (d + ra).cross(self.axis);
+        self.a2 = rb.cross(self.axis);
+        let axial = ma + mb + ia * self.a1 * self.a1 + ib * self.a2 * self.a2;
+        self.axial_mass = if axial > 0.0 { 1.0 / axial } else { 0.0 };
+
+        self.perp = self.axis.perp();
+        self.s1 = (d + ra).cross(self.perp);
+        self.s2 = rb.cross(self.perp);
+        let k22 = ia + ib;
+        self.mass = [ma + mb + ia * self.s1 * self.s1 + ib * self.s2 * self.s2,
+                     ia * self.s1 + ib * self.s2,
+                     if k22 == 0.0 { 1.0 } else { k22 }];
+
+        self.translation = self.axis * d;
+        if self.limits.is_none() {
+            self.lower_impulse = 0.0;
+            self.upper_impulse = 0.0;
+        }
+        if !self.motor_enabled {
+            self.motor_impulse = 0.0;
+        }
+
+        if step.warm_starting {
+            let axial = self.motor_impulse + self.lower_impulse - self.upper_impulse;
+            let p = self.perp * self.impulse.x + self.axis * axial;
+            let la = self.impulse.x * self.s1 + self.impulse.y + axial * self.a1;
+            let lb = self.impulse.x * self.s2 + self.impulse.y + axial * self.a2;
+            apply_impulses(&mut a, &mut b, p, la, lb);
+        } else {
+            self.impulse = Vec2d::zero();
+            self.motor_impulse = 0.0;
+            self.lower_impulse = 0.0;
+            self.upper_impulse = 0.0;
+        }
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_velocity(&mut self, bodies: &mut [SolverBody], step: &TimeStep) {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+        let (axis, a1, a2) = (self.axis, self.a1, self.a2);
+        let axial_velocity = |a: &SolverBody, b: &SolverBody| axis * (b.v - a.v) + a2 * b.w - a1 * a.w;
+
+        if self.motor_enabled {
+            let cdot = axial_velocity(&a, &b);
+            let max = step.dt * self.max_motor_force;
+            let old = self.motor_impulse;
+            self.motor_impulse = (old + self.axial_mass * (self.motor_speed - cdot)).clamp(-max, max);
+            let impulse = self.motor_impulse - old;
+            apply_impulses(&mut a, &mut b, axis * impulse, impulse * a1, impulse * a2);
+        }
+
+        if let Some((lower, upper)) = self.limits {
+            // the bias lets the bodies approach the limit over one step
+            let c = self.translation - lower;
+            let cdot = axial_velocity(&a, &b);
+            let old = self.lower_impulse;
+            self.lower_impulse = (old - self.axial_mass * (cdot + c.max(0.0) * step.inv_dt)).max(0.0);
+            let impulse = self.lower_impulse - old;
+            apply_impulses(&mut a, &mut b, axis * impulse, impulse * a1, impulse * a2);
+
+            let c = upper - self.translation;
+            let cdot = -axial_velocity(&a, &b);
+            let old = self.upper_impulse;
+            self.upper_impulse = (old - self.axial_mass * (cdot + c.max(0.0) * step.inv_dt)).max(0.0);
+            let impulse = self.upper_impulse - old;
+            apply_impulses(&mut a, &mut b, axis * -impulse, -impulse * a1, -impulse * a2);
+        }
+
+        // keep the second anchor on the axis and the rotation locked
+        let cdot = Vec2d::new(self.perp * (b.v - a.v) + self.s2 * b.w - self.s1 * a.w, b.w - a.w);
+        let impulse = solve22(self.mass, -cdot);
+        self.impulse += impulse;
+        apply_impulses(&mut a,
+                       &mut b,
+                       self.perp * impulse.x,
+                       impulse.x * self.s1 + impulse.y,
+                       impulse.x * self.s2 + impulse.y);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_position(&mut self, bodies: &mut [SolverBody]) -> bool {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+        let (ma, mb, ia, ib) = (a.inv_mass, b.inv_mass, a.inv_inertia, b.inv_inertia);
+
+        let ra = a.offset(self.local_anchor_a);
+        let rb = b.offset(self.local_anchor_b);
+        let d = (b.center + rb) - (a.center + ra);
+
+        let axis = self.local_axis_a.rotate(&Rotation::new(a.angle));
+        let a1 = (d + ra).cross(axis);
+        let a2 = rb.cross(axis);
+        let perp = axis.perp();
+        let s1 = (d + ra).cross(perp);
+        let s2 = rb.cross(perp);
+
+        let c1 = Vec2d::new(perp * d, b.angle - a.angle - self.reference_angle);
+        let mut linear_error = c1.x.abs();
+        let angular_error = c1.y.abs();
+
+        let mut c2 = None;
+        if let Some((lower, upper)) = self.limits {
+            let translation = axis * d;
+            let max = util::MAX_LINEAR_CORRECTION;
+            if upper - lower < 2.0 * util::LINEAR_SLOP {
+                c2 = Some((translation - lower).clamp(-max, max));
+                linear_error = linear_error.max((translation - lower).abs());
+            } else if translation <= lower {
+                c2 = Some((translation - lower + util::LINEAR_SLOP).clamp(-max, 0.0));
+                linear_error = linear_error.max(lower - translation);
+            } else if translation >= upper {
+                c2 = Some((translation - upper - util::LINEAR_SLOP).clamp(0.0, max));
+                linear_error = linear_error.max(translation - upper);
+            }
+        }
+
+        let k11 = ma + mb + ia * s1 * s1 + ib * s2 * s2;
+        let k12 = ia * s1 + ib * s2;
+        let k22 = if ia + ib == 0.0 { 1.0 } else { ia + ib };
+        let impulse = match c2 {
+            Some(c2) => {
+                let k13 = ia * s1 * a1 + ib * s2 * a2;
+                let k23 = ia * a1 + ib * a2;
+                let k33 = ma + mb + ia * a1 * a1 + ib * a2 * a2;
+                solve33([k11, k12, k13, k22, k23, k33], [-c1.x, -c1.y, -c2])
+            }
+            None => {
+                let impulse = solve22([k11, k12, k22], -c1);
+                [impulse.x, impulse.y, 0.0]
+            }
+        };
+
+        apply_position_impulses(&mut a,
+                                &mut b,
+                                perp * impulse[0] + axis * impulse[2],
+                                impulse[0] * s1 + impulse[1] + impulse[2] * a1,
+                                impulse[0] * s2 + impulse[1] + impulse[2] * a2);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+        linear_error <= util::LINEAR_SLOP && angular_error <= util::ANGULAR_SLOP
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use collision::shapes::Convex;
+    use common::{Rotation, Transform, Vec2d};
+    use dynamics::{BodyType, Fixture, RigidBody, World};
+    use dynamics::joints::{Joint, PrismaticJoint};
+
+    fn square(x: f64, y: f64, density: f64) -> RigidBody {
+        let shape = Convex::new(&[Vec2d::new(-0.5, -0.5),
+                                  Vec2d::new(0.5, -0.5),
+                                  Vec2d::new(0.5, 0.5),
+                                  Vec2d::new(-0.5, 0.5)])
+                        .unwrap();
+        let mut body = RigidBody::new(Transform::new(Vec2d::new(x, y), Rotation::identity()));
+        body.add_fixture(Fixture::new(shape, density));
+        body
+    }
+
+    #[test]
+    fn test_prismatic_joint_limits() {
+        // a box sliding down a diagonal rail under gravity
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let mut rail = square(0.0, 0.0, 0.0);
+        rail.set_body_type(BodyType::Static);
+        let rail = world.add_body(rail);
+        let slider = world.add_body(square(0.0, 0.0, 1.0));
+        let axis = Vec2d::new(1.0, 1.0);
+        let mut joint = PrismaticJoint::new(rail, slider, Vec2d::zero(), Vec2d::zero(), axis);
+        joint.set_limits(-2.0, 1.0);
+        world.add_joint(joint).unwrap();
+
+        for _ in 0..120 {
+            world.step(world.time_step());
+            let body = world.body(slider).unwrap();
+            assert!(body.rotation().angle().abs() < 0.01);
+            assert!(body.position().cross(axis).abs() < 0.01);
+        }
+        let body = world.body(slider).unwrap();
+        let translation = *body.position() * axis.normalize();
+        assert!((translation + 2.0).abs() < 0.02);
+        assert!(body.linear_velocity().len() < 0.05);
+    }
+
+    #[test]
+    fn test_prismatic_joint_motor() {
+        // an elevator lifting its cabin against gravity
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let mut shaft = square(0.0, 0.0, 0.0);
+        shaft.set_body_type(BodyType::Static);
+        let shaft = world.add_body(shaft);
+        let cabin = world.add_body(square(0.0, 0.0, 1.0));
+        let joint = PrismaticJoint::new(shaft, cabin, Vec2d::zero(), Vec2d::zero(), Vec2d::new(0.0, 1.0));
+        let handle = world.add_joint(joint).unwrap();
+        if let Some(&mut Joint::Prismatic(ref mut joint)) = world.joint_mut(handle) {
+            joint.enable_motor(true);
+            joint.set_motor_speed(1.0);
+            joint.set_max_motor_force(100.0);
+            joint.set_limits(0.0, 3.0);
+        }
+
+        for _ in 0..60 {
+            world.step(world.time_step());
+        }
+        let body = world.body(cabin).unwrap();
+        assert!((body.linear_velocity().y - 1.0).abs() < 1e-6);
+        assert!((body.position().y - 1.0).abs() < 0.05);
+
+        // the cabin stops at the upper limit
+        for _ in 0..180 {
+            world.step(world.time_step());
+        }
+        let body = world.body(cabin).unwrap();
+        assert!((body.position().y - 3.0).abs() < 0.02);
+        assert!(body.position().x.abs() < 1e-6);
+    }
+}
@@ -0,0 +1,263 @@
+use common::Vec2d;
+use dynamics::BodyHandle;
+use dynamics::solver_body::{SolverBody, TimeStep};
+use util;
+
+/// PulleyJoint hangs two bodies from a rope running over two fixed
+/// pulleys, so that one body rises as the other one sinks. The ratio
+/// makes a block and tackle, trading length on one side for force.
+///
+/// # Remarks
+///
+/// The joint keeps `length_a + ratio * length_b` constant, where the
+/// lengths are measured from each ground anchor to the anchor on the
+/// body hanging from it. Unless set beforehand, the total length is
+/// taken from the positions of the bodies when the joint is first solved
+#[derive(Debug, Clone)]
+pub struct PulleyJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    ground_anchor_a: Vec2d,
+    ground_anchor_b: Vec2d,
+    local_anchor_a: Vec2d,
+    local_anchor_b: Vec2d,
+    ratio: f64,
+    length: Option<f64>,
+    collide_connected: bool,
+
+    // solver state
+    impulse: f64,
+    a: usize,
+    b: usize,
+    ra: Vec2d,
+    rb: Vec2d,
+    ua: Vec2d,
+    ub: Vec2d,
+    mass: f64,
+}
+
+impl PulleyJoint {
+    /// Creates a new pulley joint hanging `local_anchor_a` in the local
+    /// space of `body_a` from `ground_anchor_a` and `local_anchor_b` in
+    /// the local space of `body_b` from `ground_anchor_b`, where the
+    /// ground anchors are given in world space
+    pub fn new(body_a: BodyHandle,
+               body_b: BodyHandle,
+               ground_anchor_a: Vec2d,
+               ground_anchor_b: Vec2d,
+               local_anchor_a: Vec2d,
+               local_anchor_b: Vec2d,
+               ratio: f64)
+               -> PulleyJoint {
+        debug_assert!(ratio > util::TOLERANCE);
+        PulleyJoint {
+            body_a,
+            body_b,
+            ground_anchor_a,
+            ground_anchor_b,
+            local_anchor_a,
+            local_anchor_b,
+            ratio,
+            length: None,
+            collide_connected: true,
+            impulse: 0.0,
+            a: 0,
+            b: 0,
+            ra: Vec2d::zero(),
+            rb: Vec2d::zero(),
+            ua: Vec2d::zero(),
+            ub: Vec2d::zero(),
+            mass: 0.0,
+        }
+    }
+
+    /// Returns the handle of the first body
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    /// Returns the handle of the second body
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    /// Returns the pulley of the first body in world space
+    pub fn ground_anchor_a(&self) -> &Vec2d {
+        &self.ground_anchor_a
+    }
+
+    /// Returns the pulley of the second body in world space
+    pub fn ground_anchor_b(&self) -> &Vec2d {
+        &self.ground_anchor_b
+    }
+
+    /// Returns the anchor in the local space of the first body
+    pub fn local_anchor_a(&self) -> &Vec2d {
+        &self.local_anchor_a
+    }
+
+    /// Returns the anchor in the local space of the second body
+    pub fn local_anchor_b(&self) -> &Vec2d {
+        &self.local_anchor_b
+    }
+
+    /// Returns the ratio of the pulley
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Returns the total length `length_a + ratio * length_b` kept
+    /// by the joint or `None` if the joint hasn't been solved yet
+    pub fn length(&self) -> Option<f64> {
+        self.length
+    }
+
+    /// Sets the total length `length_a + ratio * length_b` kept by the joint
+    pub fn set_length(&mut self, length: f64) {
+        debug_assert!(length >= 0.0);
+        self.length = Some(length);
+    }
+
+    /// Returns if the fixtures of the jointed bodies collide with each other
+    pub fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    /// Sets if the fixtures of the jointed bodies collide with each other
+    pub fn set_collide_connected(&mut self, collide: bool) {
+        self.collide_connected = collide;
+    }
+
+    /// Returns the offsets of the anchors, the unit directions of the
+    /// ropes from the pulleys and the lengths of the ropes
+    fn ropes(&self, a: &SolverBody, b: &SolverBody) -> (Vec2d, Vec2d, Vec2d, Vec2d, f64, f64) {
+        let ra = a.offset(self.local_anchor_a);
+        let rb = b.offset(self.local_anchor_b);
+        let ua = a.center + ra - self.ground_anchor_a;
+        let ub = b.center + rb - self.ground_anchor_b;
+        let (la, lb) = (ua.len(), ub.len());
+        let direction = |u: Vec2d, l: f64| if l > 10.0 * util::LINEAR_SLOP { u / l } else { Vec2d::zero() };
+        (ra, rb, direction(ua, la), direction(ub, lb), la, lb)
+    }
+
+    /// Returns the effective mass of the rope
+    fn mass(&self, a: &SolverBody, b: &SolverBody, ra: Vec2d, rb: Vec2d, ua: Vec2d, ub: Vec2d) -> f64 {
+        let (rua, rub) = (ra.cross(ua), rb.cross(ub));
+        let ma = a.inv_mass + a.inv_inertia * rua * rua;
+        let mb = b.inv_mass + b.inv_inertia * rub * rub;
+        let mass = ma + self.ratio * self.ratio * mb;
+        if mass > 0.0 { 1.0 / mass } else { 0.0 }
+    }
+
+    /// Returns the force the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2d {
+        self.ub * (-self.ratio * self.impulse * inv_dt)
+    }
+
+    /// Returns the torque the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_torque(&self, _inv_dt: f64) -> f64 {
+        0.0
+    }
+
+    pub(crate) fn init_velocity(&mut self,
+                                bodies: &mut [SolverBody],
+                                index: &dyn Fn(BodyHandle) -> usize,
+                                step: &TimeStep) {
+        self.a = index(self.body_a);
+        self.b = index(self.body_b);
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        let (ra, rb, ua, ub, la, lb) = self.ropes(&a, &b);
+        if self.length.is_none() {
+            self.length = Some(la + self.ratio * lb);
+        }
+        self.ra = ra;
+        self.rb = rb;
+        self.ua = ua;
+        self.ub = ub;
+        self.mass = self.mass(&a, &b, ra, rb, ua, ub);
+
+        if step.warm_starting {
+            a.apply_impulse(self.ua * -self.impulse, self.ra);
+            b.apply_impulse(self.ub * (-self.ratio * self.impulse), self.rb);
+        } else {
+            self.impulse = 0.0;
+        }
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_velocity(&mut self, bodies: &mut [SolverBody], _step: &TimeStep) {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        let cdot = -(self.ua * a.velocity_at(self.ra)) - self.ratio * (self.ub * b.velocity_at(self.rb));
+        let impulse = -self.mass * cdot;
+        self.impulse += impulse;
+        a.apply_impulse(self.ua * -impulse, self.ra);
+        b.apply_impulse(self.ub * (-self.ratio * impulse), self.rb);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_position(&mut self, bodies: &mut [SolverBody]) -> bool {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        let (ra, rb, ua, ub, la, lb) = self.ropes(&a, &b);
+        let c = self.length.unwrap_or(0.0) - la - self.ratio * lb;
+        let impulse = -self.mass(&a, &b, ra, rb, ua, ub) * c;
+        a.apply_position_impulse(ua * -impulse, ra);
+        b.apply_position_impulse(ub * (-self.ratio * impulse), rb);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+        c.abs() < util::LINEAR_SLOP
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use collision::shapes::Circle;
+    use common::{Rotation, Transform, Vec2d};
+    use dynamics::{Fixture, RigidBody, World};
+    use dynamics::joints::{Joint, PulleyJoint};
+
+    #[test]
+    fn test_pulley_joint() {
+        let ball = |x: f64, radius: f64| {
+            let mut body = RigidBody::new(Transform::new(Vec2d::new(x, 0.0), Rotation::identity()));
+            body.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), radius).unwrap(), 1.0));
+            body
+        };
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let heavy = world.add_body(ball(-2.0, 0.5));
+        let light = world.add_body(ball(2.0, 0.25));
+        let joint = PulleyJoint::new(heavy,
+                                     light,
+                                     Vec2d::new(-2.0, 5.0),
+                                     Vec2d::new(2.0, 5.0),
+                                     Vec2d::zero(),
+                                     Vec2d::zero(),
+                                     2.0);
+        let handle = world.add_joint(joint).unwrap();
+
+        for _ in 0..60 {
+            world.step(world.time_step());
+            let ya = world.body(heavy).unwrap().position().y;
+            let yb = world.body(light).unwrap().position().y;
+            assert!(((5.0 - ya) + 2.0 * (5.0 - yb) - 15.0).abs() < 0.01);
+        }
+
+        // the light ball is pulled up half as fast as the heavy one sinks
+        let va = world.body(heavy).unwrap().linear_velocity().y;
+        let vb = world.body(light).unwrap().linear_velocity().y;
+        assert!(va < 0.0);
+        assert!((vb + 0.5 * va).abs() < 1e-3);
+        match world.joint(handle) {
+            Some(Joint::Pulley(joint)) => assert_eq!(Some(15.0), joint.length()),
+            _ => panic!(),
+        }
+    }
+}
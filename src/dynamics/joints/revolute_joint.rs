@@ -0,0 +1,409 @@
+use common::{Rotation, Vec2d};
+use dynamics::BodyHandle;
+use dynamics::joints::joint::{point_mass, solve22};
+use dynamics::solver_body::{SolverBody, TimeStep};
+use util;
+
+/// RevoluteJoint pins two bodies together at an anchor point,
+/// leaving them free to rotate relative to each other. The relative
+/// rotation may be limited to a range and driven by a motor.
+///
+/// # Remarks
+///
+/// The joint angle is the rotation of the second body relative to the
+/// first body minus the reference angle, wrapped into `[-pi, pi]`
+#[derive(Debug, Clone)]
+pub struct RevoluteJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    local_anchor_a: Vec2d,
+    local_anchor_b: Vec2d,
+    reference_angle: f64,
+    collide_connected: bool,
+    limits: Option<(Rotation, Rotation)>,
+    motor_enabled: bool,
+    motor_speed: f64,
+    max_motor_torque: f64,
+
+    // solver state
+    impulse: Vec2d,
+    motor_impulse: f64,
+    lower_impulse: f64,
+    upper_impulse: f64,
+    a: usize,
+    b: usize,
+    ra: Vec2d,
+    rb: Vec2d,
+    mass: [f64; 3],
+    axial_mass: f64,
+}
+
+impl RevoluteJoint {
+    /// Creates a new revolute joint pinning `local_anchor_a` in the
+    /// local space of `body_a` to `local_anchor_b` in the local space
+    /// of `body_b`, without limits or motor
+    pub fn new(body_a: BodyHandle,
+               body_b: BodyHandle,
+               local_anchor_a: Vec2d,
+               local_anchor_b: Vec2d)
+               -> RevoluteJoint {
+        RevoluteJoint {
+            body_a,
+            body_b,
+            local_anchor_a,
+            local_anchor_b,
+            reference_angle: 0.0,
+            collide_connected: false,
+            limits: None,
+            motor_enabled: false,
+            motor_speed: 0.0,
+            max_motor_torque: 0.0,
+            impulse: Vec2d::zero(),
+            motor_impulse: 0.0,
+            lower_impulse: 0.0,
+            upper_impulse: 0.0,
+            a: 0,
+            b: 0,
+            ra: Vec2d::zero(),
+            rb: Vec2d::zero(),
+            mass: [0.0; 3],
+            axial_mass: 0.0,
+        }
+    }
+
+    /// Returns the handle of the first body
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    /// Returns the handle of the second body
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    /// Returns the anchor in the local space of the first body
+    pub fn local_anchor_a(&self) -> &Vec2d {
+        &self.local_anchor_a
+    }
+
+    /// Returns the anchor in the local space of the second body
+    pub fn local_anchor_b(&self) -> &Vec2d {
+        &self.local_anchor_b
+    }
+
+    /// Returns the rotation of the second body relative
+    /// to the first body at which the joint angle is 0
+    pub fn reference_angle(&self) -> f64 {
+        self.reference_angle
+    }
+
+    /// Sets the rotation of the second body relative
+    /// to the first body at which the joint angle is 0
+    pub fn set_reference_angle(&mut self, angle: f64) {
+        self.reference_angle = angle;
+    }
+
+    /// Returns if the fixtures of the jointed bodies collide with each other
+    pub fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    /// Sets if the fixtures of the jointed bodies collide with each other
+    pub fn set_collide_connected(&mut self, collide: bool) {
+        self.collide_connected = collide;
+    }
+
+    /// Returns the lower and upper limit of the joint angle
+    /// or `None` if the joint angle is unlimited
+    pub fn limits(&self) -> Option<(Rotation, Rotation)> {
+        self.limits
+    }
+
+    /// Limits the joint angle to the range from `lower` to `upper`
+    pub fn set_limits(&mut self, lower: Rotation, upper: Rotation) {
+        debug_assert!(lower.angle() <= upper.angle());
+        self.limits = Some((lower, upper));
+    }
+
+    /// Removes the limits of the joint angle
+    pub fn clear_limits(&mut self) {
+        self.limits = None;
+    }
+
+    /// Returns if the motor is enabled
+    pub fn is_motor_enabled(&self) -> bool {
+        self.motor_enabled
+    }
+
+    /// Enables or disables the motor
+    pub fn enable_motor(&mut self, enabled: bool) {
+        self.motor_enabled = enabled;
+    }
+
+    /// Returns the target speed of the motor in radians per second
+    pub fn motor_speed(&self) -> f64 {
+        self.motor_speed
+    }
+
+    /// Sets the target speed of the motor in radians per second
+    pub fn set_motor_speed(&mut self, speed: f64) {
+        self.motor_speed = speed;
+    }
+
+    /// Returns the maximum torque of the motor
+    pub fn max_motor_torque(&self) -> f64 {
+        self.max_motor_torque
+    }
+
+    /// Sets the maximum torque the motor may apply to reach its speed
+    pub fn set_max_motor_torque(&mut self, torque: f64) {
+        debug_assert!(torque >= 0.0);
+        self.max_motor_torque = torque;
+    }
+
+    /// Returns the joint angle of the bodies
+    fn angle(&self, a: &SolverBody, b: &SolverBody) -> f64 {
+        util::wrap_angle(b.angle - a.angle - self.reference_angle)
+    }
+
+    /// Returns the force the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2d {
+        self.impulse * inv_dt
+    }
+
+    /// Returns the torque the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_torque(&self, inv_dt: f64) -> f64 {
+        inv_dt * (self.motor_impulse + self.lower_impulse - self.upper_impulse)
+    }
+
+    pub(crate) fn init_velocity(&mut self,
+                                bodies: &mut [SolverBody],
+                                index: &dyn Fn(BodyHandle) -> usize,
+                                step: &TimeStep) {
+        self.a = index(self.body_a);
+        self.b = index(self.body_b);
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        self.ra = a.offset(self.local_anchor_a);
+        self.rb = b.offset(self.local_anchor_b);
+        self.mass = point_mass(&a, &b, self.ra, self.rb);
+        let fixed_rotation = a.inv_inertia + b.inv_inertia == 0.0;
+        self.axial_mass = if fixed_rotation {
+            0.0
+        } else {
+            1.0 / (a.inv_inertia + b.inv_inertia)
+        };
+
+        if self.limits.is_none() || fixed_rotation {
+            self.lower_impulse = 0.0;
+            self.upper_impulse = 0.0;
+        }
+        if !self.motor_enabled || fixed_rotation {
+            self.motor_impulse = 0.0;
+        }
+
+        if step.warm_starting {
+            let axial = self.motor_impulse + self.lower_impulse - self.upper_impulse;
+            a.apply_impulse(-self.impulse, self.ra);
+            a.w -= a.inv_inertia * axial;
+            b.apply_impulse(self.impulse, self.rb);
+            b.w += b.inv_inertia * axial;
+        } else {
+            self.impulse = Vec2d::zero();
+            self.motor_impulse = 0.0;
+            self.lower_impulse = 0.0;
+            self.upper_impulse = 0.0;
+        }
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_velocity(&mut self, bodies: &mut [SolverBody], step: &TimeStep) {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+        let fixed_rotation = self.axial_mass == 0.0;
+
+        if self.motor_enabled && !fixed_rotation {
+            let cdot = b.w - a.w - self.motor_speed;
+            let max = step.dt * self.max_motor_torque;
+            let old = self.motor_impulse;
+            self.motor_impulse = (old - self.axial_mass * cdot).clamp(-max, max);
+            let impulse = self.motor_impulse - old;
+            a.w -= a.inv_inertia * impulse;
+            b.w += b.inv_inertia * impulse;
+        }
+
+        if let (Some((lower, upper)), false) = (self.limits, fixed_rotation) {
+            let angle = self.angle(&a, &b);
+
+            // the bias lets the bodies approach the limit over one step
+            let c = angle - lower.angle();
+            let cdot = b.w - a.w;
+            let old = self.lower_impulse;
+            self.lower_impulse = (old - self.axial_mass * (cdot + c.max(0.0) * step.inv_dt)).max(0.0);
+            let impulse = self.lower_impulse - old;
+            a.w -= a.inv_inertia * impulse;
+            b.w += b.inv_inertia * impulse;
+
+            let c = upper.angle() - angle;
+            let cdot = a.w - b.w;
+            let old = self.upper_impulse;
+            self.upper_impulse = (old - self.axial_mass * (cdot + c.max(0.0) * step.inv_dt)).max(0.0);
+            let impulse = self.upper_impulse - old;
+            a.w += a.inv_inertia * impulse;
+            b.w -= b.inv_inertia * impulse;
+        }
+
+        // keep the anchors together
+        let cdot = b.velocity_at(self.rb) - a.velocity_at(self.ra);
+        let impulse = solve22(self.mass, -cdot);
+        self.impulse += impulse;
+        a.apply_impulse(-impulse, self.ra);
+        b.apply_impulse(impulse, self.rb);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_position(&mut self, bodies: &mut [SolverBody]) -> bool {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        let mut angular_error = 0.0;
+        if let (Some((lower, upper)), false) = (self.limits, self.axial_mass == 0.0) {
+            let angle = self.angle(&a, &b);
+            let (lower, upper) = (lower.angle(), upper.angle());
+            let max = util::MAX_ANGULAR_CORRECTION;
+            let c = if upper - lower < 2.0 * util::ANGULAR_SLOP {
+                (angle - lower).clamp(-max, max)
+            } else if angle <= lower {
+                (angle - lower + util::ANGULAR_SLOP).clamp(-max, 0.0)
+            } else if angle >= upper {
+                (angle - upper - util::ANGULAR_SLOP).clamp(0.0, max)
+            } else {
+                0.0
+            };
+            let impulse = -self.axial_mass * c;
+            a.angle -= a.inv_inertia * impulse;
+            b.angle += b.inv_inertia * impulse;
+            angular_error = c.abs();
+        }
+
+        let ra = a.offset(self.local_anchor_a);
+        let rb = b.offset(self.local_anchor_b);
+        let c = (b.center + rb) - (a.center + ra);
+        let impulse = -solve22(point_mass(&a, &b, ra, rb), c);
+        a.apply_position_impulse(-impulse, ra);
+        b.apply_position_impulse(impulse, rb);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+        c.len() <= util::LINEAR_SLOP && angular_error <= util::ANGULAR_SLOP
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+    use collision::shapes::Convex;
+    use common::{Rotation, Transform, Vec2d};
+    use dynamics::{BodyType, Fixture, RigidBody, World};
+    use dynamics::joints::{Joint, RevoluteJoint};
+
+    fn bar(x: f64, y: f64) -> RigidBody {
+        let shape = Convex::new(&[Vec2d::new(-1.0, -0.1),
+                                  Vec2d::new(1.0, -0.1),
+                                  Vec2d::new(1.0, 0.1),
+                                  Vec2d::new(-1.0, 0.1)])
+                        .unwrap();
+        let mut body = RigidBody::new(Transform::new(Vec2d::new(x, y), Rotation::identity()));
+        body.add_fixture(Fixture::new(shape, 1.0));
+        body
+    }
+
+    fn anchor() -> RigidBody {
+        let mut body = RigidBody::new(Transform::identity());
+        body.add_fixture(Fixture::new(Convex::new(&[Vec2d::new(-0.1, -0.1),
+                                                    Vec2d::new(0.1, -0.1),
+                                                    Vec2d::new(0.1, 0.1),
+                                                    Vec2d::new(-0.1, 0.1)])
+                                          .unwrap(),
+                                      0.0));
+        body.set_body_type(BodyType::Static);
+        body
+    }
+
+    #[test]
+    fn test_revolute_joint_pendulum() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let ground = world.add_body(anchor());
+        let pendulum = world.add_body(bar(1.0, 0.0));
+        let joint = RevoluteJoint::new(ground, pendulum, Vec2d::zero(), Vec2d::new(-1.0, 0.0));
+        let handle = world.add_joint(joint).unwrap();
+        assert_eq!(1, world.joint_count());
+
+        let mut lowest: f64 = 0.0;
+        for _ in 0..120 {
+            world.step(world.time_step());
+            let body = world.body(pendulum).unwrap();
+            let end = Vec2d::new(-1.0, 0.0).transform(body.transform());
+            assert!(end.len() < 0.01);
+            lowest = lowest.min(body.position().y);
+        }
+
+        // the bar swings down about the anchor without touching the ground body
+        assert!(lowest < -0.9);
+        assert!(world.contacts().is_empty());
+
+        // removing a body drops its joints
+        world.remove_body(ground);
+        assert!(world.joint(handle).is_none());
+        assert_eq!(0, world.joint_count());
+    }
+
+    #[test]
+    fn test_revolute_joint_limits() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let ground = world.add_body(anchor());
+        let pendulum = world.add_body(bar(1.0, 0.0));
+        let mut joint = RevoluteJoint::new(ground, pendulum, Vec2d::zero(), Vec2d::new(-1.0, 0.0));
+        joint.set_limits(Rotation::new(-PI / 4.0), Rotation::new(PI / 4.0));
+        world.add_joint(joint).unwrap();
+
+        for _ in 0..120 {
+            world.step(world.time_step());
+            let angle = world.body(pendulum).unwrap().rotation().angle();
+            assert!(angle > -PI / 4.0 - 0.05);
+        }
+        let angle = world.body(pendulum).unwrap().rotation().angle();
+        assert!((angle + PI / 4.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_revolute_joint_motor() {
+        let mut world = World::new(Vec2d::zero());
+        let ground = world.add_body(anchor());
+        let wheel = world.add_body(bar(0.0, 0.0));
+        let handle = world.add_joint(RevoluteJoint::new(ground, wheel, Vec2d::zero(), Vec2d::zero()))
+                          .unwrap();
+        if let Some(&mut Joint::Revolute(ref mut joint)) = world.joint_mut(handle) {
+            joint.enable_motor(true);
+            joint.set_motor_speed(2.0);
+            joint.set_max_motor_torque(100.0);
+        }
+
+        for _ in 0..30 {
+            world.step(world.time_step());
+        }
+        let body = world.body(wheel).unwrap();
+        assert!((body.angular_velocity() - 2.0).abs() < 1e-6);
+        assert!(body.position().len() < 1e-6);
+
+        // joints between the same body or missing bodies are rejected
+        let joint = RevoluteJoint::new(wheel, wheel, Vec2d::zero(), Vec2d::zero());
+        assert!(world.add_joint(joint).is_err());
+        world.remove_body(ground);
+        let joint = RevoluteJoint::new(ground, wheel, Vec2d::zero(), Vec2d::zero());
+        assert!(world.add_joint(joint).is_err());
+    }
+}
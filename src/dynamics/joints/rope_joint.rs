@@ -0,0 +1,223 @@
+use common::Vec2d;
+use dynamics::BodyHandle;
+use dynamics::solver_body::{SolverBody, TimeStep};
+use util;
+
+/// RopeJoint limits the distance between an anchor point on each
+/// of two bodies to a maximum length, leaving the bodies free to
+/// move closer to each other like the ends of a rope
+#[derive(Debug, Clone)]
+pub struct RopeJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    local_anchor_a: Vec2d,
+    local_anchor_b: Vec2d,
+    max_length: f64,
+    collide_connected: bool,
+
+    // solver state
+    impulse: f64,
+    a: usize,
+    b: usize,
+    ra: Vec2d,
+    rb: Vec2d,
+    u: Vec2d,
+    length: f64,
+    mass: f64,
+}
+
+impl RopeJoint {
+    /// Creates a new rope joint keeping `local_anchor_a` in the local
+    /// space of `body_a` within `max_length` of `local_anchor_b` in
+    /// the local space of `body_b`
+    pub fn new(body_a: BodyHandle,
+               body_b: BodyHandle,
+               local_anchor_a: Vec2d,
+               local_anchor_b: Vec2d,
+               max_length: f64)
+               -> RopeJoint {
+        debug_assert!(max_length >= 0.0);
+        RopeJoint {
+            body_a,
+            body_b,
+            local_anchor_a,
+            local_anchor_b,
+            max_length,
+            collide_connected: false,
+            impulse: 0.0,
+            a: 0,
+            b: 0,
+            ra: Vec2d::zero(),
+            rb: Vec2d::zero(),
+            u: Vec2d::zero(),
+            length: 0.0,
+            mass: 0.0,
+        }
+    }
+
+    /// Returns the handle of the first body
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    /// Returns the handle of the second body
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    /// Returns the anchor in the local space of the first body
+    pub fn local_anchor_a(&self) -> &Vec2d {
+        &self.local_anchor_a
+    }
+
+    /// Returns the anchor in the local space of the second body
+    pub fn local_anchor_b(&self) -> &Vec2d {
+        &self.local_anchor_b
+    }
+
+    /// Returns the maximum distance between the anchors
+    pub fn max_length(&self) -> f64 {
+        self.max_length
+    }
+
+    /// Sets the maximum distance between the anchors
+    pub fn set_max_length(&mut self, max_length: f64) {
+        debug_assert!(max_length >= 0.0);
+        self.max_length = max_length;
+    }
+
+    /// Returns if the fixtures of the jointed bodies collide with each other
+    pub fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    /// Sets if the fixtures of the jointed bodies collide with each other
+    pub fn set_collide_connected(&mut self, collide: bool) {
+        self.collide_connected = collide;
+    }
+
+    /// Returns the force the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2d {
+        self.u * (self.impulse * inv_dt)
+    }
+
+    /// Returns the torque the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_torque(&self, _inv_dt: f64) -> f64 {
+        0.0
+    }
+
+    pub(crate) fn init_velocity(&mut self,
+                                bodies: &mut [SolverBody],
+                                index: &dyn Fn(BodyHandle) -> usize,
+                                step: &TimeStep) {
+        self.a = index(self.body_a);
+        self.b = index(self.body_b);
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        self.ra = a.offset(self.local_anchor_a);
+        self.rb = b.offset(self.local_anchor_b);
+        let d = (b.center + self.rb) - (a.center + self.ra);
+        self.length = d.len();
+        if self.length <= util::LINEAR_SLOP {
+            // the anchors coincide, so the rope is slack in every direction
+            self.u = Vec2d::zero();
+            self.mass = 0.0;
+            self.impulse = 0.0;
+            return;
+        }
+        self.u = d / self.length;
+
+        let cra = self.ra.cross(self.u);
+        let crb = self.rb.cross(self.u);
+        let inv_mass = a.inv_mass + a.inv_inertia * cra * cra + b.inv_mass + b.inv_inertia * crb * crb;
+        self.mass = if inv_mass != 0.0 { 1.0 / inv_mass } else { 0.0 };
+
+        if step.warm_starting {
+            let impulse = self.u * self.impulse;
+            a.apply_impulse(-impulse, self.ra);
+            b.apply_impulse(impulse, self.rb);
+        } else {
+            self.impulse = 0.0;
+        }
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_velocity(&mut self, bodies: &mut [SolverBody], step: &TimeStep) {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        // a slack rope lets the anchors close the gap within the step
+        let mut cdot = self.u * (b.velocity_at(self.rb) - a.velocity_at(self.ra));
+        let c = self.length - self.max_length;
+        if c < 0.0 {
+            cdot += step.inv_dt * c;
+        }
+
+        // the rope may only pull
+        let old = self.impulse;
+        self.impulse = (old - self.mass * cdot).min(0.0);
+        let impulse = self.u * (self.impulse - old);
+        a.apply_impulse(-impulse, self.ra);
+        b.apply_impulse(impulse, self.rb);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_position(&mut self, bodies: &mut [SolverBody]) -> bool {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        let ra = a.offset(self.local_anchor_a);
+        let rb = b.offset(self.local_anchor_b);
+        let d = (b.center + rb) - (a.center + ra);
+        let length = d.len();
+        let u = if length > 0.0 { d / length } else { Vec2d::zero() };
+        let c = (length - self.max_length).clamp(0.0, util::MAX_LINEAR_CORRECTION);
+        let impulse = u * (-self.mass * c);
+        a.apply_position_impulse(-impulse, ra);
+        b.apply_position_impulse(impulse, rb);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+        length - self.max_length < util::LINEAR_SLOP
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use collision::shapes::Circle;
+    use common::{Rotation, Transform, Vec2d};
+    use dynamics::{BodyType, Fixture, RigidBody, World};
+    use dynamics::joints::RopeJoint;
+
+    #[test]
+    fn test_rope_joint() {
+        let ball = |x: f64, y: f64, density: f64| {
+            let mut body = RigidBody::new(Transform::new(Vec2d::new(x, y), Rotation::identity()));
+            body.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.1).unwrap(), density));
+            body
+        };
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let mut hook = ball(0.0, 0.0, 0.0);
+        hook.set_body_type(BodyType::Static);
+        let hook = world.add_body(hook);
+        let bob = world.add_body(ball(0.5, 0.0, 1.0));
+        world.add_joint(RopeJoint::new(hook, bob, Vec2d::zero(), Vec2d::zero(), 2.0)).unwrap();
+
+        // the bob falls freely until the rope goes taut
+        for _ in 0..10 {
+            world.step(world.time_step());
+        }
+        let body = world.body(bob).unwrap();
+        assert!(body.linear_velocity().y < -1.5);
+        assert!(body.position().len() < 2.0);
+
+        for _ in 0..120 {
+            world.step(world.time_step());
+            assert!(world.body(bob).unwrap().position().len() < 2.0 + 0.01);
+        }
+        assert!(world.body(bob).unwrap().position().len() > 2.0 - 0.05);
+    }
+}
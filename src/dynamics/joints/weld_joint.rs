@@ -0,0 +1,311 @@
+use std::f64::consts::PI;
+use common::Vec2d;
+use dynamics::BodyHandle;
+use dynamics::joints::joint::{apply_impulses, apply_position_impulses, solve22, solve33};
+use dynamics::solver_body::{SolverBody, TimeStep};
+use util;
+
+/// WeldJoint glues two bodies together at an anchor point, locking
+/// both their relative position and their relative rotation.
+///
+/// # Remarks
+///
+/// Given a frequency the relative rotation becomes a damped angular
+/// spring, which makes structures of welded bodies bend under load
+#[derive(Debug, Clone)]
+pub struct WeldJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    local_anchor_a: Vec2d,
+    local_anchor_b: Vec2d,
+    reference_angle: f64,
+    frequency: f64,
+    damping_ratio: f64,
+    collide_connected: bool,
+
+    // solver state
+    impulse: [f64; 3],
+    a: usize,
+    b: usize,
+    ra: Vec2d,
+    rb: Vec2d,
+    k: [f64; 6],
+    axial_mass: f64,
+    gamma: f64,
+    bias: f64,
+}
+
+impl WeldJoint {
+    /// Creates a new rigid weld joint gluing `local_anchor_a` in the
+    /// local space of `body_a` to `local_anchor_b` in the local space
+    /// of `body_b`
+    pub fn new(body_a: BodyHandle,
+               body_b: BodyHandle,
+               local_anchor_a: Vec2d,
+               local_anchor_b: Vec2d)
+               -> WeldJoint {
+        WeldJoint {
+            body_a,
+            body_b,
+            local_anchor_a,
+            local_anchor_b,
+            reference_angle: 0.0,
+            frequency: 0.0,
+            damping_ratio: 0.0,
+            collide_connected: false,
+            impulse: [0.0; 3],
+            a: 0,
+            b: 0,
+            ra: Vec2d::zero(),
+            rb: Vec2d::zero(),
+            k: [0.0; 6],
+            axial_mass: 0.0,
+            gamma: 0.0,
+            bias: 0.0,
+        }
+    }
+
+    /// Returns the handle of the first body
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    /// Returns the handle of the second body
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    /// Returns the anchor in the local space of the first body
+    pub fn local_anchor_a(&self) -> &Vec2d {
+        &self.local_anchor_a
+    }
+
+    /// Returns the anchor in the local space of the second body
+    pub fn local_anchor_b(&self) -> &Vec2d {
+        &self.local_anchor_b
+    }
+
+    /// Returns the rotation of the second body relative to the first body
+    pub fn reference_angle(&self) -> f64 {
+        self.reference_angle
+    }
+
+    /// Sets the rotation of the second body relative to the first body
+    pub fn set_reference_angle(&mut self, angle: f64) {
+        self.reference_angle = angle;
+    }
+
+    /// Returns the angular oscillation frequency in Hertz, 0 for a rigid joint
+    pub fn frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// Sets the angular oscillation frequency in Hertz. A frequency of
+    /// 0 makes the joint rigid. Frequencies above half the step rate
+    /// are unstable
+    pub fn set_frequency(&mut self, frequency: f64) {
+        debug_assert!(frequency >= 0.0);
+        self.frequency = frequency;
+    }
+
+    /// Returns the damping ratio of the angular spring
+    pub fn damping_ratio(&self) -> f64 {
+        self.damping_ratio
+    }
+
+    /// Sets the damping ratio of the angular spring. 0 doesn't
+    /// damp at all, 1 damps critically
+    pub fn set_damping_ratio(&mut self, ratio: f64) {
+        debug_assert!(ratio >= 0.0);
+        self.damping_ratio = ratio;
+    }
+
+    /// Returns if the fixtures of the jointed bodies collide with each other
+    pub fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    /// Sets if the fixtures of the jointed bodies collide with each other
+    pub fn set_collide_connected(&mut self, collide: bool) {
+        self.collide_connected = collide;
+    }
+
+    /// Returns the force the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2d {
+        Vec2d::new(self.impulse[0], self.impulse[1]) * inv_dt
+    }
+
+    /// Returns the torque the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_torque(&self, inv_dt: f64) -> f64 {
+        inv_dt * self.impulse[2]
+    }
+
+    pub(crate) fn init_velocity(&mut self,
+                                bodies: &mut [SolverBody],
+                                index: &dyn Fn(BodyHandle) -> usize,
+                                step: &TimeStep) {
+        self.a = index(self.body_a);
+        self.b = index(self.body_b);
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        self.ra = a.offset(self.local_anchor_a);
+        self.rb = b.offset(self.local_anchor_b);
+        self.k = weld_mass(&a, &b, self.ra, self.rb);
+
+        let inv_mass = a.inv_inertia + b.inv_inertia;
+        if self.frequency > 0.0 && inv_mass > 0.0 {
+            // spring stiffness and damping of the rotational mass
+            let mass = 1.0 / inv_mass;
+            let omega = 2.0 * PI * self.frequency;
+            let damping = 2.0 * mass * self.damping_ratio * omega;
+            let stiffness = mass * omega * omega;
+
+            let h = step.dt;
+            self.gamma = h * (damping + h * stiffness);
+            self.gamma = if self.gamma != 0.0 { 1.0 / self.gamma } else { 0.0 };
+            self.bias = (b.angle - a.angle - self.reference_angle) * h * stiffness * self.gamma;
+            self.axial_mass = 1.0 / (inv_mass + self.gamma);
+        } else {
+            self.gamma = 0.0;
+            self.bias = 0.0;
+            self.axial_mass = 0.0;
+        }
+
+        if step.warm_starting {
+            let p = Vec2d::new(self.impulse[0], self.impulse[1]);
+            apply_impulses(&mut a,
+                           &mut b,
+                           p,
+                           self.ra.cross(p) + self.impulse[2],
+                           self.rb.cross(p) + self.impulse[2]);
+        } else {
+            self.impulse = [0.0; 3];
+        }
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_velocity(&mut self, bodies: &mut [SolverBody], _step: &TimeStep) {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+        let [k11, k12, _, k22, _, k33] = self.k;
+
+        let impulse = if self.frequency > 0.0 {
+            let cdot = b.w - a.w;
+            let angular = -self.axial_mass * (cdot + self.bias + self.gamma * self.impulse[2]);
+            a.w -= a.inv_inertia * angular;
+            b.w += b.inv_inertia * angular;
+
+            let cdot = b.velocity_at(self.rb) - a.velocity_at(self.ra);
+            let linear = solve22([k11, k12, k22], -cdot);
+            [linear.x, linear.y, angular]
+        } else {
+            let cdot = b.velocity_at(self.rb) - a.velocity_at(self.ra);
+            let cdot = [-cdot.x, -cdot.y, a.w - b.w];
+            if k33 > 0.0 {
+                solve33(self.k, cdot)
+            } else {
+                let linear = solve22([k11, k12, k22], Vec2d::new(cdot[0], cdot[1]));
+                [linear.x, linear.y, 0.0]
+            }
+        };
+
+        let p = Vec2d::new(impulse[0], impulse[1]);
+        let angular = if self.frequency > 0.0 { 0.0 } else { impulse[2] };
+        apply_impulses(&mut a, &mut b, p, self.ra.cross(p) + angular, self.rb.cross(p) + angular);
+        for (total, impulse) in self.impulse.iter_mut().zip(&impulse) {
+            *total += impulse;
+        }
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_position(&mut self, bodies: &mut [SolverBody]) -> bool {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        let ra = a.offset(self.local_anchor_a);
+        let rb = b.offset(self.local_anchor_b);
+        let k = weld_mass(&a, &b, ra, rb);
+        let [k11, k12, _, k22, _, k33] = k;
+
+        let c1 = (b.center + rb) - (a.center + ra);
+        let c2 = b.angle - a.angle - self.reference_angle;
+        let linear_error = c1.len();
+        let (impulse, angular_error) = if self.frequency > 0.0 {
+            let linear = solve22([k11, k12, k22], -c1);
+            ([linear.x, linear.y, 0.0], 0.0)
+        } else if k33 > 0.0 {
+            (solve33(k, [-c1.x, -c1.y, -c2]), c2.abs())
+        } else {
+            let linear = solve22([k11, k12, k22], -c1);
+            ([linear.x, linear.y, 0.0], c2.abs())
+        };
+
+        let p = Vec2d::new(impulse[0], impulse[1]);
+        apply_position_impulses(&mut a, &mut b, p, ra.cross(p) + impulse[2], rb.cross(p) + impulse[2]);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+        linear_error <= util::LINEAR_SLOP && angular_error <= util::ANGULAR_SLOP
+    }
+}
+
+/// Returns the effective mass matrix `[k11, k12, k13, k22, k23, k33]`
+/// of a point and an angular constraint between the anchors at the
+/// offsets `ra` and `rb` from the centers of mass of `a` and `b`
+fn weld_mass(a: &SolverBody, b: &SolverBody, ra: Vec2d, rb: Vec2d) -> [f64; 6] {
+    let (ma, mb, ia, ib) = (a.inv_mass, b.inv_mass, a.inv_inertia, b.inv_inertia);
+    [ma + mb + ra.y * ra.y * ia + rb.y * rb.y * ib,
+     -ra.y * ra.x * ia - rb.y * rb.x * ib,
+     -ra.y * ia - rb.y * ib,
+     ma + mb + ra.x * ra.x * ia + rb.x * rb.x * ib,
+     ra.x * ia + rb.x * ib,
+     ia + ib]
+}
+
+#[cfg(test)]
+mod test {
+    use collision::shapes::Convex;
+    use common::{Rotation, Transform, Vec2d};
+    use dynamics::{BodyType, Fixture, RigidBody, World};
+    use dynamics::joints::WeldJoint;
+
+    fn bar(x: f64, density: f64) -> RigidBody {
+        let shape = Convex::new(&[Vec2d::new(-1.0, -0.1),
+                                  Vec2d::new(1.0, -0.1),
+                                  Vec2d::new(1.0, 0.1),
+                                  Vec2d::new(-1.0, 0.1)])
+                        .unwrap();
+        let mut body = RigidBody::new(Transform::new(Vec2d::new(x, 0.0), Rotation::identity()));
+        body.add_fixture(Fixture::new(shape, density));
+        body
+    }
+
+    #[test]
+    fn test_weld_joint() {
+        // a beam sticking out of a wall under gravity
+        let cantilever = |frequency: f64| {
+            let mut world = World::new(Vec2d::new(0.0, -10.0));
+            let mut wall = bar(0.0, 0.0);
+            wall.set_body_type(BodyType::Static);
+            let wall = world.add_body(wall);
+            let beam = world.add_body(bar(2.0, 1.0));
+            let mut joint = WeldJoint::new(wall, beam, Vec2d::new(1.0, 0.0), Vec2d::new(-1.0, 0.0));
+            joint.set_frequency(frequency);
+            joint.set_damping_ratio(1.0);
+            world.add_joint(joint).unwrap();
+            for _ in 0..120 {
+                world.step(world.time_step());
+            }
+            let body = world.remove_body(beam).unwrap();
+            assert!((Vec2d::new(-1.0, 0.0).transform(body.transform()) - Vec2d::new(1.0, 0.0)).len() < 0.01);
+            body.rotation().angle()
+        };
+
+        // a rigid weld holds the beam level while a soft weld lets it droop
+        assert!(cantilever(0.0).abs() < 0.01);
+        assert!(cantilever(1.0) < -0.1);
+    }
+}
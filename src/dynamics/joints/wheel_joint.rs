@@ -0,0 +1,352 @@
+use std::f64::consts::PI;
+use common::{Rotation, Vec2d};
+use dynamics::BodyHandle;
+use dynamics::joints::joint::{apply_impulses, apply_position_impulses};
+use dynamics::solver_body::{SolverBody, TimeStep};
+use util;
+
+/// WheelJoint keeps the second body on an axis fixed in the first body,
+/// suspended by a spring along that axis and free to rotate, like the
+/// wheel of a car on its suspension. The rotation may be driven by a motor.
+///
+/// # Remarks
+///
+/// A frequency of 0 disables the spring, leaving the wheel free
+/// to slide along the axis
+#[derive(Debug, Clone)]
+pub struct WheelJoint {
+    body_a: BodyHandle,
+    body_b: BodyHandle,
+    local_anchor_a: Vec2d,
+    local_anchor_b: Vec2d,
+    local_axis_a: Vec2d,
+    frequency: f64,
+    damping_ratio: f64,
+    collide_connected: bool,
+    motor_enabled: bool,
+    motor_speed: f64,
+    max_motor_torque: f64,
+
+    // solver state
+    impulse: f64,
+    spring_impulse: f64,
+    motor_impulse: f64,
+    a: usize,
+    b: usize,
+    ax: Vec2d,
+    ay: Vec2d,
+    sax: f64,
+    sbx: f64,
+    say: f64,
+    sby: f64,
+    mass: f64,
+    spring_mass: f64,
+    motor_mass: f64,
+    gamma: f64,
+    bias: f64,
+}
+
+impl WheelJoint {
+    /// Creates a new wheel joint suspending `local_anchor_b` in the local
+    /// space of `body_b` along `local_axis_a` through `local_anchor_a`
+    /// in the local space of `body_a`. The spring oscillates with 2 Hz
+    /// at a damping ratio of 0.7 and the motor is disabled
+    pub fn new(body_a: BodyHandle,
+               body_b: BodyHandle,
+               local_anchor_a: Vec2d,
+               local_anchor_b: Vec2d,
+               local_axis_a: Vec2d)
+               -> WheelJoint {
+        WheelJoint {
+            body_a,
+            body_b,
+            local_anchor_a,
+            local_anchor_b,
+            local_axis_a: local_axis_a.normalize(),
+            frequency: 2.0,
+            damping_ratio: 0.7,
+            collide_connected: false,
+            motor_enabled: false,
+            motor_speed: 0.0,
+            max_motor_torque: 0.0,
+            impulse: 0.0,
+            spring_impulse: 0.0,
+            motor_impulse: 0.0,
+            a: 0,
+            b: 0,
+            ax: Vec2d::zero(),
+            ay: Vec2d::zero(),
+            sax: 0.0,
+            sbx: 0.0,
+            say: 0.0,
+            sby: 0.0,
+            mass: 0.0,
+            spring_mass: 0.0,
+            motor_mass: 0.0,
+            gamma: 0.0,
+            bias: 0.0,
+        }
+    }
+
+    /// Returns the handle of the first body
+    pub fn body_a(&self) -> BodyHandle {
+        self.body_a
+    }
+
+    /// Returns the handle of the second body
+    pub fn body_b(&self) -> BodyHandle {
+        self.body_b
+    }
+
+    /// Returns the anchor in the local space of the first body
+    pub fn local_anchor_a(&self) -> &Vec2d {
+        &self.local_anchor_a
+    }
+
+    /// Returns the anchor in the local space of the second body
+    pub fn local_anchor_b(&self) -> &Vec2d {
+        &self.local_anchor_b
+    }
+
+    /// Returns the unit suspension axis in the local space of the first body
+    pub fn local_axis_a(&self) -> &Vec2d {
+        &self.local_axis_a
+    }
+
+    /// Returns the suspension frequency in Hertz
+    pub fn frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// Sets the suspension frequency in Hertz. Frequencies above
+    /// half the step rate are unstable
+    pub fn set_frequency(&mut self, frequency: f64) {
+        debug_assert!(frequency >= 0.0);
+        self.frequency = frequency;
+    }
+
+    /// Returns the damping ratio of the suspension
+    pub fn damping_ratio(&self) -> f64 {
+        self.damping_ratio
+    }
+
+    /// Sets the damping ratio of the suspension. 0 doesn't damp
+    /// at all, 1 damps critically
+    pub fn set_damping_ratio(&mut self, ratio: f64) {
+        debug_assert!(ratio >= 0.0);
+        self.damping_ratio = ratio;
+    }
+
+    /// Returns if the fixtures of the jointed bodies collide with each other
+    pub fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
+    /// Sets if the fixtures of the jointed bodies collide with each other
+    pub fn set_collide_connected(&mut self, collide: bool) {
+        self.collide_connected = collide;
+    }
+
+    /// Returns if the motor is enabled
+    pub fn is_motor_enabled(&self) -> bool {
+        self.motor_enabled
+    }
+
+    /// Enables or disables the motor
+    pub fn enable_motor(&mut self, enabled: bool) {
+        self.motor_enabled = enabled;
+    }
+
+    /// Returns the target speed of the motor in radians per second
+    pub fn motor_speed(&self) -> f64 {
+        self.motor_speed
+    }
+
+    /// Sets the target speed of the motor in radians per second
+    pub fn set_motor_speed(&mut self, speed: f64) {
+        self.motor_speed = speed;
+    }
+
+    /// Returns the maximum torque of the motor
+    pub fn max_motor_torque(&self) -> f64 {
+        self.max_motor_torque
+    }
+
+    /// Sets the maximum torque the motor may apply to reach its speed
+    pub fn set_max_motor_torque(&mut self, torque: f64) {
+        debug_assert!(torque >= 0.0);
+        self.max_motor_torque = torque;
+    }
+
+    /// Returns the force the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_force(&self, inv_dt: f64) -> Vec2d {
+        (self.ay * self.impulse + self.ax * self.spring_impulse) * inv_dt
+    }
+
+    /// Returns the torque the joint applied to the second body over
+    /// the last step, given the inverse of the step length
+    pub fn reaction_torque(&self, inv_dt: f64) -> f64 {
+        inv_dt * self.motor_impulse
+    }
+
+    pub(crate) fn init_velocity(&mut self,
+                                bodies: &mut [SolverBody],
+                                index: &dyn Fn(BodyHandle) -> usize,
+                                step: &TimeStep) {
+        self.a = index(self.body_a);
+        self.b = index(self.body_b);
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+        let (ma, mb, ia, ib) = (a.inv_mass, b.inv_mass, a.inv_inertia, b.inv_inertia);
+
+        let ra = a.offset(self.local_anchor_a);
+        let rb = b.offset(self.local_anchor_b);
+        let d = (b.center + rb) - (a.center + ra);
+
+        // keeps the wheel on the axis
+        self.ax = self.local_axis_a.rotate(&Rotation::new(a.angle));
+        self.ay = self.ax.perp();
+        self.say = (d + ra).cross(self.ay);
+        self.sby = rb.cross(self.ay);
+        let mass = ma + mb + ia * self.say * self.say + ib * self.sby * self.sby;
+        self.mass = if mass > 0.0 { 1.0 / mass } else { 0.0 };
+
+        // suspends the wheel along the axis
+        self.sax = (d + ra).cross(self.ax);
+        self.sbx = rb.cross(self.ax);
+        self.spring_mass = 0.0;
+        self.gamma = 0.0;
+        self.bias = 0.0;
+        let inv_mass = ma + mb + ia * self.sax * self.sax + ib * self.sbx * self.sbx;
+        if self.frequency > 0.0 && inv_mass > 0.0 {
+            let mass = 1.0 / inv_mass;
+            let omega = 2.0 * PI * self.frequency;
+            let damping = 2.0 * mass * self.damping_ratio * omega;
+            let stiffness = mass * omega * omega;
+
+            let h = step.dt;
+            self.gamma = h * (damping + h * stiffness);
+            self.gamma = if self.gamma > 0.0 { 1.0 / self.gamma } else { 0.0 };
+            self.bias = (d * self.ax) * h * stiffness * self.gamma;
+            self.spring_mass = 1.0 / (inv_mass + self.gamma);
+        } else {
+            self.spring_impulse = 0.0;
+        }
+
+        // drives the wheel
+        if self.motor_enabled && ia + ib > 0.0 {
+            self.motor_mass = 1.0 / (ia + ib);
+        } else {
+            self.motor_mass = 0.0;
+            self.motor_impulse = 0.0;
+        }
+
+        if step.warm_starting {
+            let p = self.ay * self.impulse + self.ax * self.spring_impulse;
+            let la = self.impulse * self.say + self.spring_impulse * self.sax + self.motor_impulse;
+            let lb = self.impulse * self.sby + self.spring_impulse * self.sbx + self.motor_impulse;
+            apply_impulses(&mut a, &mut b, p, la, lb);
+        } else {
+            self.impulse = 0.0;
+            self.spring_impulse = 0.0;
+            self.motor_impulse = 0.0;
+        }
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_velocity(&mut self, bodies: &mut [SolverBody], step: &TimeStep) {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        let cdot = self.ax * (b.v - a.v) + self.sbx * b.w - self.sax * a.w;
+        let impulse = -self.spring_mass * (cdot + self.bias + self.gamma * self.spring_impulse);
+        self.spring_impulse += impulse;
+        apply_impulses(&mut a, &mut b, self.ax * impulse, impulse * self.sax, impulse * self.sbx);
+
+        if self.motor_mass > 0.0 {
+            let cdot = b.w - a.w - self.motor_speed;
+            let max = step.dt * self.max_motor_torque;
+            let old = self.motor_impulse;
+            self.motor_impulse = (old - self.motor_mass * cdot).clamp(-max, max);
+            let impulse = self.motor_impulse - old;
+            apply_impulses(&mut a, &mut b, Vec2d::zero(), impulse, impulse);
+        }
+
+        let cdot = self.ay * (b.v - a.v) + self.sby * b.w - self.say * a.w;
+        let impulse = -self.mass * cdot;
+        self.impulse += impulse;
+        apply_impulses(&mut a, &mut b, self.ay * impulse, impulse * self.say, impulse * self.sby);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+    }
+
+    pub(crate) fn solve_position(&mut self, bodies: &mut [SolverBody]) -> bool {
+        let (mut a, mut b) = (bodies[self.a], bodies[self.b]);
+
+        let ra = a.offset(self.local_anchor_a);
+        let rb = b.offset(self.local_anchor_b);
+        let d = (b.center + rb) - (a.center + ra);
+
+        let ay = self.local_axis_a.rotate(&Rotation::new(a.angle)).perp();
+        let say = (d + ra).cross(ay);
+        let sby = rb.cross(ay);
+        let c = d * ay;
+        let k = a.inv_mass + b.inv_mass + a.inv_inertia * say * say + b.inv_inertia * sby * sby;
+        let impulse = if k != 0.0 { -c / k } else { 0.0 };
+        apply_position_impulses(&mut a, &mut b, ay * impulse, impulse * say, impulse * sby);
+
+        bodies[self.a] = a;
+        bodies[self.b] = b;
+        c.abs() <= util::LINEAR_SLOP
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+    use collision::shapes::{Circle, Convex};
+    use common::{Rotation, Transform, Vec2d};
+    use dynamics::{BodyType, Fixture, RigidBody, World};
+    use dynamics::joints::WheelJoint;
+
+    #[test]
+    fn test_wheel_joint() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let chassis_shape = Convex::new(&[Vec2d::new(-1.0, -0.1),
+                                          Vec2d::new(1.0, -0.1),
+                                          Vec2d::new(1.0, 0.1),
+                                          Vec2d::new(-1.0, 0.1)])
+                                .unwrap();
+        let mut chassis = RigidBody::new(Transform::new(Vec2d::new(0.0, 1.0), Rotation::identity()));
+        chassis.add_fixture(Fixture::new(chassis_shape, 0.0));
+        chassis.set_body_type(BodyType::Static);
+        let chassis = world.add_body(chassis);
+        let mut wheel = RigidBody::new(Transform::new(Vec2d::new(0.5, 0.0), Rotation::identity()));
+        wheel.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.3).unwrap(), 1.0));
+        let wheel = world.add_body(wheel);
+
+        let mut joint = WheelJoint::new(chassis,
+                                        wheel,
+                                        Vec2d::new(0.5, -1.0),
+                                        Vec2d::zero(),
+                                        Vec2d::new(0.0, 1.0));
+        joint.set_damping_ratio(1.0);
+        joint.enable_motor(true);
+        joint.set_motor_speed(-3.0);
+        joint.set_max_motor_torque(10.0);
+        world.add_joint(joint).unwrap();
+
+        for _ in 0..180 {
+            world.step(world.time_step());
+            assert!((world.body(wheel).unwrap().position().x - 0.5).abs() < 0.01);
+        }
+
+        // the wheel settles where the spring carries its weight and spins at the motor speed
+        let body = world.body(wheel).unwrap();
+        let omega = 2.0 * PI * 2.0;
+        assert!((body.position().y + 10.0 / (omega * omega)).abs() < 0.01);
+        assert!(body.linear_velocity().len() < 0.01);
+        assert!((body.angular_velocity() + 3.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,36 @@
+use std::fmt;
+use collision::Manifold;
+use dynamics::Contact;
+
+/// ContactListener is told about the contacts of a `World` while the
+/// world steps. The contact of a sensor is never reported.
+///
+/// # Remarks
+///
+/// The listener can't access the world while it steps. Changes to the
+/// world in response to contacts should be recorded and made after the
+/// step, or be based on the contact events drained from the world
+pub trait ContactListener {
+    /// Called when the fixtures of the contact start touching
+    fn begin_contact(&mut self, _contact: &Contact) {}
+
+    /// Called when the fixtures of the contact stop touching. The
+    /// contact still holds the manifold of the last step they touched
+    fn end_contact(&mut self, _contact: &Contact) {}
+
+    /// Called every step for every touching contact of an awake body
+    /// before the contact is solved, with the manifold of the previous
+    /// step. The contact may be disabled for the step or have its
+    /// friction, restitution and tangent speed changed
+    fn pre_solve(&mut self, _contact: &mut Contact, _old_manifold: Option<&Manifold>) {}
+
+    /// Called every step for every solved contact with the
+    /// normal and tangent impulses applied to the contact
+    fn post_solve(&mut self, _contact: &Contact) {}
+}
+
+impl fmt::Debug for dyn ContactListener + Send {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ContactListener")
+    }
+}
@@ -0,0 +1,21 @@
+pub use self::body::{BodyType, RigidBody};
+pub use self::contact::Contact;
+pub use self::filter::{ContactFilter, Filter};
+pub use self::fixture::{DEFAULT_FRICTION, Fixture};
+pub use self::listener::ContactListener;
+pub use self::world::{BodyHandle, ContactEvent, DEFAULT_ANGULAR_SLEEP_TOLERANCE,
+                      DEFAULT_LINEAR_SLEEP_TOLERANCE, DEFAULT_MAX_SUB_STEPS, DEFAULT_POSITION_ITERATIONS,
+                      DEFAULT_TIME_STEP, DEFAULT_TIME_TO_SLEEP, DEFAULT_VELOCITY_ITERATIONS, JointEvent,
+                      JointHandle, SensorEvent, World};
+
+pub mod joints;
+
+mod body;
+mod contact;
+mod contact_solver;
+mod filter;
+mod fixture;
+mod island;
+mod listener;
+mod solver_body;
+mod world;
\ No newline at end of file
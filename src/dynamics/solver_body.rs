@@ -0,0 +1,80 @@
+use common::{Rotation, Transform, Vec2d};
+use dynamics::RigidBody;
+
+/// The time step the solvers work with
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TimeStep {
+    pub dt: f64,
+    pub inv_dt: f64,
+    pub warm_starting: bool,
+}
+
+/// The state of a body the contact and joint solvers work on
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SolverBody {
+    pub local_center: Vec2d,
+    pub center: Vec2d,
+    pub angle: f64,
+    pub v: Vec2d,
+    pub w: f64,
+    pub inv_mass: f64,
+    pub inv_inertia: f64,
+}
+
+impl SolverBody {
+    /// Copies the state of the body
+    pub fn new(body: &RigidBody) -> SolverBody {
+        SolverBody {
+            local_center: *body.local_center(),
+            center: body.world_center(),
+            angle: body.rotation().angle(),
+            v: *body.linear_velocity(),
+            w: body.angular_velocity(),
+            inv_mass: body.inv_mass(),
+            inv_inertia: body.inv_inertia(),
+        }
+    }
+
+    /// Returns the transform of the body origin
+    pub fn transform(&self) -> Transform {
+        let rotation = Rotation::new(self.angle);
+        Transform::new(self.center - self.local_center.rotate(&rotation), rotation)
+    }
+
+    /// Integrates the position of the body over `dt`
+    /// by rotating about the center of mass
+    pub fn integrate(&mut self, dt: f64) {
+        self.center += self.v * dt;
+        self.angle += self.w * dt;
+    }
+
+    /// Writes the solved transform and velocity back to the body
+    pub fn store(&self, body: &mut RigidBody) {
+        body.set_solved_state(self.transform(), self.v, self.w);
+    }
+
+    /// Returns the offset of the point given in the local
+    /// space of the body from the center of mass
+    pub fn offset(&self, local_point: Vec2d) -> Vec2d {
+        (local_point - self.local_center).rotate(&Rotation::new(self.angle))
+    }
+
+    /// Applies the impulse at the offset `r` from the center of mass
+    pub fn apply_impulse(&mut self, impulse: Vec2d, r: Vec2d) {
+        self.v += impulse * self.inv_mass;
+        self.w += self.inv_inertia * r.cross(impulse);
+    }
+
+    /// Moves the body as if the impulse was applied at the offset
+    /// `r` from the center of mass for a unit of time
+    pub fn apply_position_impulse(&mut self, impulse: Vec2d, r: Vec2d) {
+        self.center += impulse * self.inv_mass;
+        self.angle += self.inv_inertia * r.cross(impulse);
+    }
+
+    /// Returns the velocity of the point at the offset
+    /// `r` from the center of mass
+    pub fn velocity_at(&self, r: Vec2d) -> Vec2d {
+        self.v + r.perp() * self.w
+    }
+}
@@ -0,0 +1,1975 @@
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::vec::{self, Vec};
+use collision::{distance, time_of_impact, CollidesWith, ContactsWith, HasAabb, Sweep, ToiState};
+use collision::broad_phase::{BroadPhase, DynamicTree, ProxyId};
+use common::{Transform, Vec2d};
+use dynamics::{BodyType, Contact, ContactFilter, ContactListener, Fixture, RigidBody};
+use dynamics::contact_solver::ContactSolver;
+use dynamics::island::{self, Node};
+use dynamics::joints::Joint;
+use dynamics::solver_body::{SolverBody, TimeStep};
+use util;
+
+/// The default fixed time step of a `World` in seconds
+pub const DEFAULT_TIME_STEP: f64 = 1.0 / 60.0;
+
+/// The default maximum number of fixed steps a single
+/// call to `World::step` may take
+pub const DEFAULT_MAX_SUB_STEPS: usize = 8;
+
+/// The default number of velocity iterations of the constraint solver
+pub const DEFAULT_VELOCITY_ITERATIONS: usize = 8;
+
+/// The default number of position iterations of the constraint solver
+pub const DEFAULT_POSITION_ITERATIONS: usize = 3;
+
+/// The default linear velocity in meters per second
+/// below which a body is considered to be resting
+pub const DEFAULT_LINEAR_SLEEP_TOLERANCE: f64 = 0.01;
+
+/// The default angular velocity in radians per second
+/// below which a body is considered to be resting
+pub const DEFAULT_ANGULAR_SLEEP_TOLERANCE: f64 = 2.0 / 180.0 * ::std::f64::consts::PI;
+
+/// The default time in seconds every body of an island
+/// must rest before the island falls asleep
+pub const DEFAULT_TIME_TO_SLEEP: f64 = 0.5;
+
+/// BodyHandle identifies a body owned by a `World`. Handles of
+/// removed bodies are never reused for new bodies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BodyHandle {
+    index: usize,
+    generation: usize,
+}
+
+/// A slot in the body storage of a world. The generation
+/// is bumped every time the slot is vacated. The body type
+/// is the one the proxies of the body were last paired with
+#[derive(Debug, Clone)]
+struct BodySlot {
+    generation: usize,
+    body: Option<RigidBody>,
+    body_type: BodyType,
+}
+
+/// JointHandle identifies a joint owned by a `World`. Handles of
+/// removed joints are never reused for new joints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JointHandle {
+    index: usize,
+    generation: usize,
+}
+
+/// JointEvent reports what happened to a joint during a step or
+/// because a joint it couples was removed
+#[derive(Debug, Clone)]
+pub enum JointEvent {
+    /// The joint was strained beyond its break force or torque
+    /// and has been removed from the world
+    Broken {
+        /// The handle the joint had in the world
+        handle: JointHandle,
+        /// The removed joint
+        joint: Joint,
+    },
+    /// The gear joint was removed from the world along with
+    /// a joint it couples, which either broke or was removed
+    Removed {
+        /// The handle the joint had in the world
+        handle: JointHandle,
+        /// The removed joint
+        joint: Joint,
+    },
+}
+
+/// ContactEvent reports a contact starting or stopping to touch during
+/// a step or because one of its bodies was removed. Contacts of sensors
+/// aren't reported
+#[derive(Debug, Clone)]
+pub enum ContactEvent {
+    /// The fixtures of the contact started touching. The contact holds
+    /// the manifold and the impulses of the step they started touching
+    Begin(Contact),
+    /// The fixtures of the contact stopped touching. The contact holds
+    /// the manifold and the impulses of the last step they touched
+    End(Contact),
+}
+
+/// SensorEvent reports a fixture starting or stopping to overlap a
+/// sensor fixture during a step. Fixtures are given by the handle of
+/// their body and their index on the body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorEvent {
+    /// The fixture started overlapping the sensor
+    Begin {
+        /// The sensor fixture
+        sensor: (BodyHandle, usize),
+        /// The fixture overlapping the sensor
+        other: (BodyHandle, usize),
+    },
+    /// The fixture stopped overlapping the sensor
+    End {
+        /// The sensor fixture
+        sensor: (BodyHandle, usize),
+        /// The fixture that overlapped the sensor
+        other: (BodyHandle, usize),
+    },
+}
+
+/// A slot in the joint storage of a world along with the strain
+/// the joint breaks under. The generation is bumped every time the
+/// slot is vacated
+#[derive(Debug, Clone)]
+struct JointSlot {
+    generation: usize,
+    joint: Option<Joint>,
+    break_force: Option<f64>,
+    break_torque: Option<f64>,
+}
+
+/// Events recorded by a world until they're drained. Events
+/// recorded before the last step began are stale and discarded
+/// when the next step begins
+#[derive(Debug)]
+struct EventQueue<T> {
+    events: Vec<T>,
+    stale: usize,
+}
+
+impl<T> EventQueue<T> {
+    fn new() -> EventQueue<T> {
+        EventQueue {
+            events: Vec::new(),
+            stale: 0,
+        }
+    }
+
+    fn push(&mut self, event: T) {
+        self.events.push(event);
+    }
+
+    fn drain(&mut self) -> vec::Drain<'_, T> {
+        self.stale = 0;
+        self.events.drain(..)
+    }
+
+    /// Discards the stale events, after which
+    /// the remaining events become stale
+    fn discard_stale(&mut self) {
+        self.events.drain(..self.stale);
+        self.stale = self.events.len();
+    }
+}
+
+/// World owns a collection of bodies and advances them
+/// through time in fixed steps. Fixtures of the bodies are
+/// tracked by a broad phase to find potentially touching pairs,
+/// which is a `DynamicTree` unless specified otherwise
+#[derive(Debug)]
+pub struct World<B: BroadPhase = DynamicTree> {
+    gravity: Vec2d,
+    time_step: f64,
+    max_sub_steps: usize,
+    velocity_iterations: usize,
+    position_iterations: usize,
+    warm_starting: bool,
+    sleeping_allowed: bool,
+    linear_sleep_tolerance: f64,
+    angular_sleep_tolerance: f64,
+    time_to_sleep: f64,
+    accumulator: f64,
+    slots: Vec<BodySlot>,
+    free_slots: Vec<usize>,
+    joint_slots: Vec<JointSlot>,
+    free_joint_slots: Vec<usize>,
+    broad_phase: B,
+    proxy_owners: HashMap<ProxyId, (BodyHandle, usize)>,
+    pairs: Vec<(ProxyId, ProxyId)>,
+    contacts: Vec<Contact>,
+    contact_filter: Option<Box<dyn ContactFilter + Send>>,
+    contact_listener: Option<Arc<Mutex<dyn ContactListener + Send>>>,
+    sensor_overlaps: HashSet<(ProxyId, ProxyId)>,
+    joint_events: EventQueue<JointEvent>,
+    sensor_events: EventQueue<SensorEvent>,
+    contact_events: EventQueue<ContactEvent>,
+}
+
+impl World {
+    /// Creates a new empty world with the given gravity
+    pub fn new(gravity: Vec2d) -> World {
+        World::with_broad_phase(gravity, DynamicTree::new())
+    }
+}
+
+impl<B: BroadPhase> World<B> {
+    /// Creates a new empty world with the given gravity
+    /// using `broad_phase` to find potentially touching pairs
+    pub fn with_broad_phase(gravity: Vec2d, broad_phase: B) -> World<B> {
+        World {
+            gravity,
+            time_step: DEFAULT_TIME_STEP,
+            max_sub_steps: DEFAULT_MAX_SUB_STEPS,
+            velocity_iterations: DEFAULT_VELOCITY_ITERATIONS,
+            position_iterations: DEFAULT_POSITION_ITERATIONS,
+            warm_starting: true,
+            sleeping_allowed: true,
+            linear_sleep_tolerance: DEFAULT_LINEAR_SLEEP_TOLERANCE,
+            angular_sleep_tolerance: DEFAULT_ANGULAR_SLEEP_TOLERANCE,
+            time_to_sleep: DEFAULT_TIME_TO_SLEEP,
+            accumulator: 0.0,
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            joint_slots: Vec::new(),
+            free_joint_slots: Vec::new(),
+            broad_phase,
+            proxy_owners: HashMap::new(),
+            pairs: Vec::new(),
+            contacts: Vec::new(),
+            contact_filter: None,
+            contact_listener: None,
+            sensor_overlaps: HashSet::new(),
+            joint_events: EventQueue::new(),
+            sensor_events: EventQueue::new(),
+            contact_events: EventQueue::new(),
+        }
+    }
+
+    /// Returns the gravity of the world
+    pub fn gravity(&self) -> &Vec2d {
+        &self.gravity
+    }
+
+    /// Sets the gravity of the world
+    pub fn set_gravity(&mut self, gravity: Vec2d) {
+        self.gravity = gravity;
+    }
+
+    /// Returns the fixed time step in seconds
+    pub fn time_step(&self) -> f64 {
+        self.time_step
+    }
+
+    /// Sets the fixed time step in seconds. Fails
+    /// unless the time step is positive and finite
+    pub fn set_time_step(&mut self, time_step: f64) -> Result<(), ()> {
+        if !(time_step > 0.0 && time_step.is_finite()) {
+            return Err(());
+        }
+        self.time_step = time_step;
+        Ok(())
+    }
+
+    /// Returns the maximum number of fixed steps a single call to `step` may take
+    pub fn max_sub_steps(&self) -> usize {
+        self.max_sub_steps
+    }
+
+    /// Sets the maximum number of fixed steps a single call to `step` may take.
+    /// Time beyond the maximum is dropped so a slow frame can't snowball
+    pub fn set_max_sub_steps(&mut self, max_sub_steps: usize) {
+        self.max_sub_steps = max_sub_steps;
+    }
+
+    /// Returns the number of velocity iterations of the constraint solver
+    pub fn velocity_iterations(&self) -> usize {
+        self.velocity_iterations
+    }
+
+    /// Sets the number of velocity iterations of the constraint solver.
+    /// More iterations give stiffer stacks and joints and more accurate
+    /// friction at a higher cost
+    pub fn set_velocity_iterations(&mut self, iterations: usize) {
+        self.velocity_iterations = iterations;
+    }
+
+    /// Returns the maximum number of position iterations of the constraint solver
+    pub fn position_iterations(&self) -> usize {
+        self.position_iterations
+    }
+
+    /// Sets the maximum number of position iterations of the constraint solver.
+    /// Position iterations stop early once no contact overlaps noticeably
+    /// and every joint is within the slop
+    pub fn set_position_iterations(&mut self, iterations: usize) {
+        self.position_iterations = iterations;
+    }
+
+    /// Returns if the constraint solver is warm started
+    pub fn warm_starting(&self) -> bool {
+        self.warm_starting
+    }
+
+    /// Sets if the constraint solver is warm started with the impulses of
+    /// the previous step, which makes stacks settle much faster. Only
+    /// worth disabling for testing
+    pub fn set_warm_starting(&mut self, warm_starting: bool) {
+        self.warm_starting = warm_starting;
+    }
+
+    /// Returns if islands of resting bodies fall asleep
+    pub fn is_sleeping_allowed(&self) -> bool {
+        self.sleeping_allowed
+    }
+
+    /// Sets if islands of resting bodies fall asleep. Sleeping bodies
+    /// cost next to nothing until they're woken by a force, a change of
+    /// transform or velocity, or a touching body that is awake.
+    /// Disallowing sleep wakes every body
+    pub fn set_sleeping_allowed(&mut self, allowed: bool) {
+        self.sleeping_allowed = allowed;
+        if !allowed {
+            for body in self.slots.iter_mut().filter_map(|slot| slot.body.as_mut()) {
+                body.set_awake(true);
+            }
+        }
+    }
+
+    /// Returns the linear velocity below which a body is considered to be resting
+    pub fn linear_sleep_tolerance(&self) -> f64 {
+        self.linear_sleep_tolerance
+    }
+
+    /// Sets the linear velocity in meters per second
+    /// below which a body is considered to be resting
+    pub fn set_linear_sleep_tolerance(&mut self, tolerance: f64) {
+        debug_assert!(tolerance >= 0.0);
+        self.linear_sleep_tolerance = tolerance;
+    }
+
+    /// Returns the angular velocity below which a body is considered to be resting
+    pub fn angular_sleep_tolerance(&self) -> f64 {
+        self.angular_sleep_tolerance
+    }
+
+    /// Sets the angular velocity in radians per second
+    /// below which a body is considered to be resting
+    pub fn set_angular_sleep_tolerance(&mut self, tolerance: f64) {
+        debug_assert!(tolerance >= 0.0);
+        self.angular_sleep_tolerance = tolerance;
+    }
+
+    /// Returns the time every body of an island must rest before the island falls asleep
+    pub fn time_to_sleep(&self) -> f64 {
+        self.time_to_sleep
+    }
+
+    /// Sets the time in seconds every body of an island
+    /// must rest before the island falls asleep
+    pub fn set_time_to_sleep(&mut self, time: f64) {
+        debug_assert!(time >= 0.0);
+        self.time_to_sleep = time;
+    }
+
+    /// Sets the contact filter deciding which fixtures collide on top
+    /// of their `Filter`s, replacing the previous contact filter
+    pub fn set_contact_filter<F: ContactFilter + Send + 'static>(&mut self, filter: F) {
+        self.contact_filter = Some(Box::new(filter));
+    }
+
+    /// Removes the contact filter, leaving it to the `Filter`s
+    /// of the fixtures which fixtures collide
+    pub fn clear_contact_filter(&mut self) {
+        self.contact_filter = None;
+    }
+
+    /// Sets the listener told about the contacts of the world while it
+    /// steps, replacing the previous listener. The listener is shared
+    /// with the caller, so whatever it records can be read after a step.
+    /// The world locks the listener whenever it calls it, so holding the
+    /// lock while the world steps or removes a body deadlocks
+    pub fn set_contact_listener<L: ContactListener + Send + 'static>(&mut self, listener: Arc<Mutex<L>>) {
+        self.contact_listener = Some(listener);
+    }
+
+    /// Removes the contact listener
+    pub fn clear_contact_listener(&mut self) {
+        self.contact_listener = None;
+    }
+
+    /// Returns how far the world is into the next fixed step as a
+    /// fraction in `[0, 1)`. Useful for interpolating rendered transforms
+    pub fn interpolation_alpha(&self) -> f64 {
+        self.accumulator / self.time_step
+    }
+
+    /// Returns the broad phase of the world
+    pub fn broad_phase(&self) -> &B {
+        &self.broad_phase
+    }
+
+    /// Adds a body to the world, returning the handle to the body
+    pub fn add_body(&mut self, body: RigidBody) -> BodyHandle {
+        let handle = self.insert_slot(body);
+        self.create_proxies(handle);
+        handle
+    }
+
+    /// Stores the body in a free slot, returning the handle to the slot
+    fn insert_slot(&mut self, body: RigidBody) -> BodyHandle {
+        match self.free_slots.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.body_type = body.body_type();
+                slot.body = Some(body);
+                BodyHandle {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(BodySlot {
+                    generation: 0,
+                    body_type: body.body_type(),
+                    body: Some(body),
+                });
+                BodyHandle {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+
+    /// Removes the body from the world, returning the body or `None`
+    /// if the handle does not refer to a body in this world. Contacts
+    /// and joints involving the body are dropped, waking the bodies
+    /// the body touched or was jointed to. Touching contacts and
+    /// overlaps with sensors end, recording an event for each
+    pub fn remove_body(&mut self, handle: BodyHandle) -> Option<RigidBody> {
+        self.body(handle)?;
+
+        let touching: Vec<BodyHandle> = self.contacts
+                                            .iter()
+                                            .filter(|c| c.is_touching())
+                                            .filter_map(|c| if c.body_a() == handle {
+                                                Some(c.body_b())
+                                            } else if c.body_b() == handle {
+                                                Some(c.body_a())
+                                            } else {
+                                                None
+                                            })
+                                            .collect();
+        self.wake(&touching);
+
+        let attached: Vec<JointHandle> = self.joints()
+                                             .filter(|&(_, j)| j.body_a() == handle || j.body_b() == handle)
+                                             .map(|(joint, _)| joint)
+                                             .collect();
+        for joint in attached {
+            self.remove_joint(joint);
+        }
+
+        let owners = &self.proxy_owners;
+        let mut left: Vec<(ProxyId, ProxyId)> = self.sensor_overlaps
+                                                    .iter()
+                                                    .filter(|&(a, b)| owners[a].0 == handle || owners[b].0 == handle)
+                                                    .cloned()
+                                                    .collect();
+        left.sort();
+        for pair in left {
+            let (sensor, other) = self.sensor_pair(pair);
+            self.sensor_events.push(SensorEvent::End { sensor, other });
+            self.sensor_overlaps.remove(&pair);
+        }
+
+        let ended: Vec<Contact> = self.contacts
+                                      .iter()
+                                      .filter(|c| c.is_touching() && (c.body_a() == handle || c.body_b() == handle))
+                                      .cloned()
+                                      .collect();
+        for contact in &ended {
+            self.end_contact(contact);
+        }
+        self.contacts.retain(|c| c.body_a() != handle && c.body_b() != handle);
+        let owners = &self.proxy_owners;
+        self.pairs.retain(|&(a, b)| owners[&a].0 != handle && owners[&b].0 != handle);
+
+        let slot = &mut self.slots[handle.index];
+        slot.generation += 1;
+        self.free_slots.push(handle.index);
+
+        let mut body = slot.body.take().unwrap();
+        for fixture in body.fixtures_mut() {
+            if let Some(proxy) = fixture.proxy() {
+                self.broad_phase.destroy_proxy(proxy);
+                self.proxy_owners.remove(&proxy);
+                fixture.set_proxy(None);
+            }
+        }
+        Some(body)
+    }
+
+    /// Returns the body for the handle or `None` if the handle does
+    /// not refer to a body in this world
+    pub fn body(&self, handle: BodyHandle) -> Option<&RigidBody> {
+        self.slots
+            .get(handle.index)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.body.as_ref())
+    }
+
+    /// Returns the body for the handle mutably or `None` if the
+    /// handle does not refer to a body in this world
+    pub fn body_mut(&mut self, handle: BodyHandle) -> Option<&mut RigidBody> {
+        self.slots
+            .get_mut(handle.index)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.body.as_mut())
+    }
+
+    /// Returns an iterator over the handles and bodies of the world
+    pub fn bodies<'a>(&'a self) -> impl Iterator<Item = (BodyHandle, &'a RigidBody)> + 'a {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.body.as_ref().map(|body| {
+                (BodyHandle {
+                     index,
+                     generation: slot.generation,
+                 },
+                 body)
+            })
+        })
+    }
+
+    /// Returns the number of bodies in the world
+    pub fn body_count(&self) -> usize {
+        self.slots.len() - self.free_slots.len()
+    }
+
+    /// Adds a joint between two bodies of the world, returning the handle
+    /// to the joint or an error if either body is not part of the world
+    /// or both bodies are the same. Gear joints are also rejected if
+    /// either coupled joint is not part of the world. Both bodies are woken
+    pub fn add_joint<J: Into<Joint>>(&mut self, joint: J) -> Result<JointHandle, ()> {
+        let joint = joint.into();
+        if joint.body_a() == joint.body_b() || self.body(joint.body_a()).is_none() ||
+           self.body(joint.body_b()).is_none() {
+            return Err(());
+        }
+        if let Joint::Gear(ref gear) = joint {
+            if self.joint(gear.joint_a()).is_none() || self.joint(gear.joint_b()).is_none() {
+                return Err(());
+            }
+        }
+        self.wake(&[joint.body_a(), joint.body_b()]);
+
+        Ok(match self.free_joint_slots.pop() {
+            Some(index) => {
+                let slot = &mut self.joint_slots[index];
+                slot.joint = Some(joint);
+                slot.break_force = None;
+                slot.break_torque = None;
+                JointHandle {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.joint_slots.push(JointSlot {
+                    generation: 0,
+                    joint: Some(joint),
+                    break_force: None,
+                    break_torque: None,
+                });
+                JointHandle {
+                    index: self.joint_slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        })
+    }
+
+    /// Removes the joint from the world, returning the joint or `None`
+    /// if the handle does not refer to a joint in this world. Gear
+    /// joints coupling the joint are dropped, recording a removed
+    /// event for each, and the bodies of the joint are woken
+    pub fn remove_joint(&mut self, handle: JointHandle) -> Option<Joint> {
+        self.joint(handle)?;
+
+        let gears: Vec<JointHandle> = self.joints()
+                                          .filter(|&(_, j)| match *j {
+                                              Joint::Gear(ref g) => g.joint_a() == handle || g.joint_b() == handle,
+                                              _ => false,
+                                          })
+                                          .map(|(gear, _)| gear)
+                                          .collect();
+        for gear in gears {
+            if let Some(joint) = self.remove_joint(gear) {
+                self.joint_events.push(JointEvent::Removed { handle: gear, joint });
+            }
+        }
+
+        let slot = &mut self.joint_slots[handle.index];
+        slot.generation += 1;
+        self.free_joint_slots.push(handle.index);
+        let joint = slot.joint.take().unwrap();
+        self.wake(&[joint.body_a(), joint.body_b()]);
+        Some(joint)
+    }
+
+    /// Returns the joint for the handle or `None` if the handle does
+    /// not refer to a joint in this world
+    pub fn joint(&self, handle: JointHandle) -> Option<&Joint> {
+        self.joint_slot(handle).and_then(|slot| slot.joint.as_ref())
+    }
+
+    /// Returns the joint for the handle mutably or `None` if the handle
+    /// does not refer to a joint in this world. The bodies of the joint
+    /// are woken so they respond to changes of the joint
+    pub fn joint_mut(&mut self, handle: JointHandle) -> Option<&mut Joint> {
+        let bodies = self.joint(handle).map(|j| [j.body_a(), j.body_b()])?;
+        self.wake(&bodies);
+        self.joint_slot_mut(handle).and_then(|slot| slot.joint.as_mut())
+    }
+
+    /// Returns the reaction force above which the joint breaks or
+    /// `None` if the joint never breaks under force
+    pub fn joint_break_force(&self, handle: JointHandle) -> Option<f64> {
+        self.joint_slot(handle).and_then(|slot| slot.break_force)
+    }
+
+    /// Sets the reaction force above which the joint breaks, `None` for
+    /// a joint that never breaks under force. Fails if the handle does
+    /// not refer to a joint in this world
+    pub fn set_joint_break_force(&mut self, handle: JointHandle, force: Option<f64>) -> Result<(), ()> {
+        let slot = self.joint_slot_mut(handle).ok_or(())?;
+        slot.break_force = force;
+        Ok(())
+    }
+
+    /// Returns the reaction torque above which the joint breaks or
+    /// `None` if the joint never breaks under torque
+    pub fn joint_break_torque(&self, handle: JointHandle) -> Option<f64> {
+        self.joint_slot(handle).and_then(|slot| slot.break_torque)
+    }
+
+    /// Sets the reaction torque above which the joint breaks, `None` for
+    /// a joint that never breaks under torque. Fails if the handle does
+    /// not refer to a joint in this world
+    pub fn set_joint_break_torque(&mut self, handle: JointHandle, torque: Option<f64>) -> Result<(), ()> {
+        let slot = self.joint_slot_mut(handle).ok_or(())?;
+        slot.break_torque = torque;
+        Ok(())
+    }
+
+    /// Returns the occupied slot of the joint
+    fn joint_slot(&self, handle: JointHandle) -> Option<&JointSlot> {
+        self.joint_slots
+            .get(handle.index)
+            .filter(|slot| slot.generation == handle.generation && slot.joint.is_some())
+    }
+
+    /// Returns the occupied slot of the joint mutably
+    fn joint_slot_mut(&mut self, handle: JointHandle) -> Option<&mut JointSlot> {
+        self.joint_slots
+            .get_mut(handle.index)
+            .filter(|slot| slot.generation == handle.generation && slot.joint.is_some())
+    }
+
+    /// Returns an iterator over the handles and joints of the world
+    pub fn joints<'a>(&'a self) -> impl Iterator<Item = (JointHandle, &'a Joint)> + 'a {
+        self.joint_slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.joint.as_ref().map(|joint| {
+                (JointHandle {
+                     index,
+                     generation: slot.generation,
+                 },
+                 joint)
+            })
+        })
+    }
+
+    /// Returns the number of joints in the world
+    pub fn joint_count(&self) -> usize {
+        self.joint_slots.len() - self.free_joint_slots.len()
+    }
+
+    /// Returns the contacts of all fixture pairs whose `Aabb`s overlapped
+    /// during the last fixed step, touching or not
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts
+    }
+
+    /// Removes and returns the joint events recorded since they were
+    /// last drained. Events still undrained by the second call to `step`
+    /// after they were recorded are discarded, so drain after every step
+    pub fn drain_joint_events<'a>(&'a mut self) -> impl Iterator<Item = JointEvent> + 'a {
+        self.joint_events.drain()
+    }
+
+    /// Removes and returns the contact events recorded since they were
+    /// last drained. Events still undrained by the second call to `step`
+    /// after they were recorded are discarded, so drain after every step
+    pub fn drain_contact_events<'a>(&'a mut self) -> impl Iterator<Item = ContactEvent> + 'a {
+        self.contact_events.drain()
+    }
+
+    /// Removes and returns the sensor events recorded since they were
+    /// last drained. Events still undrained by the second call to `step`
+    /// after they were recorded are discarded, so drain after every step
+    pub fn drain_sensor_events<'a>(&'a mut self) -> impl Iterator<Item = SensorEvent> + 'a {
+        self.sensor_events.drain()
+    }
+
+    /// Advances the world by `dt` seconds in as many fixed steps as fit
+    /// into the elapsed time, returning the number of fixed steps taken.
+    /// Left over time is carried into the next call. Events recorded
+    /// before the last call that haven't been drained are discarded so
+    /// they don't pile up in a world whose events are never drained
+    pub fn step(&mut self, dt: f64) -> usize {
+        self.joint_events.discard_stale();
+        self.sensor_events.discard_stale();
+        self.contact_events.discard_stale();
+        self.accumulator += dt;
+        let mut steps = 0;
+        while self.accumulator >= self.time_step && steps < self.max_sub_steps {
+            let time_step = self.time_step;
+            self.fixed_step(time_step);
+            self.accumulator -= time_step;
+            steps += 1;
+        }
+        if steps == self.max_sub_steps {
+            self.accumulator %= self.time_step;
+        }
+        steps
+    }
+
+    /// Advances the world by exactly one step of `dt` seconds.
+    /// Contacts are found at the current transforms, after which
+    /// bodies are integrated with semi-implicit euler while resolving
+    /// the contacts and bullets are swept to their first impact
+    fn fixed_step(&mut self, dt: f64) {
+        self.synchronize(dt);
+        let began = self.collide();
+
+        let starts: Vec<Option<Transform>> = self.slots
+                                                 .iter()
+                                                 .map(|slot| slot.body.as_ref().map(|b| *b.transform()))
+                                                 .collect();
+        self.solve(dt);
+        for i in began {
+            self.contact_events.push(ContactEvent::Begin(self.contacts[i].clone()));
+        }
+        self.break_joints(dt);
+        self.solve_toi(&starts);
+    }
+
+    /// Splits the awake bodies into islands, wakes the sleeping bodies
+    /// pulled into them and integrates the velocities of every awake
+    /// body over `dt`. Then solves the velocities of the joints and
+    /// touching contacts of every island, integrates the positions of
+    /// the bodies and pushes apart bodies that still overlap or drifted
+    /// out of their joints. Islands whose bodies all rested long enough
+    /// are put to sleep
+    fn solve(&mut self, dt: f64) {
+        let nodes: Vec<Node> = self.slots
+                                   .iter()
+                                   .map(|slot| match slot.body {
+                                       Some(ref body) if is_static(body) => Node::Static,
+                                       Some(ref body) if body.is_awake() => Node::Awake,
+                                       Some(_) => Node::Asleep,
+                                       None => Node::Static,
+                                   })
+                                   .collect();
+        let contact_edges: Vec<Option<(usize, usize)>> =
+            self.contacts
+                .iter()
+                .map(|c| if c.is_touching() && c.is_enabled() {
+                    Some((c.body_a().index, c.body_b().index))
+                } else {
+                    None
+                })
+                .collect();
+        let joint_edges: Vec<(usize, usize)> = self.joints()
+                                                   .map(|(_, j)| (j.body_a().index, j.body_b().index))
+                                                   .collect();
+        let islands = island::build(&nodes, &contact_edges, &joint_edges);
+
+        for &i in islands.iter().flat_map(|island| &island.bodies) {
+            if nodes[i] == Node::Asleep {
+                self.slots[i].body.as_mut().unwrap().set_awake(true);
+            }
+        }
+        let gravity = self.gravity;
+        for body in self.slots.iter_mut().filter_map(|slot| slot.body.as_mut()).filter(|b| b.is_awake()) {
+            body.integrate_velocity(gravity, dt);
+            body.clear_forces();
+        }
+
+        let mut index = vec![usize::MAX; self.slots.len()];
+        let mut joints: Vec<&mut Joint> = self.joint_slots
+                                              .iter_mut()
+                                              .filter_map(|slot| slot.joint.as_mut())
+                                              .collect();
+
+        let step = TimeStep {
+            dt,
+            inv_dt: 1.0 / dt,
+            warm_starting: self.warm_starting,
+        };
+        for island in &islands {
+            // static bodies may be shared with other islands, so
+            // every island works on its own copy of its bodies
+            let mut bodies = Vec::with_capacity(island.bodies.len());
+            for &i in &island.bodies {
+                index[i] = bodies.len();
+                bodies.push(SolverBody::new(self.slots[i].body.as_ref().unwrap()));
+            }
+            let index_of = |handle: BodyHandle| index[handle.index];
+            let mut solver = ContactSolver::new(&self.contacts, &island.contacts, &bodies, &index_of);
+            if self.warm_starting {
+                solver.warm_start(&mut bodies);
+            } else {
+                solver.reset_impulses();
+            }
+            for &j in &island.joints {
+                joints[j].init_velocity(&mut bodies, &index_of, &step);
+            }
+
+            for _ in 0..self.velocity_iterations {
+                for &j in &island.joints {
+                    joints[j].solve_velocity(&mut bodies, &step);
+                }
+                solver.solve_velocity(&mut bodies);
+            }
+            solver.store_impulses(&mut self.contacts);
+            if let Some(ref listener) = self.contact_listener {
+                let mut listener = listener.lock().unwrap();
+                for &ci in &island.contacts {
+                    listener.post_solve(&self.contacts[ci]);
+                }
+            }
+
+            for &i in &island.bodies {
+                bodies[index[i]].integrate(dt);
+            }
+            let mut solved = false;
+            for _ in 0..self.position_iterations {
+                let contacts_ok = solver.solve_position(&mut bodies);
+                let mut joints_ok = true;
+                for &j in &island.joints {
+                    joints_ok &= joints[j].solve_position(&mut bodies);
+                }
+                if contacts_ok && joints_ok {
+                    solved = true;
+                    break;
+                }
+            }
+
+            // static bodies may be shared with other islands
+            let moved = island.bodies.iter().filter(|&&i| nodes[i] != Node::Static);
+            let mut min_sleep_time = f64::MAX;
+            for &i in moved.clone() {
+                let body = self.slots[i].body.as_mut().unwrap();
+                bodies[index[i]].store(body);
+
+                let resting = body.linear_velocity().len_sq() <= self.linear_sleep_tolerance.powi(2) &&
+                              body.angular_velocity().abs() <= self.angular_sleep_tolerance;
+                if body.is_sleeping_allowed() && resting {
+                    let time = body.sleep_time() + dt;
+                    body.set_sleep_time(time);
+                    min_sleep_time = min_sleep_time.min(time);
+                } else {
+                    body.set_sleep_time(0.0);
+                    min_sleep_time = 0.0;
+                }
+            }
+
+            // an island only falls asleep as a whole, once it's solved
+            if self.sleeping_allowed && solved && min_sleep_time >= self.time_to_sleep {
+                for &i in moved {
+                    self.slots[i].body.as_mut().unwrap().set_awake(false);
+                }
+            }
+        }
+    }
+
+    /// Removes the joints strained beyond their break force or torque
+    /// over the step of `dt` seconds, recording an event for each
+    fn break_joints(&mut self, dt: f64) {
+        let inv_dt = 1.0 / dt;
+        let broken: Vec<JointHandle> = self.joints()
+                                           .filter(|&(handle, joint)| {
+                                               let slot = &self.joint_slots[handle.index];
+                                               slot.break_force.is_some_and(|force| {
+                                                   joint.reaction_force(inv_dt).len() > force
+                                               }) ||
+                                               slot.break_torque.is_some_and(|torque| {
+                                                   joint.reaction_torque(inv_dt).abs() > torque
+                                               })
+                                           })
+                                           .map(|(handle, _)| handle)
+                                           .collect();
+        for handle in broken {
+            // a gear may already be gone with the joint it couples
+            if let Some(joint) = self.remove_joint(handle) {
+                self.joint_events.push(JointEvent::Broken { handle, joint });
+            }
+        }
+    }
+
+    /// Sweeps every bullet from its transform in `starts` to its current
+    /// transform against the bodies that aren't bullets. A bullet closing
+    /// in on another body is moved back to the first impact and loses the
+    /// part of its velocity heading into the other body
+    fn solve_toi(&mut self, starts: &[Option<Transform>]) {
+        let bullets: Vec<BodyHandle> = self.bodies()
+                                           .filter(|&(_, body)| {
+                                               body.is_bullet() && body.is_awake() &&
+                                               body.body_type() == BodyType::Dynamic
+                                           })
+                                           .map(|(handle, _)| handle)
+                                           .collect();
+        for handle in bullets {
+            let body = self.slots[handle.index].body.as_ref().unwrap();
+            let sweep = Sweep::new(*body.local_center(),
+                                   &starts[handle.index].unwrap(),
+                                   body.transform());
+
+            // the earliest impact along with the normal and the velocity of the other body
+            let mut hit: Option<(f64, Vec2d, Vec2d)> = None;
+            for fixture in body.fixtures() {
+                let swept = fixture.aabb(&sweep.transform_at(0.0))
+                                   .combine(&fixture.aabb(body.transform()));
+                let mut candidates = Vec::new();
+                self.broad_phase.query(&swept, &mut |proxy| {
+                    candidates.push(proxy);
+                    true
+                });
+
+                for proxy in candidates {
+                    let (other, i) = self.proxy_owners[&proxy];
+                    let other_body = self.slots[other.index].body.as_ref().unwrap();
+                    let other_fixture = &other_body.fixtures()[i];
+                    if other == handle || other_body.is_bullet() || fixture.is_sensor() || other_fixture.is_sensor() ||
+                       !self.should_collide((handle, fixture), (other, other_fixture)) {
+                        continue;
+                    }
+
+                    let other_sweep = Sweep::new(*other_body.local_center(),
+                                                 &starts[other.index].unwrap(),
+                                                 other_body.transform());
+                    let shape = other_fixture.shape();
+                    let toi = time_of_impact(fixture.shape(), &sweep, shape, &other_sweep);
+                    let earlier = hit.is_some_and(|(t, _, _)| t <= toi.fraction);
+                    if toi.state != ToiState::Touching || earlier {
+                        continue;
+                    }
+
+                    // ignore shapes that touch but are moving apart
+                    let out = distance(fixture.shape(),
+                                       &sweep.transform_at(toi.fraction),
+                                       shape,
+                                       &other_sweep.transform_at(toi.fraction));
+                    // the witness points collapse when the shapes end up touching
+                    // exactly, so fall back to the direction between the centers
+                    let direction = if out.distance > util::TOLERANCE {
+                        out.point_b - out.point_a
+                    } else {
+                        other_sweep.center_at(toi.fraction) - sweep.center_at(toi.fraction)
+                    };
+                    if direction.len_sq() < util::TOLERANCE * util::TOLERANCE {
+                        continue;
+                    }
+                    let normal = direction.normalize();
+                    if passes_through(body, fixture, normal) ||
+                       passes_through(other_body, other_fixture, normal * -1.0) {
+                        continue;
+                    }
+                    let velocity = *other_body.linear_velocity();
+                    if (*body.linear_velocity() - velocity) * normal > 0.0 {
+                        hit = Some((toi.fraction, normal, velocity));
+                    }
+                }
+            }
+
+            if let Some((fraction, normal, velocity)) = hit {
+                let body = self.slots[handle.index].body.as_mut().unwrap();
+                body.set_transform(sweep.transform_at(fraction));
+                let v = *body.linear_velocity();
+                body.set_linear_velocity(v - normal * ((v - velocity) * normal));
+            }
+        }
+    }
+
+    /// Creates broad phase proxies for the fixtures of the body
+    /// that are not yet part of the broad phase
+    fn create_proxies(&mut self, handle: BodyHandle) {
+        let body = self.slots[handle.index].body.as_mut().unwrap();
+        let transform = *body.transform();
+        for (i, fixture) in body.fixtures_mut().iter_mut().enumerate() {
+            if fixture.proxy().is_none() {
+                let proxy = self.broad_phase.create_proxy(&fixture.aabb(&transform));
+                self.proxy_owners.insert(proxy, (handle, i));
+                fixture.set_proxy(Some(proxy));
+            }
+        }
+    }
+
+    /// Moves the broad phase proxies of every fixture to the current
+    /// transform of its body, predicting the motion over the next `dt`.
+    /// Fixtures attached since the last step are added to the broad phase
+    /// and the proxies of bodies that changed type are paired up again
+    fn synchronize(&mut self, dt: f64) {
+        let handles: Vec<BodyHandle> = self.bodies().map(|(handle, _)| handle).collect();
+        for handle in handles {
+            self.create_proxies(handle);
+
+            let slot = &mut self.slots[handle.index];
+            let body = slot.body.as_ref().unwrap();
+            if body.body_type() != slot.body_type {
+                slot.body_type = body.body_type();
+                for fixture in body.fixtures() {
+                    self.broad_phase.touch_proxy(fixture.proxy().unwrap());
+                }
+            }
+            if !body.is_awake() {
+                continue;
+            }
+            let displacement = *body.linear_velocity() * dt;
+            for fixture in body.fixtures() {
+                let aabb = fixture.aabb(body.transform());
+                self.broad_phase.move_proxy(fixture.proxy().unwrap(), &aabb, displacement);
+            }
+        }
+    }
+
+    /// Keeps the pairs of fixtures whose fattened `Aabb`s still overlap
+    /// and adds the pairs the broad phase found for proxies that moved.
+    /// Pairs of fixtures of the same body or of bodies that both aren't
+    /// dynamic are left out
+    fn update_pairs(&mut self) {
+        let found = self.broad_phase.moved_pairs();
+        let broad_phase = &self.broad_phase;
+        self.pairs.retain(|&(a, b)| broad_phase.fat_aabb(a).intersects(broad_phase.fat_aabb(b)));
+
+        let (owners, slots) = (&self.proxy_owners, &self.slots);
+        let dynamic = |(handle, _): (BodyHandle, usize)| slots[handle.index].body_type == BodyType::Dynamic;
+        self.pairs.extend(found.into_iter().filter(|&(a, b)| {
+            let (a, b) = (owners[&a], owners[&b]);
+            a.0 != b.0 && (dynamic(a) || dynamic(b))
+        }));
+        self.pairs.sort();
+        self.pairs.dedup();
+    }
+
+    /// Updates the contacts of the fixture pairs overlapping in the
+    /// broad phase, keeping the contacts of pairs that overlapped during
+    /// the last step. Returns the indices of the contacts that started
+    /// touching
+    fn collide(&mut self) -> Vec<usize> {
+        self.update_pairs();
+
+        // bodies held together by joints don't collide unless asked to
+        let connected: HashSet<(BodyHandle, BodyHandle)> = self.joints()
+                                                               .map(|(_, j)| j)
+                                                               .filter(|j| !j.collide_connected())
+                                                               .map(|j| {
+                                                                   let (a, b) = (j.body_a(), j.body_b());
+                                                                   (a.min(b), a.max(b))
+                                                               })
+                                                               .collect();
+        // contacts are kept in the order of their pairs, so the old contacts
+        // are walked along with the sorted pairs instead of looked up
+        let mut cached = mem::take(&mut self.contacts).into_iter().peekable();
+        let mut ended = Vec::new();
+        let mut contacts = Vec::new();
+        let mut began = Vec::new();
+        let mut woken = Vec::new();
+        let mut overlaps = HashSet::new();
+        let pairs = mem::take(&mut self.pairs);
+        for &(proxy_a, proxy_b) in &pairs {
+            let pair = (proxy_a, proxy_b);
+            while let Some(contact) = cached.next_if(|c| c.proxies() < pair) {
+                ended.push(contact);
+            }
+            let (handle_a, index_a) = self.proxy_owners[&proxy_a];
+            let (handle_b, index_b) = self.proxy_owners[&proxy_b];
+            if connected.contains(&(handle_a.min(handle_b), handle_a.max(handle_b))) {
+                continue;
+            }
+
+            // pairs of resting bodies are left as they were
+            let body_a = self.slots[handle_a.index].body.as_ref().unwrap();
+            let body_b = self.slots[handle_b.index].body.as_ref().unwrap();
+            let active = |body: &RigidBody| body.is_awake() && !is_static(body);
+            if !active(body_a) && !active(body_b) {
+                contacts.extend(cached.next_if(|c| c.proxies() == pair));
+                if self.sensor_overlaps.contains(&pair) {
+                    overlaps.insert(pair);
+                }
+                continue;
+            }
+
+            let fixture_a = &body_a.fixtures()[index_a];
+            let fixture_b = &body_b.fixtures()[index_b];
+            // only pairs involving a dynamic body respond to contact
+            if body_a.body_type() != BodyType::Dynamic && body_b.body_type() != BodyType::Dynamic ||
+               !self.should_collide((handle_a, fixture_a), (handle_b, fixture_b)) {
+                continue;
+            }
+
+            if fixture_a.is_sensor() || fixture_b.is_sensor() {
+                // sensors only record overlaps and don't sense each other
+                let overlapping = !(fixture_a.is_sensor() && fixture_b.is_sensor()) &&
+                                  fixture_a.shape().collides_with(fixture_b.shape(),
+                                                                  body_a.transform(),
+                                                                  body_b.transform());
+                if overlapping {
+                    overlaps.insert(pair);
+                }
+                continue;
+            }
+
+            let mut contact = cached.next_if(|c| c.proxies() == pair).unwrap_or_else(|| {
+                Contact::new(handle_a,
+                             (index_a, fixture_a),
+                             handle_b,
+                             (index_b, fixture_b),
+                             (proxy_a, proxy_b))
+            });
+            let old_manifold = contact.manifold().cloned();
+            let (shape_a, shape_b) = (fixture_a.shape(), fixture_b.shape());
+            let manifold = shape_a.manifold(shape_b, body_a.transform(), body_b.transform());
+            let passing = manifold.as_ref().is_some_and(|m| {
+                passes_through(body_a, fixture_a, *m.normal()) ||
+                passes_through(body_b, fixture_b, *m.normal() * -1.0)
+            });
+            if old_manifold.is_some() && manifold.is_none() {
+                self.end_contact(&contact);
+            }
+            contact.update(manifold);
+            if contact.is_touching() {
+                if old_manifold.is_none() {
+                    began.push(contacts.len());
+                    if let Some(ref listener) = self.contact_listener {
+                        listener.lock().unwrap().begin_contact(&contact);
+                    }
+                    contact.set_passing(passing);
+                }
+                // contacts that start touching a one way fixture from
+                // the wrong side pass through until they stop touching
+                if contact.is_passing() {
+                    contact.set_enabled(false);
+                }
+                if let Some(ref listener) = self.contact_listener {
+                    listener.lock().unwrap().pre_solve(&mut contact, old_manifold.as_ref());
+                }
+            }
+            if contact.is_touching() != old_manifold.is_some() {
+                woken.extend_from_slice(&[handle_a, handle_b]);
+            }
+            contacts.push(contact);
+        }
+
+        self.pairs = pairs;
+        ended.extend(cached);
+        for contact in ended.into_iter().filter(|c| c.is_touching()) {
+            woken.extend_from_slice(&[contact.body_a(), contact.body_b()]);
+            self.end_contact(&contact);
+        }
+        self.contacts = contacts;
+        self.wake(&woken);
+
+        let mut entered: Vec<_> = overlaps.difference(&self.sensor_overlaps).cloned().collect();
+        let mut left: Vec<_> = self.sensor_overlaps.difference(&overlaps).cloned().collect();
+        entered.sort();
+        left.sort();
+        for pair in entered {
+            let (sensor, other) = self.sensor_pair(pair);
+            self.sensor_events.push(SensorEvent::Begin { sensor, other });
+        }
+        for pair in left {
+            let (sensor, other) = self.sensor_pair(pair);
+            self.sensor_events.push(SensorEvent::End { sensor, other });
+        }
+        self.sensor_overlaps = overlaps;
+        began
+    }
+
+    /// Tells the contact listener and the contact events
+    /// that the touching contact stops touching
+    fn end_contact(&mut self, contact: &Contact) {
+        if let Some(ref listener) = self.contact_listener {
+            listener.lock().unwrap().end_contact(contact);
+        }
+        self.contact_events.push(ContactEvent::End(contact.clone()));
+    }
+
+    /// Returns the sensor fixture and the other fixture of the pair
+    fn sensor_pair(&self, (proxy_a, proxy_b): (ProxyId, ProxyId))
+                   -> ((BodyHandle, usize), (BodyHandle, usize)) {
+        let (a, b) = (self.proxy_owners[&proxy_a], self.proxy_owners[&proxy_b]);
+        if self.slots[a.0.index].body.as_ref().unwrap().fixtures()[a.1].is_sensor() {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+
+    /// Returns if the fixtures collide according to their filters
+    /// and the contact filter of the world
+    fn should_collide(&self,
+                      (body_a, fixture_a): (BodyHandle, &Fixture),
+                      (body_b, fixture_b): (BodyHandle, &Fixture))
+                      -> bool {
+        fixture_a.filter().should_collide(fixture_b.filter()) &&
+        self.contact_filter
+            .as_ref()
+            .is_none_or(|filter| filter.should_collide(body_a, fixture_a, body_b, fixture_b))
+    }
+
+    /// Wakes the bodies
+    fn wake(&mut self, bodies: &[BodyHandle]) {
+        for handle in bodies {
+            self.slots[handle.index].body.as_mut().unwrap().set_awake(true);
+        }
+    }
+}
+
+/// Returns if the fixture of the body is one way and lets through a
+/// fixture touching it along `normal`, pointing away from the fixture
+fn passes_through(body: &RigidBody, fixture: &Fixture, normal: Vec2d) -> bool {
+    fixture.one_way().is_some_and(|n| n.rotate(body.transform().rotation()) * normal <= util::TOLERANCE)
+}
+
+/// Returns if the body neither moves nor can be moved
+fn is_static(body: &RigidBody) -> bool {
+    body.body_type() == BodyType::Static
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+    use std::sync::{Arc, Mutex};
+    use collision::Manifold;
+    use collision::broad_phase::SweepAndPrune;
+    use collision::shapes::{Circle, Convex};
+    use common::{Rotation, Transform, Vec2d};
+    use dynamics::{BodyHandle, BodyType, Contact, ContactEvent, ContactListener, Filter, Fixture, JointEvent,
+                   RigidBody, SensorEvent, World};
+    use dynamics::joints::{RevoluteJoint, WeldJoint};
+
+    fn square_body(x: f64, y: f64) -> RigidBody {
+        let shape = Convex::new(&[Vec2d::new(-0.5, -0.5),
+                                  Vec2d::new(0.5, -0.5),
+                                  Vec2d::new(0.5, 0.5),
+                                  Vec2d::new(-0.5, 0.5)])
+                        .unwrap();
+        let mut body = RigidBody::new(Transform::new(Vec2d::new(x, y), Rotation::identity()));
+        body.add_fixture(Fixture::new(shape, 1.0));
+        body
+    }
+
+    #[test]
+    fn test_world_fixed_step_free_fall() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let handle = world.add_body(square_body(0.0, 0.0));
+
+        // half a step doesn't advance the world
+        assert_eq!(0, world.step(0.5 / 60.0));
+        assert_eq!(Vec2d::zero(), *world.body(handle).unwrap().position());
+
+        // 60 steps of semi-implicit euler: y = -g * dt^2 * n(n+1)/2
+        let mut steps = 0;
+        for _ in 0..60 {
+            steps += world.step(1.0 / 60.0);
+        }
+        assert_eq!(60, steps);
+        let body = world.body(handle).unwrap();
+        let expected = -10.0 * (1.0 / 3600.0) * (60.0 * 61.0 / 2.0);
+        assert!((body.position().y - expected).abs() < 1e-9);
+        assert!((body.linear_velocity().y + 10.0).abs() < 1e-9);
+
+        // falling behind drops the whole steps beyond the maximum
+        world.set_max_sub_steps(2);
+        assert_eq!(2, world.step(4.0 / 60.0));
+        assert!((world.interpolation_alpha() - 0.5).abs() < 1e-6);
+        assert_eq!(0, world.step(0.0));
+
+        // a time step that isn't positive is rejected
+        assert!(world.set_time_step(0.0).is_err());
+        assert!(world.set_time_step(f64::NAN).is_err());
+        assert!(world.set_time_step(1.0 / 120.0).is_ok());
+        assert_eq!(1.0 / 120.0, world.time_step());
+    }
+
+    #[test]
+    fn test_world_add_remove_bodies() {
+        let mut world = World::new(Vec2d::zero());
+        let a = world.add_body(square_body(0.0, 0.0));
+        let b = world.add_body(square_body(0.8, 0.0));
+        let mut c = RigidBody::new(Transform::new(Vec2d::new(0.0, 0.9), Rotation::identity()));
+        c.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.5).unwrap(), 1.0));
+        let c = world.add_body(c);
+        assert_eq!(3, world.body_count());
+
+        world.step(world.time_step());
+        assert_eq!(3, world.contacts().iter().filter(|c| c.is_touching()).count());
+
+        assert!(world.remove_body(b).is_some());
+        assert!(world.remove_body(b).is_none());
+        assert!(world.body(b).is_none());
+        assert_eq!(1, world.contacts().len());
+
+        // the freed slot is reused under a new handle
+        let d = world.add_body(square_body(5.0, 0.0));
+        assert!(d != b);
+        assert!(world.body(b).is_none());
+        assert_eq!(3, world.bodies().count());
+
+        world.step(world.time_step());
+        assert_eq!(1, world.contacts().len());
+        let contact = &world.contacts()[0];
+        assert_eq!((a, c), (contact.body_a(), contact.body_b()));
+    }
+
+    #[test]
+    fn test_world_with_broad_phase() {
+        let mut world = World::with_broad_phase(Vec2d::zero(), SweepAndPrune::new());
+        world.add_body(square_body(0.0, 0.0));
+        world.add_body(square_body(0.9, 0.0));
+        world.add_body(square_body(3.0, 0.0));
+        world.step(world.time_step());
+        assert_eq!(1, world.contacts().len());
+    }
+
+    #[test]
+    fn test_world_bullet() {
+        let wall_shape = Convex::new(&[Vec2d::new(-0.05, -2.0),
+                                       Vec2d::new(0.05, -2.0),
+                                       Vec2d::new(0.05, 2.0),
+                                       Vec2d::new(-0.05, 2.0)])
+                             .unwrap();
+        let mut wall = RigidBody::new(Transform::identity());
+        wall.add_fixture(Fixture::new(wall_shape, 0.0));
+        wall.set_body_type(BodyType::Static);
+
+        let shoot = |bullet: bool| {
+            let mut world = World::new(Vec2d::zero());
+            world.add_body(wall.clone());
+            let mut body = RigidBody::new(Transform::new(Vec2d::new(-2.0, 0.0), Rotation::identity()));
+            body.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.1).unwrap(), 1.0));
+            body.set_linear_velocity(Vec2d::new(200.0, 1.0));
+            body.set_bullet(bullet);
+            let handle = world.add_body(body);
+            for _ in 0..5 {
+                world.step(world.time_step());
+            }
+            world.remove_body(handle).unwrap()
+        };
+
+        // moving over 3 units per step, a regular body tunnels through the wall
+        assert!(shoot(false).position().x > 0.0);
+
+        // a bullet stops at the wall and keeps sliding along it
+        let body = shoot(true);
+        assert!(body.position().x < -0.1 && body.position().x > -0.2);
+        assert!(body.linear_velocity().x.abs() < 1e-9);
+        assert!((body.linear_velocity().y - 1.0).abs() < 1e-9);
+        assert!(body.position().y > 0.0);
+    }
+
+    fn ground() -> RigidBody {
+        let shape = Convex::new(&[Vec2d::new(-10.0, -0.5),
+                                  Vec2d::new(10.0, -0.5),
+                                  Vec2d::new(10.0, 0.5),
+                                  Vec2d::new(-10.0, 0.5)])
+                        .unwrap();
+        let mut ground = RigidBody::new(Transform::identity());
+        ground.add_fixture(Fixture::new(shape, 0.0));
+        ground.set_body_type(BodyType::Static);
+        ground
+    }
+
+    #[test]
+    fn test_world_resting_contact() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        world.add_body(ground());
+        let a = world.add_body(square_body(0.0, 2.0));
+        let b = world.add_body(square_body(0.1, 3.5));
+        for _ in 0..180 {
+            world.step(world.time_step());
+        }
+
+        // the boxes settle on top of each other within the slop
+        let a = world.body(a).unwrap();
+        let b = world.body(b).unwrap();
+        assert!((a.position().y - 1.0).abs() < 0.02);
+        assert!((b.position().y - 2.0).abs() < 0.04);
+        assert!(a.linear_velocity().len() < 0.05);
+        assert!(b.linear_velocity().len() < 0.05);
+        assert!(world.contacts().iter().all(|c| c.normal_impulses().iter().all(|&i| i >= 0.0)));
+    }
+
+    #[test]
+    fn test_world_restitution() {
+        let bounce = |restitution: f64| {
+            let mut world = World::new(Vec2d::new(0.0, -10.0));
+            world.add_body(ground());
+            let mut ball = RigidBody::new(Transform::new(Vec2d::new(0.0, 1.5), Rotation::identity()));
+            let mut fixture = Fixture::new(Circle::new(Vec2d::zero(), 0.5).unwrap(), 1.0);
+            fixture.set_restitution(restitution);
+            ball.add_fixture(fixture);
+            ball.set_linear_velocity(Vec2d::new(0.0, -5.0));
+            let handle = world.add_body(ball);
+            for _ in 0..10 {
+                world.step(world.time_step());
+            }
+            world.body(handle).unwrap().linear_velocity().y
+        };
+
+        // an elastic ball leaves with about the speed it came in with
+        assert!(bounce(1.0) > 4.5);
+        assert!(bounce(0.5) > 2.0 && bounce(0.5) < 3.0);
+        assert!(bounce(0.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_world_friction() {
+        let slide = |friction: f64| {
+            let mut world = World::new(Vec2d::new(0.0, -10.0));
+            let mut floor = ground();
+            floor.fixture_mut(0).unwrap().set_friction(friction);
+            world.add_body(floor);
+            let mut body = square_body(0.0, 0.995);
+            body.fixture_mut(0).unwrap().set_friction(friction);
+            body.set_linear_velocity(Vec2d::new(4.0, 0.0));
+            let handle = world.add_body(body);
+            for _ in 0..120 {
+                world.step(world.time_step());
+            }
+            *world.body(handle).unwrap().position()
+        };
+
+        // v^2 / (2 * mu * g) = 16 / 10 with a friction of 0.5
+        let rough = slide(0.5);
+        assert!((rough.x - 1.6).abs() < 0.1);
+        let slick = slide(0.0);
+        assert!((slick.x - 8.0).abs() < 0.1);
+        assert!((slick.y - 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_world_warm_starting() {
+        let stack = |warm_starting: bool| {
+            let mut world = World::new(Vec2d::new(0.0, -10.0));
+            world.set_warm_starting(warm_starting);
+            let floor = world.add_body(ground());
+            for i in 0..5 {
+                world.add_body(square_body(0.0, 1.0 + i as f64));
+            }
+            for _ in 0..120 {
+                world.step(world.time_step());
+            }
+            let jitter = world.bodies().fold(0.0f64, |max, (_, b)| max.max(b.linear_velocity().len()));
+            let support: f64 = world.contacts()
+                                    .iter()
+                                    .filter(|c| c.body_a() == floor || c.body_b() == floor)
+                                    .flat_map(|c| c.normal_impulses().iter())
+                                    .sum();
+            (jitter, support)
+        };
+
+        // the floor carries the weight of all five boxes every step
+        let (warm, support) = stack(true);
+        assert!((support - 5.0 * 10.0 / 60.0).abs() < 0.01);
+        let (cold, _) = stack(false);
+        assert!(warm < 0.01);
+        assert!(warm * 10.0 < cold);
+    }
+
+    #[test]
+    fn test_world_contact_persistence() {
+        let mut world = World::new(Vec2d::zero());
+        let a = world.add_body(square_body(0.0, 0.0));
+        let b = world.add_body(square_body(0.95, 0.0));
+        world.step(world.time_step());
+        assert_eq!(1, world.contacts().len());
+        assert!(world.contacts()[0].is_touching());
+
+        // apart but with overlapping aabbs the contact is kept without touching
+        let apart = Transform::new(Vec2d::new(1.1, 0.0), Rotation::identity());
+        world.body_mut(b).unwrap().set_transform(apart);
+        world.step(world.time_step());
+        assert_eq!(1, world.contacts().len());
+        assert!(!world.contacts()[0].is_touching());
+        assert!(world.contacts()[0].normal_impulses().is_empty());
+        assert_eq!((a, b), (world.contacts()[0].body_a(), world.contacts()[0].body_b()));
+
+        let away = Transform::new(Vec2d::new(5.0, 0.0), Rotation::identity());
+        world.body_mut(b).unwrap().set_transform(away);
+        world.step(world.time_step());
+        assert!(world.contacts().is_empty());
+    }
+
+    #[test]
+    fn test_world_breakable_joints() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let ground = world.add_body(ground());
+        let a = world.add_body(square_body(0.0, 3.0));
+        let b = world.add_body(square_body(0.0, 4.0));
+        let weight = 10.0 * world.body(a).unwrap().mass();
+
+        // a box hangs from the ground and another box hangs from it
+        let upper = WeldJoint::new(ground, a, Vec2d::new(1.0, 3.5), Vec2d::new(1.0, 0.5));
+        let upper = world.add_joint(upper).unwrap();
+        world.set_joint_break_torque(upper, Some(5.0 * weight)).unwrap();
+        let lower = RevoluteJoint::new(a, b, Vec2d::new(0.0, 0.5), Vec2d::new(0.0, -0.5));
+        let lower = world.add_joint(lower).unwrap();
+        world.set_joint_break_force(lower, Some(2.0 * weight)).unwrap();
+        assert_eq!(Some(2.0 * weight), world.joint_break_force(lower));
+        assert_eq!(None, world.joint_break_torque(lower));
+        for _ in 0..60 {
+            world.step(world.time_step());
+            assert_eq!(0, world.drain_joint_events().count());
+        }
+
+        // the weld carries both boxes a unit off its anchor, the hinge one box
+        let inv_dt = 1.0 / world.time_step();
+        let joint = world.joint(upper).unwrap();
+        assert!((joint.reaction_force(inv_dt) - Vec2d::new(0.0, 2.0 * weight)).len() < 1e-3);
+        assert!((joint.reaction_torque(inv_dt) + 2.0 * weight).abs() < 1e-3);
+        let joint = world.joint(lower).unwrap();
+        assert!((joint.reaction_force(inv_dt) - Vec2d::new(0.0, weight)).len() < 1e-3);
+
+        // a heavier load snaps the hinge
+        world.body_mut(b).unwrap().apply_force_to_center(Vec2d::new(0.0, -2.0 * weight));
+        world.step(world.time_step());
+        let events: Vec<JointEvent> = world.drain_joint_events().collect();
+        assert_eq!(1, events.len());
+        match events[0] {
+            JointEvent::Broken { handle, ref joint } => {
+                assert_eq!(lower, handle);
+                assert_eq!(b, joint.body_b());
+            }
+            _ => panic!(),
+        }
+        assert!(world.joint(lower).is_none());
+        assert!(world.set_joint_break_force(lower, None).is_err());
+        assert!(world.joint(upper).is_some());
+        assert_eq!(0, world.drain_joint_events().count());
+    }
+
+    #[test]
+    fn test_world_sleeping() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        world.add_body(ground());
+        let a = world.add_body(square_body(0.0, 1.0));
+        let b = world.add_body(square_body(0.0, 2.0));
+        let c = world.add_body(square_body(5.0, 1.0));
+        let ball = world.add_body(square_body(20.0, 1.0));
+
+        // the resting boxes fall asleep while the ball keeps falling
+        for _ in 0..60 {
+            world.step(world.time_step());
+        }
+        for &handle in &[a, b, c] {
+            let body = world.body(handle).unwrap();
+            assert!(!body.is_awake());
+            assert_eq!(Vec2d::zero(), *body.linear_velocity());
+        }
+        assert!(world.body(ball).unwrap().is_awake());
+        let rest = *world.body(b).unwrap().transform();
+        world.step(world.time_step());
+        assert_eq!(rest, *world.body(b).unwrap().transform());
+
+        // pushing the lower box wakes the stack, not the box beside it
+        world.body_mut(a).unwrap().apply_force_to_center(Vec2d::new(1.0, 0.0));
+        world.step(world.time_step());
+        assert!(world.body(a).unwrap().is_awake());
+        assert!(world.body(b).unwrap().is_awake());
+        assert!(!world.body(c).unwrap().is_awake());
+        for _ in 0..60 {
+            world.step(world.time_step());
+        }
+        assert!(!world.body(a).unwrap().is_awake() && !world.body(b).unwrap().is_awake());
+
+        // a box dropped onto the sleeping box wakes it on impact
+        world.body_mut(ball).unwrap().set_transform(Transform::new(Vec2d::new(5.0, 3.0), Rotation::identity()));
+        world.body_mut(ball).unwrap().set_linear_velocity(Vec2d::zero());
+        let mut woke = false;
+        for _ in 0..30 {
+            world.step(world.time_step());
+            woke |= world.body(c).unwrap().is_awake();
+        }
+        assert!(woke);
+        assert!(!world.body(a).unwrap().is_awake());
+
+        // moving a sleeping body wakes it
+        let moved = Transform::new(Vec2d::new(-5.0, 1.0), Rotation::identity());
+        world.body_mut(a).unwrap().set_transform(moved);
+        assert!(world.body(a).unwrap().is_awake());
+
+        // without sleep the stack is simulated forever
+        world.set_sleeping_allowed(false);
+        for _ in 0..120 {
+            world.step(world.time_step());
+        }
+        assert!(world.bodies().all(|(_, body)| body.is_awake()));
+    }
+
+    #[test]
+    fn test_world_sleeping_island_members() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        world.add_body(ground());
+        let slippery = |y: f64| {
+            let mut body = square_body(0.0, y);
+            body.fixture_mut(0).unwrap().set_friction(0.0);
+            body
+        };
+        let a = world.add_body(slippery(1.0));
+        let b = world.add_body(slippery(2.0));
+        for _ in 0..60 {
+            world.step(world.time_step());
+        }
+        assert!(!world.body(a).unwrap().is_awake());
+
+        // the sleeping box under the woken one is pulled into its
+        // island and feels the sideways gravity from the same step
+        world.set_gravity(Vec2d::new(5.0, -10.0));
+        world.body_mut(b).unwrap().set_awake(true);
+        world.step(world.time_step());
+        for &handle in &[a, b] {
+            let body = world.body(handle).unwrap();
+            assert!(body.is_awake());
+            assert!((body.linear_velocity().x - 5.0 * world.time_step()).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_world_body_types() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        // a static body ignores the mass of its fixtures
+        let mut floor = RigidBody::new(Transform::identity());
+        floor.add_fixture(ground().fixtures()[0].clone());
+        floor.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.1).unwrap(), 1.0));
+        floor.set_body_type(BodyType::Static);
+        let floor = world.add_body(floor);
+        let mut wall = square_body(8.0, 1.0);
+        wall.set_body_type(BodyType::Static);
+        let wall = world.add_body(wall);
+        let mut pusher = square_body(-3.0, 1.0);
+        pusher.set_body_type(BodyType::Kinematic);
+        pusher.set_linear_velocity(Vec2d::new(2.0, 0.0));
+        let pusher = world.add_body(pusher);
+        let block = world.add_body(square_body(0.0, 1.0));
+
+        for _ in 0..120 {
+            world.step(world.time_step());
+        }
+
+        // the static floor holds while the platform moves on unaffected
+        assert_eq!(Vec2d::zero(), *world.body(floor).unwrap().position());
+        let body = world.body(pusher).unwrap();
+        assert!((*body.position() - Vec2d::new(1.0, 1.0)).len() < 1e-9);
+        assert_eq!(Vec2d::new(2.0, 0.0), *body.linear_velocity());
+
+        // the block is pushed ahead of the platform
+        let x = world.body(block).unwrap().position().x;
+        assert!(x > 1.95 && x < 2.1);
+
+        // only pairs with a dynamic body make contacts
+        assert!(world.contacts().iter().all(|c| c.body_a() == block || c.body_b() == block));
+        assert_eq!(2, world.contacts().iter().filter(|c| c.is_touching()).count());
+
+        // the wall pairs up with the floor it stands on once it turns dynamic
+        world.body_mut(wall).unwrap().set_body_type(BodyType::Dynamic);
+        world.step(world.time_step());
+        assert!(world.contacts()
+                     .iter()
+                     .any(|c| c.is_touching() && (c.body_a(), c.body_b()) == (floor, wall)));
+    }
+
+    #[test]
+    fn test_world_collision_filtering() {
+        let filtered = |body: RigidBody, filter: Filter| {
+            let mut body = body;
+            body.fixture_mut(0).unwrap().set_filter(filter);
+            body
+        };
+        let mut world = World::new(Vec2d::zero());
+        let player = Filter {
+            category_bits: 0x0002,
+            ..Filter::default()
+        };
+        let bullet = Filter {
+            category_bits: 0x0004,
+            mask_bits: !0x0002,
+            ..Filter::default()
+        };
+        let a = world.add_body(filtered(square_body(0.0, 0.0), player));
+        let b = world.add_body(filtered(square_body(0.9, 0.0), bullet));
+        let c = world.add_body(square_body(0.0, 0.9));
+        let d = world.add_body(square_body(0.9, 0.9));
+        world.step(world.time_step());
+
+        // the bullet passes through the player only
+        let pairs = |world: &World| {
+            let mut pairs: Vec<(BodyHandle, BodyHandle)> =
+                world.contacts().iter().map(|c| (c.body_a(), c.body_b())).collect();
+            pairs.sort();
+            pairs
+        };
+        assert_eq!(vec![(a, c), (a, d), (b, c), (b, d), (c, d)], pairs(&world));
+
+        // the contact filter rules out more pairs
+        world.set_contact_filter(move |body_a: BodyHandle, _: &Fixture, body_b: BodyHandle, _: &Fixture| {
+            body_a != c && body_b != c
+        });
+        world.step(world.time_step());
+        assert_eq!(vec![(a, d), (b, d)], pairs(&world));
+        world.clear_contact_filter();
+        world.step(world.time_step());
+        assert_eq!(5, world.contacts().len());
+
+        // filters may change after the fixtures were attached
+        world.body_mut(b).unwrap().fixture_mut(0).unwrap().set_filter(player);
+        world.step(world.time_step());
+        assert_eq!(vec![(a, b), (a, c), (a, d), (b, c), (b, d), (c, d)], pairs(&world));
+        assert!(world.body_mut(b).unwrap().fixture_mut(1).is_none());
+    }
+
+    #[test]
+    fn test_world_sensors() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let mut zone = RigidBody::new(Transform::identity());
+        let mut fixture = Fixture::new(Circle::new(Vec2d::zero(), 1.0).unwrap(), 0.0);
+        fixture.set_sensor(true);
+        zone.add_fixture(fixture);
+        zone.set_body_type(BodyType::Static);
+        let zone = world.add_body(zone);
+        let mut ball = RigidBody::new(Transform::new(Vec2d::new(0.0, 1.5), Rotation::identity()));
+        ball.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.25).unwrap(), 1.0));
+        let ball = world.add_body(ball);
+
+        // a falling sensor senses the zone as little as the zone senses it
+        let mut probe = RigidBody::new(Transform::new(Vec2d::new(1.5, 1.5), Rotation::identity()));
+        let mut fixture = Fixture::new(Circle::new(Vec2d::zero(), 1.0).unwrap(), 1.0);
+        fixture.set_sensor(true);
+        probe.add_fixture(fixture);
+        let probe = world.add_body(probe);
+
+        // the ball falls through the zone unhindered
+        let mut events = Vec::new();
+        for _ in 0..60 {
+            world.step(world.time_step());
+            events.extend(world.drain_sensor_events());
+        }
+        assert!(world.contacts().is_empty());
+        assert!((world.body(ball).unwrap().linear_velocity().y + 10.0).abs() < 1e-9);
+        assert!(world.body(probe).unwrap().position().y < -2.5);
+        assert_eq!(vec![SensorEvent::Begin {
+                            sensor: (zone, 0),
+                            other: (ball, 0),
+                        },
+                        SensorEvent::End {
+                            sensor: (zone, 0),
+                            other: (ball, 0),
+                        }],
+                   events);
+
+        // removing a body inside the zone ends its overlap
+        let mut pickup = RigidBody::new(Transform::identity());
+        pickup.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.25).unwrap(), 1.0));
+        let pickup = world.add_body(pickup);
+        world.step(world.time_step());
+        assert_eq!(1, world.drain_sensor_events().count());
+        world.remove_body(pickup);
+        world.step(world.time_step());
+        assert_eq!(vec![SensorEvent::End {
+                            sensor: (zone, 0),
+                            other: (pickup, 0),
+                        }],
+                   world.drain_sensor_events().collect::<Vec<_>>());
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        ghost: Option<BodyHandle>,
+        conveyor: Option<BodyHandle>,
+        begun: usize,
+        ended: usize,
+        impulse: f64,
+    }
+
+    impl ContactListener for Recorder {
+        fn begin_contact(&mut self, _: &Contact) {
+            self.begun += 1;
+        }
+
+        fn end_contact(&mut self, _: &Contact) {
+            self.ended += 1;
+        }
+
+        fn pre_solve(&mut self, contact: &mut Contact, _: Option<&Manifold>) {
+            let (a, b) = (contact.body_a(), contact.body_b());
+            let involves = |body: Option<BodyHandle>| body.is_some_and(|body| body == a || body == b);
+            if involves(self.ghost) {
+                contact.set_enabled(false);
+            }
+            if involves(self.conveyor) {
+                // the belt carries the other body to the right
+                let sign = if Some(a) == self.conveyor { 1.0 } else { -1.0 };
+                contact.set_tangent_speed(sign * 1.0);
+                contact.set_friction(1.0);
+            }
+        }
+
+        fn post_solve(&mut self, contact: &Contact) {
+            self.impulse += contact.normal_impulses().iter().sum::<f64>();
+        }
+    }
+
+    #[test]
+    fn test_world_contact_listener() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let mut floor = ground();
+        floor.set_body_type(BodyType::Static);
+        let floor = world.add_body(floor);
+        let ball = |x: f64| {
+            let mut body = RigidBody::new(Transform::new(Vec2d::new(x, 1.0), Rotation::identity()));
+            body.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.25).unwrap(), 1.0));
+            body
+        };
+        let solid = world.add_body(ball(-5.0));
+        let ghost = world.add_body(ball(5.0));
+        let parcel = world.add_body(square_body(0.0, 1.1));
+        let recorder = Arc::new(Mutex::new(Recorder {
+            ghost: Some(ghost),
+            conveyor: Some(floor),
+            ..Recorder::default()
+        }));
+        world.set_contact_listener(recorder.clone());
+
+        // the world with its listener can be sent to another thread
+        fn assert_send<T: Send>(_: &T) {}
+        assert_send(&world);
+
+        for _ in 0..60 {
+            world.step(world.time_step());
+        }
+
+        // the ghost falls through the floor, leaving it behind
+        assert!(world.body(solid).unwrap().position().y > 0.7);
+        assert!(world.body(ghost).unwrap().position().y < -1.0);
+        let touching = world.contacts().iter().filter(|c| c.is_touching()).count();
+        assert_eq!(2, touching);
+        {
+            let recorder = recorder.lock().unwrap();
+            assert!(recorder.ended >= 1);
+            assert_eq!(touching, recorder.begun - recorder.ended);
+            assert!(recorder.impulse > 0.0);
+        }
+
+        // the conveyor carries the parcel at the speed of the belt
+        let body = world.body(parcel).unwrap();
+        assert!((body.linear_velocity().x - 1.0).abs() < 0.01);
+        assert!(body.position().x > 0.5);
+
+        // removing a body ends its contacts
+        world.drain_contact_events().count();
+        world.remove_body(solid);
+        let touching = world.contacts().iter().filter(|c| c.is_touching()).count();
+        assert_eq!(1, touching);
+        let recorder = recorder.lock().unwrap();
+        assert_eq!(touching, recorder.begun - recorder.ended);
+        match world.drain_contact_events().collect::<Vec<_>>()[..] {
+            [ContactEvent::End(ref contact)] => assert_eq!(solid, contact.body_b()),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_world_contact_events() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        world.add_body(ground());
+        let mut ball = RigidBody::new(Transform::new(Vec2d::new(0.0, 1.0), Rotation::identity()));
+        let mut fixture = Fixture::new(Circle::new(Vec2d::zero(), 0.25).unwrap(), 1.0);
+        fixture.set_restitution(1.0);
+        ball.add_fixture(fixture);
+        ball.set_linear_velocity(Vec2d::new(0.0, -5.0));
+        let ball = world.add_body(ball);
+
+        // the ball bounces off the ground, hitting it hard
+        let mut events = Vec::new();
+        for _ in 0..30 {
+            world.step(world.time_step());
+            events.extend(world.drain_contact_events());
+        }
+        assert_eq!(2, events.len());
+        match events[0] {
+            ContactEvent::Begin(ref contact) => {
+                assert_eq!(ball, contact.body_b());
+                let impulse: f64 = contact.normal_impulses().iter().sum();
+                assert!(impulse > 0.5 * 2.0 * 5.0 * world.body(ball).unwrap().mass());
+            }
+            ContactEvent::End(_) => panic!(),
+        }
+        match events[1] {
+            ContactEvent::End(ref contact) => assert_eq!(ball, contact.body_b()),
+            ContactEvent::Begin(_) => panic!(),
+        }
+        assert!(world.body(ball).unwrap().linear_velocity().y > 0.0);
+    }
+
+    #[test]
+    fn test_world_undrained_events() {
+        let undrained = |steps: usize| {
+            let mut world = World::new(Vec2d::new(0.0, -10.0));
+            world.add_body(ground());
+            world.add_body(square_body(0.0, 1.0));
+            for _ in 0..steps {
+                world.step(world.time_step());
+            }
+            world.drain_contact_events().count()
+        };
+
+        // the box lands during the first step and the event
+        // outlives the next step but not the one after
+        assert_eq!(1, undrained(1));
+        assert_eq!(1, undrained(2));
+        assert_eq!(0, undrained(3));
+    }
+
+    #[test]
+    fn test_world_one_way_platforms() {
+        let mut world = World::new(Vec2d::new(0.0, -10.0));
+        let platform = |transform: Transform, normal: Vec2d| {
+            let mut fixture = ground().fixtures()[0].clone();
+            fixture.set_one_way(Some(normal));
+            let mut body = RigidBody::new(transform);
+            body.add_fixture(fixture);
+            body.set_body_type(BodyType::Static);
+            body
+        };
+        world.add_body(platform(Transform::identity(), Vec2d::new(0.0, 2.0)));
+        let mut ball = RigidBody::new(Transform::new(Vec2d::new(0.0, -2.0), Rotation::identity()));
+        ball.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.25).unwrap(), 1.0));
+        ball.set_linear_velocity(Vec2d::new(0.0, 10.0));
+        let ball = world.add_body(ball);
+
+        // the normal of the upside down ledge points down
+        let ceiling = Transform::new(Vec2d::new(30.0, 0.0), Rotation::new(PI));
+        world.add_body(platform(ceiling, Vec2d::new(0.0, 1.0)));
+        let mut stone = RigidBody::new(Transform::new(Vec2d::new(30.0, 2.0), Rotation::identity()));
+        stone.add_fixture(Fixture::new(Circle::new(Vec2d::zero(), 0.25).unwrap(), 1.0));
+        let stone = world.add_body(stone);
+
+        // the ball jumps through the ledge and lands on top of it
+        let mut max_height = f64::MIN;
+        for _ in 0..180 {
+            world.step(world.time_step());
+            max_height = max_height.max(world.body(ball).unwrap().position().y);
+        }
+        assert!(max_height > 2.5);
+        let body = world.body(ball).unwrap();
+        assert!((body.position().y - 0.75).abs() < 0.01);
+        assert!(body.linear_velocity().len() < 0.01);
+
+        // the stone falls through the ceiling
+        assert!(world.body(stone).unwrap().position().y < -5.0);
+    }
+}
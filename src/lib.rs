@@ -1,7 +1,10 @@
 #![crate_type = "lib"]
 #![crate_name = "rustics2d"]
+// constructors signal invalid geometry with `Err(())`
+#![allow(clippy::result_unit_err)]
 
 pub mod collision;
 pub mod common;
+pub mod dynamics;
 
 mod util;
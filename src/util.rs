@@ -1,7 +1,33 @@
 pub const TOLERANCE: f64 = 1e-10;
 
+/// A small length used as a collision and constraint tolerance.
+/// Chosen to be numerically significant but visually insignificant
+pub const LINEAR_SLOP: f64 = 0.005;
+
+/// A small angle in radians used as a constraint tolerance
+pub const ANGULAR_SLOP: f64 = 2.0 / 180.0 * ::std::f64::consts::PI;
+
+/// The maximum linear position correction of a constraint
+/// per position iteration, preventing overshoot
+pub const MAX_LINEAR_CORRECTION: f64 = 0.2;
+
+/// The maximum angular position correction of a constraint
+/// per position iteration, preventing overshoot
+pub const MAX_ANGULAR_CORRECTION: f64 = 8.0 / 180.0 * ::std::f64::consts::PI;
+
 /// Returns true if the floats are equal or within
 /// 1e-10 of each other
 pub fn feq(f1: f64, f2: f64) -> bool {
     (f1 - f2).abs() < TOLERANCE
 }
+
+/// Returns the angle wrapped into `[-pi, pi]`
+pub fn wrap_angle(angle: f64) -> f64 {
+    use std::f64::consts::PI;
+    let a = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if a == -PI {
+        PI
+    } else {
+        a
+    }
+}